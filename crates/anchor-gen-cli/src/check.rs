@@ -0,0 +1,41 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anchor_idl::{diff_idls, Compatibility};
+use anyhow::Result;
+use clap::Args;
+
+use crate::read_idl;
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Path to the previous version of the IDL.
+    old: PathBuf,
+    /// Path to the new version of the IDL.
+    new: PathBuf,
+    /// Only print breaking changes.
+    #[arg(long)]
+    breaking_only: bool,
+}
+
+pub fn run(args: CheckArgs) -> Result<ExitCode> {
+    let old = read_idl(&args.old)?;
+    let new = read_idl(&args.new)?;
+    let diff = diff_idls(&old, &new);
+
+    diff.changes
+        .iter()
+        .filter(|change| !args.breaking_only || change.compatibility == Compatibility::Breaking)
+        .for_each(|change| println!("{}", change));
+
+    let breaking = diff.breaking_changes().count();
+    if breaking > 0 {
+        eprintln!(
+            "{} breaking change(s) out of {} change(s)",
+            breaking,
+            diff.changes.len()
+        );
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
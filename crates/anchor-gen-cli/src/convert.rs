@@ -0,0 +1,100 @@
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use anchor_idl::{legacy_idl_to_new, Idl};
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+
+use crate::read_idl;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum IdlSpec {
+    /// The format of Anchor up to 0.29.
+    Legacy,
+    /// The specification introduced by Anchor 0.30.
+    New,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// Path to the IDL, in any format the other commands read.
+    idl: PathBuf,
+    /// Format to convert the IDL to.
+    #[arg(long, value_enum)]
+    to: IdlSpec,
+    /// Address of the program, if the IDL does not include it.
+    #[arg(long)]
+    address: Option<String>,
+    /// File to write the converted IDL to, instead of the standard output.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+pub fn run(args: ConvertArgs) -> Result<ExitCode> {
+    let mut idl = read_idl(&args.idl)?;
+    let converted = match args.to {
+        IdlSpec::Legacy => {
+            if let Some(address) = &args.address {
+                set_address(&mut idl, address)
+                    .with_context(|| format!("converting {}", args.idl.display()))?;
+            }
+            serde_json::to_value(&idl)?
+        }
+        IdlSpec::New => legacy_idl_to_new(&idl, args.address.as_deref())
+            .with_context(|| format!("converting {}", args.idl.display()))?,
+    };
+    let converted = serde_json::to_string_pretty(&converted)?;
+    match &args.out {
+        Some(out) => {
+            fs::write(out, converted + "\n")
+                .with_context(|| format!("writing {}", out.display()))?;
+        }
+        None => println!("{}", converted),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Sets the address of the program in the metadata of a legacy IDL.
+fn set_address(idl: &mut Idl, address: &str) -> Result<()> {
+    match idl
+        .metadata
+        .get_or_insert_with(|| json!({}))
+        .as_object_mut()
+    {
+        Some(metadata) => {
+            metadata.insert("address".to_string(), json!(address));
+            Ok(())
+        }
+        None => bail!("metadata: expected an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps";
+
+    fn idl(metadata: serde_json::Value) -> Idl {
+        serde_json::from_value(json!({
+            "version": "0.1.0",
+            "name": "counter",
+            "instructions": [],
+            "metadata": metadata
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_set_address() {
+        for metadata in [json!(null), json!({ "origin": "shank" })] {
+            let mut idl = idl(metadata);
+            set_address(&mut idl, ADDRESS).unwrap();
+            assert_eq!(idl.metadata.unwrap()["address"], ADDRESS);
+        }
+
+        let mut idl = idl(json!("counter"));
+        let error = set_address(&mut idl, ADDRESS).unwrap_err();
+        assert_eq!(error.to_string(), "metadata: expected an object");
+    }
+}
@@ -0,0 +1,205 @@
+use std::{fs, path::Path, path::PathBuf, process::ExitCode};
+
+use anchor_idl::dynamic::IdlCodec;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::json;
+
+use crate::read_idl;
+
+#[derive(Args)]
+pub struct DecodeArgs {
+    #[command(subcommand)]
+    command: DecodeCommand,
+}
+
+#[derive(Subcommand)]
+enum DecodeCommand {
+    /// Decodes the data of an account, identified by its discriminator.
+    Account {
+        #[command(flatten)]
+        input: DataArgs,
+        /// Account to decode the data as, required for programs whose accounts have no
+        /// discriminator.
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Decodes the data of an instruction, identified by its discriminator.
+    Ix {
+        #[command(flatten)]
+        input: DataArgs,
+    },
+}
+
+#[derive(Args)]
+struct DataArgs {
+    /// Path to the IDL of the program.
+    #[arg(long)]
+    idl: PathBuf,
+    /// The data, or a file containing it. Files may contain the raw data, the data as text in
+    /// the given encoding, or the output of `solana account --output json`.
+    #[arg(long)]
+    data: String,
+    /// Encoding of data given as text.
+    #[arg(long, value_enum, default_value_t = Encoding::Base64)]
+    encoding: Encoding,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Encoding {
+    Base64,
+    /// As in the instructions of `solana confirm --output json`.
+    Base58,
+}
+
+impl Encoding {
+    fn decode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Base64 => base64::decode(text).ok(),
+            Encoding::Base58 => bs58::decode(text).into_vec().ok(),
+        }
+    }
+}
+
+/// Reads data given on the command line, or from a file.
+fn read_data(data: &str, encoding: Encoding) -> Result<Vec<u8>> {
+    let path = Path::new(data);
+    if !path.is_file() {
+        return encoding
+            .decode(data.trim())
+            .with_context(|| format!("`{}` is neither a file nor encoded data", data));
+    }
+    let contents = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    // raw data is unlikely to be valid text in the encoding
+    if let Some(decoded) = std::str::from_utf8(&contents)
+        .ok()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .and_then(|text| encoding.decode(text))
+    {
+        return Ok(decoded);
+    }
+    anchor_idl::account_dump_data(&contents).with_context(|| format!("parsing {}", path.display()))
+}
+
+pub fn run(args: DecodeArgs) -> Result<ExitCode> {
+    let decoded = match &args.command {
+        DecodeCommand::Account { input, name } => {
+            let idl = read_idl(&input.idl)?;
+            let codec = IdlCodec::new(&idl);
+            let data = read_data(&input.data, input.encoding)?;
+            let (name, value) = match name {
+                Some(name) => (name.clone(), codec.decode_account_as(name, &data)?),
+                None => {
+                    let decoded = codec.decode_account(&data)?;
+                    (decoded.name, decoded.value)
+                }
+            };
+            json!({ "account": name, "data": value.to_json() })
+        }
+        DecodeCommand::Ix { input } => {
+            let idl = read_idl(&input.idl)?;
+            let codec = IdlCodec::new(&idl);
+            let data = read_data(&input.data, input.encoding)?;
+            let decoded = codec.decode_instruction(&data)?;
+            json!({ "instruction": decoded.name, "args": decoded.value.to_json() })
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&decoded)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_idl::dynamic::Value;
+
+    use super::*;
+
+    fn swap_data() -> Vec<u8> {
+        let idl = read_idl(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/whirlpools/idl.json"),
+        )
+        .unwrap();
+        let codec = IdlCodec::new(&idl);
+        let args = codec
+            .instruction_args_from_json(
+                "swap",
+                &json!({
+                    "amount": "1000",
+                    "otherAmountThreshold": "990",
+                    "sqrtPriceLimit": "4295048016",
+                    "amountSpecifiedIsInput": true,
+                    "aToB": false,
+                }),
+            )
+            .unwrap();
+        let data = codec.encode_instruction("swap", &args).unwrap();
+        let decoded = codec.decode_instruction(&data).unwrap();
+        assert_eq!(decoded.name, "swap");
+        assert!(matches!(decoded.value, Value::Struct(_)));
+        data
+    }
+
+    /// Writes `contents` to a temporary file named `name`, returning its path.
+    fn file(name: &str, contents: impl AsRef<[u8]>) -> String {
+        let path = std::env::temp_dir().join(format!("anchor-gen-cli-decode-{}", name));
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_inline_data() {
+        let data = swap_data();
+        let base64 = base64::encode(&data);
+        let base58 = bs58::encode(&data).into_string();
+        assert_eq!(read_data(&base64, Encoding::Base64).unwrap(), data);
+        assert_eq!(read_data(&base58, Encoding::Base58).unwrap(), data);
+        assert_eq!(
+            read_data(&format!(" {}\n", base64), Encoding::Base64).unwrap(),
+            data
+        );
+
+        let error = read_data("not base64!", Encoding::Base64).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`not base64!` is neither a file nor encoded data"
+        );
+        // `0` is not in the base58 alphabet
+        assert!(read_data("0x01", Encoding::Base58).is_err());
+    }
+
+    #[test]
+    fn test_data_files() {
+        let data = swap_data();
+        let base64 = file("base64.txt", format!("{}\n", base64::encode(&data)));
+        assert_eq!(read_data(&base64, Encoding::Base64).unwrap(), data);
+        let base58 = file("base58.txt", bs58::encode(&data).into_string());
+        assert_eq!(read_data(&base58, Encoding::Base58).unwrap(), data);
+
+        // raw data is not valid text in the encoding
+        let raw = file("raw.bin", &data);
+        assert_eq!(read_data(&raw, Encoding::Base64).unwrap(), data);
+        assert_eq!(read_data(&raw, Encoding::Base58).unwrap(), data);
+    }
+
+    #[test]
+    fn test_account_dump() {
+        let data = swap_data();
+        let dump = file(
+            "account.json",
+            json!({
+                "pubkey": "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc",
+                "account": { "data": [base64::encode(&data), "base64"] }
+            })
+            .to_string(),
+        );
+        assert_eq!(read_data(&dump, Encoding::Base58).unwrap(), data);
+
+        let invalid = file(
+            "invalid-account.json",
+            json!({ "account": { "data": ["", "base58"] } }).to_string(),
+        );
+        let error = read_data(&invalid, Encoding::Base64).unwrap_err();
+        assert!(error.to_string().starts_with("parsing "));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{fs, path::Path, path::PathBuf, process::ExitCode};
+
+use anchor_idl::{dynamic::IdlCodec, IdlAccountItem};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Subcommand};
+use serde_json::{json, Map, Value};
+
+use crate::read_idl;
+
+#[derive(Args)]
+pub struct EncodeArgs {
+    #[command(subcommand)]
+    command: EncodeCommand,
+}
+
+#[derive(Subcommand)]
+enum EncodeCommand {
+    /// Serializes an instruction, printing its data as base64 and, if accounts are given, its
+    /// account metas.
+    Ix {
+        /// Path to the IDL of the program.
+        #[arg(long)]
+        idl: PathBuf,
+        /// Name of the instruction, as in the IDL or in snake case.
+        #[arg(long)]
+        name: String,
+        /// Arguments of the instruction as a JSON object by argument name, or a file containing
+        /// it. Integers may be strings, and public keys are base58 strings.
+        #[arg(long)]
+        args: Option<String>,
+        /// Public keys of the accounts of the instruction as a JSON object by account name, or a
+        /// file containing it. Accounts nested in a group are in an object named after it.
+        #[arg(long)]
+        accounts: Option<String>,
+        /// The program id, if the IDL does not include it in `metadata.address`.
+        #[arg(long)]
+        program_id: Option<String>,
+    },
+}
+
+/// Reads JSON given on the command line, or from a file.
+fn read_json(json: &str) -> Result<Value> {
+    let path = Path::new(json);
+    if path.is_file() {
+        let contents = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&contents).with_context(|| format!("parsing {}", path.display()))
+    } else {
+        serde_json::from_str(json).with_context(|| format!("`{}` is neither a file nor JSON", json))
+    }
+}
+
+/// Account metas of an instruction, from the public keys of its accounts by name.
+///
+/// Optional accounts which are not given are replaced with the program id, as Anchor expects.
+fn account_metas(
+    items: &[IdlAccountItem],
+    keys: &Map<String, Value>,
+    program_id: Option<&str>,
+    path: &str,
+    metas: &mut Vec<Value>,
+) -> Result<()> {
+    let names = items
+        .iter()
+        .map(|item| match item {
+            IdlAccountItem::IdlAccount(account) => &account.name,
+            IdlAccountItem::IdlAccounts(group) => &group.name,
+        })
+        .collect::<Vec<_>>();
+    if let Some(unknown) = keys.keys().find(|key| !names.contains(key)) {
+        bail!("{}{}: unknown account", path, unknown);
+    }
+    for item in items {
+        match item {
+            IdlAccountItem::IdlAccount(account) => {
+                let name = format!("{}{}", path, account.name);
+                let (pubkey, is_writable, is_signer) = match keys.get(&account.name) {
+                    Some(Value::String(key)) => {
+                        check_pubkey(key)
+                            .with_context(|| format!("{}: invalid public key", name))?;
+                        (key.as_str(), account.is_mut, account.is_signer)
+                    }
+                    Some(_) => bail!("{}: expected a base58 public key", name),
+                    None if account.is_optional == Some(true) => {
+                        let program_id = program_id.ok_or_else(|| {
+                            anyhow!(
+                                "{}: optional accounts which are not given require the program id",
+                                name
+                            )
+                        })?;
+                        (program_id, false, false)
+                    }
+                    None => bail!("{}: missing account", name),
+                };
+                metas.push(json!({
+                    "name": name,
+                    "pubkey": pubkey,
+                    "isSigner": is_signer,
+                    "isWritable": is_writable,
+                }));
+            }
+            IdlAccountItem::IdlAccounts(group) => {
+                let empty = Map::new();
+                let keys = match keys.get(&group.name) {
+                    Some(Value::Object(keys)) => keys,
+                    Some(_) => bail!("{}{}: expected an object", path, group.name),
+                    None => &empty,
+                };
+                account_metas(
+                    &group.accounts,
+                    keys,
+                    program_id,
+                    &format!("{}{}.", path, group.name),
+                    metas,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_pubkey(key: &str) -> Result<()> {
+    let bytes = bs58::decode(key).into_vec()?;
+    if bytes.len() != 32 {
+        bail!("expected 32 bytes, found {}", bytes.len());
+    }
+    Ok(())
+}
+
+pub fn run(args: EncodeArgs) -> Result<ExitCode> {
+    let EncodeCommand::Ix {
+        idl: idl_path,
+        name,
+        args,
+        accounts,
+        program_id,
+    } = args.command;
+    let idl = read_idl(&idl_path)?;
+    let codec = IdlCodec::new(&idl);
+
+    let json_args = args.as_deref().map(read_json).transpose()?;
+    let ix_args =
+        codec.instruction_args_from_json(&name, json_args.as_ref().unwrap_or(&Value::Null))?;
+    let data = codec.encode_instruction(&name, &ix_args)?;
+
+    let program_id = program_id.or_else(|| {
+        idl.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("address"))
+            .and_then(|address| address.as_str())
+            .map(str::to_string)
+    });
+    let mut ix = Map::new();
+    if let Some(program_id) = &program_id {
+        check_pubkey(program_id).with_context(|| format!("invalid program id {}", program_id))?;
+        ix.insert("programId".to_string(), json!(program_id));
+    }
+    if let Some(accounts) = &accounts {
+        let keys = match read_json(accounts)? {
+            Value::Object(keys) => keys,
+            _ => bail!("the accounts must be a JSON object"),
+        };
+        let mut metas = vec![];
+        account_metas(
+            &codec.instruction(&name)?.accounts,
+            &keys,
+            program_id.as_deref(),
+            "",
+            &mut metas,
+        )?;
+        ix.insert("accounts".to_string(), Value::Array(metas));
+    }
+    ix.insert("data".to_string(), json!(base64::encode(&data)));
+    println!("{}", serde_json::to_string_pretty(&Value::Object(ix))?);
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_idl::Idl;
+
+    use super::*;
+
+    const PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+    fn example(name: &str) -> Idl {
+        read_idl(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join(format!("../../examples/{}/idl.json", name)),
+        )
+        .unwrap()
+    }
+
+    fn accounts(idl: &Idl, ix: &str) -> Vec<IdlAccountItem> {
+        IdlCodec::new(idl).instruction(ix).unwrap().accounts.clone()
+    }
+
+    /// Distinct public keys for each of `names`.
+    fn keys(names: &[&str]) -> Map<String, Value> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let key = bs58::encode([index as u8 + 1; 32]).into_string();
+                (name.to_string(), json!(key))
+            })
+            .collect()
+    }
+
+    const SWAP_ACCOUNTS: [&str; 11] = [
+        "tokenProgram",
+        "tokenAuthority",
+        "whirlpool",
+        "tokenOwnerAccountA",
+        "tokenVaultA",
+        "tokenOwnerAccountB",
+        "tokenVaultB",
+        "tickArray0",
+        "tickArray1",
+        "tickArray2",
+        "oracle",
+    ];
+
+    #[test]
+    fn test_read_json() {
+        assert_eq!(
+            read_json(r#"{ "amount": "1" }"#).unwrap(),
+            json!({ "amount": "1" })
+        );
+
+        let path = std::env::temp_dir().join("anchor-gen-cli-encode-args.json");
+        fs::write(&path, r#"{ "amount": 2 }"#).unwrap();
+        assert_eq!(
+            read_json(path.to_str().unwrap()).unwrap(),
+            json!({ "amount": 2 })
+        );
+
+        let error = read_json("missing.json").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`missing.json` is neither a file nor JSON"
+        );
+    }
+
+    #[test]
+    fn test_account_metas() {
+        let idl = example("whirlpools");
+        let mut metas = vec![];
+        account_metas(
+            &accounts(&idl, "swap"),
+            &keys(&SWAP_ACCOUNTS),
+            None,
+            "",
+            &mut metas,
+        )
+        .unwrap();
+        let names = metas
+            .iter()
+            .map(|meta| meta["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, SWAP_ACCOUNTS);
+        assert_eq!(
+            metas[1],
+            json!({
+                "name": "tokenAuthority",
+                "pubkey": bs58::encode([2; 32]).into_string(),
+                "isSigner": true,
+                "isWritable": false,
+            })
+        );
+        assert_eq!(metas[2]["isWritable"], true);
+    }
+
+    #[test]
+    fn test_invalid_accounts() {
+        let idl = example("whirlpools");
+        let items = accounts(&idl, "swap");
+        let error = |keys: &Map<String, Value>| {
+            account_metas(&items, keys, Some(PROGRAM_ID), "", &mut vec![])
+                .unwrap_err()
+                .to_string()
+        };
+
+        let mut missing = keys(&SWAP_ACCOUNTS);
+        missing.remove("oracle");
+        assert_eq!(error(&missing), "oracle: missing account");
+
+        let mut unknown = keys(&SWAP_ACCOUNTS);
+        unknown.insert("position".to_string(), json!(PROGRAM_ID));
+        assert_eq!(error(&unknown), "position: unknown account");
+
+        let mut invalid = keys(&SWAP_ACCOUNTS);
+        invalid.insert("whirlpool".to_string(), json!("not a key"));
+        assert_eq!(error(&invalid), "whirlpool: invalid public key");
+        invalid.insert(
+            "whirlpool".to_string(),
+            json!(bs58::encode([1; 31]).into_string()),
+        );
+        assert_eq!(error(&invalid), "whirlpool: invalid public key");
+        invalid.insert("whirlpool".to_string(), json!(1));
+        assert_eq!(error(&invalid), "whirlpool: expected a base58 public key");
+    }
+
+    #[test]
+    fn test_optional_account_defaults_to_program_id() {
+        let idl = example("whirlpools");
+        let mut items = accounts(&idl, "swap");
+        match items.last_mut().unwrap() {
+            IdlAccountItem::IdlAccount(oracle) => oracle.is_optional = Some(true),
+            IdlAccountItem::IdlAccounts(_) => unreachable!(),
+        }
+        let mut keys = keys(&SWAP_ACCOUNTS);
+        keys.remove("oracle");
+
+        let mut metas = vec![];
+        account_metas(&items, &keys, Some(PROGRAM_ID), "", &mut metas).unwrap();
+        assert_eq!(
+            metas[10],
+            json!({
+                "name": "oracle",
+                "pubkey": PROGRAM_ID,
+                "isSigner": false,
+                "isWritable": false,
+            })
+        );
+
+        let error = account_metas(&items, &keys, None, "", &mut vec![]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "oracle: optional accounts which are not given require the program id"
+        );
+    }
+
+    #[test]
+    fn test_nested_account_groups() {
+        let idl = example("marinade-cpi");
+        let items = accounts(&idl, "update_active");
+        let common = keys(&[
+            "state",
+            "stakeList",
+            "stakeAccount",
+            "stakeWithdrawAuthority",
+            "reservePda",
+            "msolMint",
+            "msolMintAuthority",
+            "treasuryMsolAccount",
+            "clock",
+            "stakeHistory",
+            "stakeProgram",
+            "tokenProgram",
+        ]);
+        let mut keys = keys(&["validatorList"]);
+        keys.insert("common".to_string(), Value::Object(common.clone()));
+
+        let mut metas = vec![];
+        account_metas(&items, &keys, None, "", &mut metas).unwrap();
+        assert_eq!(metas.len(), 13);
+        assert_eq!(metas[0]["name"], "common.state");
+        assert_eq!(metas[0]["pubkey"], common["state"]);
+        assert_eq!(metas[11]["name"], "common.tokenProgram");
+        assert_eq!(metas[12]["name"], "validatorList");
+
+        let mut unknown = common;
+        unknown.insert("oracle".to_string(), json!(PROGRAM_ID));
+        keys.insert("common".to_string(), Value::Object(unknown));
+        let error = account_metas(&items, &keys, None, "", &mut vec![]).unwrap_err();
+        assert_eq!(error.to_string(), "common.oracle: unknown account");
+
+        keys.insert("common".to_string(), json!(PROGRAM_ID));
+        let error = account_metas(&items, &keys, None, "", &mut vec![]).unwrap_err();
+        assert_eq!(error.to_string(), "common: expected an object");
+    }
+}
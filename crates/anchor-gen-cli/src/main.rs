@@ -0,0 +1,76 @@
+//! Command line tools for working with Anchor IDLs.
+
+use std::{ffi::OsStr, fs, path::Path, process::ExitCode};
+
+use anchor_idl::Idl;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+
+mod check;
+mod convert;
+mod decode;
+mod encode;
+mod workspace;
+
+#[derive(Parser)]
+#[command(name = "anchor-gen", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compares two versions of an IDL, exiting with a non-zero code on breaking changes.
+    Check(check::CheckArgs),
+    /// Converts an IDL to the legacy Anchor format or to the specification of Anchor 0.30.
+    Convert(convert::ConvertArgs),
+    /// Decodes the data of an account or an instruction, printing it as JSON.
+    Decode(decode::DecodeArgs),
+    /// Serializes an instruction from its arguments and accounts given as JSON.
+    Encode(encode::EncodeArgs),
+    /// Generates a CPI crate for every program of an Anchor workspace.
+    Workspace(workspace::WorkspaceArgs),
+    /// Prints the address of the on-chain IDL account of a program.
+    IdlAddress {
+        /// The program id.
+        program_id: String,
+    },
+}
+
+/// Reads and parses an IDL file, either an Anchor IDL in the legacy or 0.30 format or a Codama
+/// root node, or a dump of an on-chain IDL account if it ends with `.bin` or is in the format of
+/// `solana account --output json`.
+fn read_idl(path: &Path) -> Result<Idl> {
+    let contents = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let is_json_account = serde_json::from_slice::<serde_json::Value>(&contents)
+        .map(|value| value.get("account").is_some())
+        .unwrap_or(false);
+    if is_json_account || path.extension() == Some(OsStr::new("bin")) {
+        return anchor_idl::parse_idl_account_dump(&contents)
+            .with_context(|| format!("parsing {}", path.display()));
+    }
+    anchor_idl::parse_idl_json(&contents).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn main() -> Result<ExitCode> {
+    match Cli::parse().command {
+        Command::Check(args) => check::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Decode(args) => decode::run(args),
+        Command::Encode(args) => encode::run(args),
+        Command::Workspace(args) => workspace::run(args),
+        Command::IdlAddress { program_id } => {
+            let program_id: [u8; 32] = bs58::decode(&program_id)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .with_context(|| format!("invalid program id {}", program_id))?;
+            println!(
+                "{}",
+                bs58::encode(anchor_idl::idl_address(&program_id)).into_string()
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
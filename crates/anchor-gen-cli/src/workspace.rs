@@ -0,0 +1,39 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anchor_idl::AnchorWorkspace;
+use anyhow::Result;
+use clap::Args;
+
+#[derive(Args)]
+pub struct WorkspaceArgs {
+    /// Path to the `Anchor.toml` of the workspace.
+    #[arg(long, default_value = "Anchor.toml")]
+    anchor_toml: PathBuf,
+    /// Cluster whose program ids are used in `declare_id!`.
+    #[arg(long, default_value = "localnet")]
+    cluster: String,
+    /// Directory to write the crates to.
+    #[arg(long)]
+    out_dir: PathBuf,
+}
+
+pub fn run(args: WorkspaceArgs) -> Result<ExitCode> {
+    let workspace = AnchorWorkspace::load(&args.anchor_toml)?;
+    let generated = workspace.generate_crates(&args.cluster, &args.out_dir)?;
+    for crate_dir in &generated.crates {
+        println!("generated {}", crate_dir.display());
+    }
+    for mismatch in &generated.mismatches {
+        let groups = mismatch
+            .groups
+            .iter()
+            .map(|group| group.join(", "))
+            .collect::<Vec<_>>()
+            .join("] and [");
+        eprintln!(
+            "warning: `{}` has different layouts in [{}], generating it separately",
+            mismatch.name, groups
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,145 @@
+//! Runtime support for the `fuzz` module of generated crates.
+//!
+//! Passing the `arbitrary` option to `generate_cpi_interface!` implements
+//! [arbitrary::Arbitrary] and [proptest::arbitrary::Arbitrary] for the generated types, accounts
+//! and instruction arguments, and generates a Borsh round-trip test for each of them. The
+//! proptest strategies are derived from the `arbitrary` implementations, so that they generate
+//! the same values.
+//!
+//! # Examples
+//!
+//! ```
+//! anchor_gen::generate_cpi_interface!(
+//!     idl_path = "../../examples/govern-cpi/idl.json",
+//!     arbitrary,
+//! );
+//! declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+//!
+//! use anchor_gen::arbitrary::{
+//!     arbitrary::{Arbitrary, Unstructured},
+//!     proptest::{prelude::*, test_runner::TestRunner},
+//! };
+//!
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! let data = [7; 256];
+//! let proposal = Proposal::arbitrary(&mut Unstructured::new(&data))?;
+//! assert_eq!(proposal.governor, Pubkey::new_from_array([7; 32]));
+//!
+//! TestRunner::default().run(&any::<GovernanceParameters>(), |params| {
+//!     let bytes = params.try_to_vec().unwrap();
+//!     prop_assert_eq!(bytes.len(), GovernanceParameters::LEN);
+//!     Ok(())
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+pub use arbitrary;
+pub use proptest;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use proptest::{prelude::any, strategy::BoxedStrategy, strategy::Strategy};
+use solana_program::pubkey::Pubkey;
+
+/// Maximum number of bytes a proptest strategy derives a value from.
+///
+/// Values which need more bytes get default values for the rest of their fields.
+pub const MAX_STRATEGY_BYTES: usize = 4096;
+
+/// A value which can be a field of a generated type.
+///
+/// This is [Arbitrary] for the types of the fields of generated types, including those which do
+/// not implement it: public keys are generated from arbitrary bytes, and floats are never NaN,
+/// which Borsh refuses to serialize.
+pub trait ArbitraryField<'a>: Sized {
+    /// Generates an arbitrary value from raw data.
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self>;
+}
+
+macro_rules! impl_arbitrary_field {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> ArbitraryField<'a> for $ty {
+                fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+                    Arbitrary::arbitrary(u)
+                }
+            }
+        )*
+    };
+}
+
+impl_arbitrary_field!(bool, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, String);
+
+impl<'a> ArbitraryField<'a> for f32 {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        let value = f32::arbitrary(u)?;
+        Ok(if value.is_nan() { 0.0 } else { value })
+    }
+}
+
+impl<'a> ArbitraryField<'a> for f64 {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        let value = f64::arbitrary(u)?;
+        Ok(if value.is_nan() { 0.0 } else { value })
+    }
+}
+
+impl<'a> ArbitraryField<'a> for Pubkey {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Pubkey::new_from_array(Arbitrary::arbitrary(u)?))
+    }
+}
+
+impl<'a, T: ArbitraryField<'a>> ArbitraryField<'a> for Box<T> {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        T::arbitrary_field(u).map(Box::new)
+    }
+}
+
+impl<'a, T: ArbitraryField<'a>> ArbitraryField<'a> for Option<T> {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Some(T::arbitrary_field(u)?)
+        } else {
+            None
+        })
+    }
+}
+
+impl<'a, T: ArbitraryField<'a>> ArbitraryField<'a> for Vec<T> {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        // stops once the data runs out, since `bool`s then default to `false`
+        let mut elements = vec![];
+        while u.arbitrary()? {
+            elements.push(T::arbitrary_field(u)?);
+        }
+        Ok(elements)
+    }
+}
+
+impl<'a, T: ArbitraryField<'a>, const N: usize> ArbitraryField<'a> for [T; N] {
+    fn arbitrary_field(u: &mut Unstructured<'a>) -> Result<Self> {
+        let elements = (0..N)
+            .map(|_| T::arbitrary_field(u))
+            .collect::<Result<Vec<_>>>()?;
+        match elements.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+/// A proptest strategy generating values through their [Arbitrary] implementation, from up to
+/// [MAX_STRATEGY_BYTES] bytes of data. Shrinking the data shrinks the values.
+pub fn arbitrary_strategy<T>() -> BoxedStrategy<T>
+where
+    T: for<'a> Arbitrary<'a> + fmt::Debug + 'static,
+{
+    proptest::collection::vec(any::<u8>(), 0..=MAX_STRATEGY_BYTES)
+        .prop_filter_map("not enough data", |data| {
+            T::arbitrary(&mut Unstructured::new(&data)).ok()
+        })
+        .boxed()
+}
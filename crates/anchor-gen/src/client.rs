@@ -0,0 +1,389 @@
+//! Runtime support for the `client` module of generated crates.
+//!
+//! Generated `fetch_*` functions perform their RPC calls through the [RpcLike] trait,
+//! which is implemented for the nonblocking `solana-client` RPC client when the
+//! `solana-client` feature is enabled, and for [InMemoryRpc] for use in tests.
+//!
+//! # Examples
+//!
+//! ```
+//! anchor_gen::generate_cpi_interface!(
+//!     idl_path = "../../examples/whirlpools/idl.json",
+//!     client,
+//! );
+//! declare_id!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+//!
+//! use anchor_gen::client::{InMemoryRpc, RpcAccount};
+//!
+//! # fn main() -> Result<()> {
+//! let rpc = InMemoryRpc::new();
+//! let address = Pubkey::new_unique();
+//! let position = Position {
+//!     whirlpool: Pubkey::new_unique(),
+//!     liquidity: 1_000,
+//!     ..Default::default()
+//! };
+//! let mut data = vec![];
+//! position.try_serialize(&mut data)?;
+//! rpc.set_account(
+//!     address,
+//!     RpcAccount {
+//!         lamports: 1,
+//!         owner: ID,
+//!         data,
+//!     },
+//! );
+//!
+//! let fetched = futures::executor::block_on(client::fetch_position(&rpc, &address)).unwrap();
+//! assert_eq!(fetched.whirlpool, position.whirlpool);
+//! let all = futures::executor::block_on(client::get_all_position(&rpc)).unwrap();
+//! assert_eq!(all.len(), 1);
+//!
+//! let by_whirlpool = futures::executor::block_on(client::get_all_position_with_filters(
+//!     &rpc,
+//!     &[Position::filter_by_whirlpool(&position.whirlpool)],
+//! ))
+//! .unwrap();
+//! assert_eq!(by_whirlpool.len(), 1);
+//! let by_other_whirlpool = futures::executor::block_on(client::get_all_position_with_filters(
+//!     &rpc,
+//!     &[Position::filter_by_whirlpool(&Pubkey::new_unique())],
+//! ))
+//! .unwrap();
+//! assert!(by_other_whirlpool.is_empty());
+//! assert_eq!(Position::filter_by_liquidity(&1_000).offset, 72);
+//! #   Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fmt, sync::RwLock};
+
+use async_trait::async_trait;
+use solana_program::pubkey::Pubkey;
+
+/// An account as returned by the RPC.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RpcAccount {
+    /// Lamports held by the account.
+    pub lamports: u64,
+    /// Program which owns the account.
+    pub owner: Pubkey,
+    /// Raw account data.
+    pub data: Vec<u8>,
+}
+
+/// Filter matching accounts whose data contains `bytes` at `offset`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Memcmp {
+    /// Offset into the account data.
+    pub offset: usize,
+    /// Bytes which must be present at the offset.
+    pub bytes: Vec<u8>,
+}
+
+impl Memcmp {
+    /// Creates a new [Memcmp] filter.
+    pub fn new(offset: usize, bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            offset,
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Returns true if the account data matches this filter.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.get(self.offset..self.offset + self.bytes.len()) == Some(self.bytes.as_slice())
+    }
+}
+
+/// Error returned by generated client functions.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The RPC request failed.
+    Rpc(Box<dyn std::error::Error + Send + Sync>),
+    /// No account exists at the address.
+    AccountNotFound(Pubkey),
+    /// The account is not owned by the expected program.
+    InvalidOwner {
+        address: Pubkey,
+        owner: Pubkey,
+        expected: Pubkey,
+    },
+    /// The account data could not be decoded into the requested type.
+    Decode {
+        address: Pubkey,
+        error: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Rpc(error) => write!(f, "RPC request failed: {}", error),
+            ClientError::AccountNotFound(address) => write!(f, "account {} not found", address),
+            ClientError::InvalidOwner {
+                address,
+                owner,
+                expected,
+            } => write!(
+                f,
+                "account {} is owned by {}, expected {}",
+                address, owner, expected
+            ),
+            ClientError::Decode { address, error } => {
+                write!(f, "could not decode account {}: {}", address, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// The RPC methods used by generated clients.
+#[async_trait]
+pub trait RpcLike {
+    /// Fetches an account, returning `None` if it does not exist.
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<RpcAccount>, ClientError>;
+
+    /// Fetches several accounts, returning `None` for those which do not exist.
+    ///
+    /// Generated clients request at most [MAX_MULTIPLE_ACCOUNTS] accounts at a time, through
+    /// [get_multiple_accounts].
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<RpcAccount>>, ClientError>;
+
+    /// Fetches all accounts owned by a program which match every filter.
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Memcmp],
+    ) -> Result<Vec<(Pubkey, RpcAccount)>, ClientError>;
+}
+
+/// Maximum number of accounts RPC nodes return from a single `getMultipleAccounts` request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Fetches several accounts in requests of at most [MAX_MULTIPLE_ACCOUNTS] accounts, returning
+/// `None` for those which do not exist.
+pub async fn get_multiple_accounts(
+    rpc: &(impl RpcLike + ?Sized),
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<RpcAccount>>, ClientError> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(rpc.get_multiple_accounts(chunk).await?);
+    }
+    Ok(accounts)
+}
+
+/// An in-memory [RpcLike], useful for testing code which uses generated clients.
+#[derive(Debug, Default)]
+pub struct InMemoryRpc {
+    accounts: RwLock<BTreeMap<Pubkey, RpcAccount>>,
+}
+
+impl InMemoryRpc {
+    /// Creates an empty [InMemoryRpc].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces an account.
+    pub fn set_account(&self, address: Pubkey, account: RpcAccount) {
+        self.accounts.write().unwrap().insert(address, account);
+    }
+
+    /// Removes an account.
+    pub fn remove_account(&self, address: &Pubkey) -> Option<RpcAccount> {
+        self.accounts.write().unwrap().remove(address)
+    }
+}
+
+#[async_trait]
+impl RpcLike for InMemoryRpc {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<RpcAccount>, ClientError> {
+        Ok(self.accounts.read().unwrap().get(address).cloned())
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<RpcAccount>>, ClientError> {
+        let accounts = self.accounts.read().unwrap();
+        Ok(addresses
+            .iter()
+            .map(|address| accounts.get(address).cloned())
+            .collect())
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Memcmp],
+    ) -> Result<Vec<(Pubkey, RpcAccount)>, ClientError> {
+        Ok(self
+            .accounts
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, account)| {
+                account.owner == *program_id
+                    && filters.iter().all(|filter| filter.matches(&account.data))
+            })
+            .map(|(address, account)| (*address, account.clone()))
+            .collect())
+    }
+}
+
+#[cfg(feature = "solana-client")]
+mod rpc_client {
+    use async_trait::async_trait;
+    use solana_account_decoder::UiAccountEncoding;
+    use solana_client::{
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp as RpcMemcmp, RpcFilterType},
+    };
+    use solana_program::pubkey::Pubkey;
+
+    use super::{ClientError, Memcmp, RpcAccount, RpcLike};
+
+    fn to_rpc_account(account: solana_sdk::account::Account) -> RpcAccount {
+        RpcAccount {
+            lamports: account.lamports,
+            owner: account.owner,
+            data: account.data,
+        }
+    }
+
+    fn rpc_error(error: solana_client::client_error::ClientError) -> ClientError {
+        ClientError::Rpc(Box::new(error))
+    }
+
+    #[async_trait]
+    impl RpcLike for RpcClient {
+        async fn get_account(&self, address: &Pubkey) -> Result<Option<RpcAccount>, ClientError> {
+            let response = self
+                .get_account_with_commitment(address, self.commitment())
+                .await
+                .map_err(rpc_error)?;
+            Ok(response.value.map(to_rpc_account))
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            addresses: &[Pubkey],
+        ) -> Result<Vec<Option<RpcAccount>>, ClientError> {
+            let accounts = RpcClient::get_multiple_accounts(self, addresses)
+                .await
+                .map_err(rpc_error)?;
+            Ok(accounts
+                .into_iter()
+                .map(|account| account.map(to_rpc_account))
+                .collect())
+        }
+
+        async fn get_program_accounts(
+            &self,
+            program_id: &Pubkey,
+            filters: &[Memcmp],
+        ) -> Result<Vec<(Pubkey, RpcAccount)>, ClientError> {
+            let filters = filters
+                .iter()
+                .map(|filter| {
+                    RpcFilterType::Memcmp(RpcMemcmp::new_raw_bytes(
+                        filter.offset,
+                        filter.bytes.clone(),
+                    ))
+                })
+                .collect();
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let accounts = self
+                .get_program_accounts_with_config(program_id, config)
+                .await
+                .map_err(rpc_error)?;
+            Ok(accounts
+                .into_iter()
+                .map(|(address, account)| (address, to_rpc_account(account)))
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Records the number of accounts of every `getMultipleAccounts` request.
+    #[derive(Default)]
+    struct CountingRpc {
+        rpc: InMemoryRpc,
+        requests: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl RpcLike for CountingRpc {
+        async fn get_account(&self, address: &Pubkey) -> Result<Option<RpcAccount>, ClientError> {
+            self.rpc.get_account(address).await
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            addresses: &[Pubkey],
+        ) -> Result<Vec<Option<RpcAccount>>, ClientError> {
+            self.requests.lock().unwrap().push(addresses.len());
+            self.rpc.get_multiple_accounts(addresses).await
+        }
+
+        async fn get_program_accounts(
+            &self,
+            program_id: &Pubkey,
+            filters: &[Memcmp],
+        ) -> Result<Vec<(Pubkey, RpcAccount)>, ClientError> {
+            self.rpc.get_program_accounts(program_id, filters).await
+        }
+    }
+
+    #[test]
+    fn test_get_multiple_accounts_in_chunks() {
+        let rpc = CountingRpc::default();
+        let addresses = (0..250).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        for (lamports, address) in addresses.iter().enumerate().step_by(2) {
+            rpc.rpc.set_account(
+                *address,
+                RpcAccount {
+                    lamports: lamports as u64,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let accounts =
+            futures::executor::block_on(get_multiple_accounts(&rpc, &addresses)).unwrap();
+        assert_eq!(*rpc.requests.lock().unwrap(), [100, 100, 50]);
+        assert_eq!(accounts.len(), 250);
+        for (index, account) in accounts.iter().enumerate() {
+            let lamports = account.as_ref().map(|account| account.lamports as usize);
+            assert_eq!(lamports, (index % 2 == 0).then_some(index));
+        }
+    }
+
+    #[test]
+    fn test_get_no_accounts() {
+        let rpc = CountingRpc::default();
+        let accounts = futures::executor::block_on(get_multiple_accounts(&rpc, &[])).unwrap();
+        assert!(accounts.is_empty());
+        assert!(rpc.requests.lock().unwrap().is_empty());
+    }
+}
@@ -12,7 +12,29 @@
 //!
 //! This will generate a fully functional Rust CPI client for your IDL.
 //!
+//! # Fetching accounts
+//!
+//! Passing the `client` option to `generate_cpi_interface!` generates a `client` module with
+//! `fetch_<account>`, `fetch_multiple_<account>` and `get_all_<account>` functions. These require the
+//! `client` feature of this crate, and take any `anchor_gen::client::RpcLike`, which is implemented for
+//! the nonblocking `solana-client` RPC client behind the `solana-client` feature.
+//!
+//! # Fuzzing
+//!
+//! Passing the `arbitrary` option to `generate_cpi_interface!` generates a `fuzz` module
+//! implementing `arbitrary::Arbitrary` and `proptest::arbitrary::Arbitrary` for the generated types,
+//! accounts and instruction arguments, along with Borsh round-trip tests. These require the
+//! `arbitrary` feature of this crate, which re-exports both crates from `anchor_gen::arbitrary`.
+//!
 //! More examples can be found in the [examples/](https://github.com/saber-hq/anchor-gen/tree/master/examples) directory.
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "client")]
+pub mod client;
+
+#[doc(hidden)]
+pub use base64;
+
 pub use anchor_generate_cpi_crate::generate_cpi_crate;
 pub use anchor_generate_cpi_interface::generate_cpi_interface;
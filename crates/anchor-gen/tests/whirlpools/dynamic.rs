@@ -0,0 +1,94 @@
+//! The dynamic codec of `anchor_idl` agrees with the generated types.
+
+use anchor_gen::arbitrary::proptest::prelude::*;
+use anchor_idl::dynamic::IdlCodec;
+use anchor_lang::{prelude::*, Discriminator, InstructionData};
+
+use crate::{instruction, TickArray, Whirlpool};
+
+fn idl() -> anchor_idl::Idl {
+    serde_json::from_str(include_str!("../../../../examples/whirlpools/idl.json")).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn test_accounts_agree(whirlpool: Whirlpool) {
+        let idl = idl();
+        let codec = IdlCodec::new(&idl);
+        let mut data = vec![];
+        whirlpool.try_serialize(&mut data).unwrap();
+
+        let decoded = codec.decode_account(&data).unwrap();
+        prop_assert_eq!(&decoded.name, "Whirlpool");
+        prop_assert_eq!(codec.encode_account("Whirlpool", &decoded.value).unwrap(), data);
+    }
+
+    #[test]
+    fn test_zero_copy_accounts_agree(tick_array: TickArray) {
+        let idl = idl();
+        let codec = IdlCodec::new(&idl);
+        // packed zero copy accounts have the layout of their Borsh serialization
+        let mut data = TickArray::DISCRIMINATOR.to_vec();
+        data.extend(bytemuck::bytes_of(&tick_array));
+
+        let value = codec.decode_account_as("TickArray", &data).unwrap();
+        prop_assert_eq!(codec.encode_account("TickArray", &value).unwrap(), data);
+    }
+
+    #[test]
+    fn test_instructions_agree(swap: instruction::Swap) {
+        let idl = idl();
+        let codec = IdlCodec::new(&idl);
+        let data = swap.data();
+
+        let decoded = codec.decode_instruction(&data).unwrap();
+        prop_assert_eq!(&decoded.name, "swap");
+        prop_assert_eq!(codec.encode_instruction("swap", &decoded.value).unwrap(), data);
+    }
+}
+
+#[test]
+fn test_instruction_from_json() {
+    let idl = idl();
+    let codec = IdlCodec::new(&idl);
+    let args = codec
+        .instruction_args_from_json(
+            "swap",
+            &serde_json::json!({
+                "amount": 1_000_000,
+                "otherAmountThreshold": 0,
+                "sqrtPriceLimit": "4295048016",
+                "amountSpecifiedIsInput": true,
+                "aToB": false,
+            }),
+        )
+        .unwrap();
+    let expected = instruction::Swap {
+        amount: 1_000_000,
+        other_amount_threshold: 0,
+        sqrt_price_limit: 4295048016,
+        amount_specified_is_input: true,
+        a_to_b: false,
+    };
+    assert_eq!(
+        codec.encode_instruction("swap", &args).unwrap(),
+        expected.data()
+    );
+}
+
+#[test]
+fn test_public_keys_are_base58() {
+    let idl = idl();
+    let codec = IdlCodec::new(&idl);
+    let key = Pubkey::new_unique();
+    let value = codec
+        .decode(&anchor_idl::IdlType::PublicKey, &mut &key.to_bytes()[..])
+        .unwrap();
+    assert_eq!(value.to_json(), serde_json::json!(key.to_string()));
+    assert_eq!(
+        codec
+            .from_json(&anchor_idl::IdlType::PublicKey, &value.to_json())
+            .unwrap(),
+        value
+    );
+}
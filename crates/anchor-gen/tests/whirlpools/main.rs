@@ -0,0 +1,20 @@
+//! The Whirlpools program with the options the example leaves out: its client, a handler
+//! standing in for the program, and `Arbitrary` implementations, whose Borsh round trips are
+//! tested by the generated `fuzz` module.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+// legacy zero copy structs are `repr(packed)`
+#![allow(clippy::repr_packed_without_abi)]
+
+anchor_gen::generate_cpi_interface!(
+    idl_path = "../../examples/whirlpools/idl.json",
+    zero_copy_unsafe(TickArray, Tick),
+    packed_representation(TickArray, Tick),
+    client,
+    handler,
+    arbitrary,
+);
+declare_id!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+mod dynamic;
+mod mock;
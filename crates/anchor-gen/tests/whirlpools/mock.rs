@@ -0,0 +1,129 @@
+//! A mock registered as the handler stands in for the program.
+
+use std::sync::Mutex;
+
+use anchor_lang::{error::ErrorCode, prelude::*, InstructionData};
+
+use crate::{
+    entry,
+    handler::{set_handler, Handler},
+    instruction,
+    ix_accounts::InitializeConfig,
+    ID,
+};
+
+/// Configs initialized through the mock, with their fee authority and protocol fee rate.
+static CONFIGS: Mutex<Vec<(Pubkey, Pubkey, u16)>> = Mutex::new(vec![]);
+
+struct MockWhirlpools;
+
+impl Handler for MockWhirlpools {
+    fn initialize_config(
+        &self,
+        ctx: Context<InitializeConfig>,
+        fee_authority: Pubkey,
+        _collect_protocol_fees_authority: Pubkey,
+        _reward_emissions_super_authority: Pubkey,
+        default_protocol_fee_rate: u16,
+    ) -> Result<()> {
+        CONFIGS.lock().unwrap().push((
+            ctx.accounts.config.key(),
+            fee_authority,
+            default_protocol_fee_rate,
+        ));
+        Ok(())
+    }
+}
+
+/// Keys, signer and writable flags of the accounts of an instruction.
+fn execute(
+    accounts: &[(Pubkey, bool, bool)],
+    data: &[u8],
+) -> std::result::Result<(), ProgramError> {
+    set_handler(MockWhirlpools);
+    let owner = Pubkey::default();
+    let mut lamports = vec![0u64; accounts.len()];
+    let mut account_data = vec![vec![]; accounts.len()];
+    let infos = accounts
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(account_data.iter_mut())
+        .map(|(((key, is_signer, is_writable), lamports), data)| {
+            AccountInfo::new(
+                key,
+                *is_signer,
+                *is_writable,
+                lamports,
+                data,
+                &owner,
+                false,
+                0,
+            )
+        })
+        .collect::<Vec<_>>();
+    entry(&ID, &infos, data)
+}
+
+#[test]
+fn test_handler_is_executed() {
+    let config = Pubkey::new_unique();
+    let fee_authority = Pubkey::new_unique();
+    let data = instruction::InitializeConfig {
+        fee_authority,
+        collect_protocol_fees_authority: Pubkey::new_unique(),
+        reward_emissions_super_authority: Pubkey::new_unique(),
+        default_protocol_fee_rate: 300,
+    }
+    .data();
+    let accounts = [
+        (config, true, true),
+        (Pubkey::new_unique(), true, true),
+        (anchor_lang::system_program::ID, false, false),
+    ];
+    execute(&accounts, &data).unwrap();
+    assert!(CONFIGS
+        .lock()
+        .unwrap()
+        .contains(&(config, fee_authority, 300)));
+}
+
+#[test]
+fn test_handler_checks_accounts() {
+    let data = instruction::InitializeConfig {
+        fee_authority: Pubkey::new_unique(),
+        collect_protocol_fees_authority: Pubkey::new_unique(),
+        reward_emissions_super_authority: Pubkey::new_unique(),
+        default_protocol_fee_rate: 300,
+    }
+    .data();
+    // the config must sign
+    let accounts = [
+        (Pubkey::new_unique(), false, true),
+        (Pubkey::new_unique(), true, true),
+        (anchor_lang::system_program::ID, false, false),
+    ];
+    assert_eq!(
+        execute(&accounts, &data),
+        Err(ProgramError::Custom(ErrorCode::AccountNotSigner.into()))
+    );
+}
+
+#[test]
+fn test_unimplemented_instruction() {
+    let data = instruction::InitializeTickArray {
+        start_tick_index: 88,
+    }
+    .data();
+    let accounts = [
+        (Pubkey::new_unique(), false, false),
+        (Pubkey::new_unique(), true, true),
+        (Pubkey::new_unique(), false, true),
+        (anchor_lang::system_program::ID, false, false),
+    ];
+    assert_eq!(
+        execute(&accounts, &data),
+        Err(ProgramError::Custom(
+            ErrorCode::InstructionFallbackNotFound.into()
+        ))
+    );
+}
@@ -10,7 +10,8 @@
 //! declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
 //! ```
 //!
-//! This will generate a fully functional Rust CPI client for your IDL.
+//! This will generate a fully functional Rust CPI client for your IDL. The generated code refers
+//! to `anchor_gen` to parse events, so the crate must depend on `anchor-gen`.
 //!
 //! More examples can be found in the [examples/](https://github.com/saber-hq/anchor-gen/tree/master/examples) directory.
 
@@ -35,6 +36,7 @@ use syn::{parse_macro_input, LitStr};
 ///     voting_period: 0,
 ///     voting_delay: 0,
 /// };
+/// assert_eq!(GovernanceParameters::LEN, 32);
 /// #   Ok(())
 /// # }
 /// ```
@@ -42,8 +44,11 @@ use syn::{parse_macro_input, LitStr};
 pub fn generate_cpi_crate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let id_literal = parse_macro_input!(input as LitStr);
     let opts = GeneratorOptions {
-        idl_path: id_literal.value(),
+        idl_path: Some(id_literal.value()),
         ..Default::default()
     };
-    opts.to_generator().generate_cpi_interface().into()
+    match opts.to_generator() {
+        Ok(generator) => generator.generate_cpi_interface().into(),
+        Err(e) => e.write_errors().into(),
+    }
 }
@@ -10,7 +10,8 @@
 //! declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
 //! ```
 //!
-//! This will generate a fully functional Rust CPI client for your IDL.
+//! This will generate a fully functional Rust CPI client for your IDL. The generated code refers
+//! to `anchor_gen` to parse events, so the crate must depend on `anchor-gen`.
 //!
 //! More examples can be found in the [examples/](https://github.com/saber-hq/anchor-gen/tree/master/examples) directory.
 
@@ -23,7 +24,55 @@ use syn::parse_macro_input;
 ///
 /// # Arguments
 ///
-/// * `idl_path` - Path to a JSON IDL relative to the crate's the Cargo.toml.
+/// * `idl_path` - Path to a JSON IDL relative to the crate's the Cargo.toml, or else to the root
+///   of its workspace. JSON IDLs are either Anchor IDLs, in the legacy format or the one of
+///   Anchor 0.30, or Codama root nodes.
+/// * `idl_account_path` - Path to a dump of the program's on-chain IDL account, used instead of
+///   `idl_path`. Both raw account data and the output of `solana account --output json` are accepted.
+/// * `idl_json` - The JSON IDL itself, used instead of `idl_path`.
+/// * `idl_env` - Environment variable containing the path to a JSON IDL, used instead of `idl_path`.
+/// * `zero_copy`, `zero_copy_unsafe` - Structs and accounts to generate as zero copy. Safe zero
+///   copy types may only contain numbers, public keys, and arrays and structs of them.
+/// * `c_representation`, `transparent_representation`, `packed_representation` - Types to
+///   generate with the given `repr`. Packed types are `repr(C, packed)`, so that their fields keep
+///   the order of the IDL.
+/// * `align_representation` - Alignment of types, e.g. `align_representation(Foo = 8)`.
+/// * `int_representation` - Integer representation of enums, e.g. `int_representation(Side = "u8")`.
+/// * `extern_types` - Types to re-export from another crate instead of generating them, e.g.
+///   `extern_types(Name = "other_crate::typedefs::Name")`.
+/// * `placeholder_types` - Types referenced but not defined by the IDL, e.g. types from other
+///   crates, to generate as opaque bytes, e.g. `placeholder_types(Metadata = "[u8; 32]")`.
+/// * `include_instructions`, `exclude_instructions` - Instructions to generate or skip, by snake
+///   case name, e.g. `include_instructions(swap)`.
+/// * `include_accounts` - Accounts to generate.
+/// * `include_types` - Types to generate even if nothing generated references them. When any of
+///   the `include_*` or `exclude_*` options is specified, only the types referenced by the
+///   generated instructions, accounts and events are generated.
+/// * `module` - Module wrapping the generated items, e.g. `module = "governance"`. The `#[program]`
+///   module stays at the crate root, which Anchor requires, along with a glob import of the Anchor
+///   prelude, so that items of the crate such as a `Result` alias take precedence over it.
+/// * `program_module` - Name of the `#[program]` module. Defaults to the name of the program.
+/// * `typedefs_module`, `state_module`, `ix_accounts_module` - Names of the modules containing the
+///   types, accounts and instruction accounts. Default to `typedefs`, `state` and `ix_accounts`.
+/// * `glob_reexports` - Whether the types and accounts are publicly re-exported next to their
+///   modules. Defaults to `true`.
+/// * `type_naming`, `field_naming` - Naming conventions of types and fields, either `"preserve"` to
+///   keep the names of the IDL or `"rust"` to convert them to `PascalCase` and `snake_case`.
+///   Default to `"preserve"` for types and `"rust"` for fields. Accounts and events always keep
+///   their names, since their discriminators are derived from them.
+/// * `discriminators` - How instructions and accounts are discriminated, either `"anchor"` or
+///   `"shank"`. Defaults to `"shank"` for Shank IDLs, i.e. those whose `metadata.origin` is
+///   `"shank"`. Programs with Shank discriminators get `instruction`, `accounts` and `cpi` modules
+///   in place of the `#[program]` module, and their accounts are plain Borsh structs.
+/// * `client` - Generates a `client` module for fetching accounts over RPC.
+/// * `handler` - Generates a `handler` module whose `Handler` trait has a method for every
+///   instruction. The `#[program]` module delegates to the implementation registered with
+///   `handler::set_handler`, so that the program can stand in for the real one in tests.
+/// * `arbitrary` - Generates a `fuzz` module implementing `arbitrary::Arbitrary` and proptest's
+///   `Arbitrary` for the generated types, accounts and instruction arguments, with a Borsh
+///   round-trip test for each of them. Requires the `arbitrary` feature of `anchor-gen`.
+///
+/// The crate is rebuilt whenever the IDL file or environment variable changes.
 ///
 /// # Examples
 ///
@@ -40,6 +89,108 @@ use syn::parse_macro_input;
 /// #   Ok(())
 /// # }
 /// ```
+///
+/// Generating from a dump of the on-chain IDL account:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_account_path = "../../examples/govern-cpi/idl-account.json"
+/// );
+/// declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+/// # fn main() {
+/// assert_eq!(GovernanceParameters::LEN, 32);
+/// # }
+/// ```
+///
+/// Generating only some of the instructions and accounts of a program:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_path = "../../examples/whirlpools/idl.json",
+///     include_instructions(swap, open_position),
+///     include_accounts(Whirlpool),
+/// );
+/// declare_id!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+/// # fn main() {
+/// let _bumps = OpenPositionBumps { position_bump: 0 };
+/// # }
+/// ```
+///
+/// Generating into a module, without re-exporting its contents:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_path = "../../examples/govern-cpi/idl.json",
+///     module = "governance",
+///     glob_reexports = false,
+/// );
+/// declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+///
+/// /// Not ambiguous with the generated `Governor` account.
+/// pub struct Governor;
+/// # fn main() {
+/// assert_eq!(governance::typedefs::GovernanceParameters::LEN, 32);
+/// let _governor: Option<governance::state::Governor> = None;
+/// # }
+/// ```
+///
+/// Instructions with a `returns` type return it from their handlers, and the `returns` module
+/// decodes it from the return data once the instruction has been invoked:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_json = r#"{
+///         "version": "0.1.0",
+///         "name": "pricer",
+///         "instructions": [{
+///             "name": "getQuote",
+///             "accounts": [{ "name": "pool", "isMut": false, "isSigner": false }],
+///             "args": [{ "name": "amountIn", "type": "u64" }],
+///             "returns": { "defined": "Quote" }
+///         }],
+///         "types": [{
+///             "name": "Quote",
+///             "type": {
+///                 "kind": "struct",
+///                 "fields": [{ "name": "amountOut", "type": "u64" }]
+///             }
+///         }]
+///     }"#
+/// );
+/// declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+///
+/// /// Returns the output amount quoted by the last `cpi::get_quote`.
+/// pub fn quoted_amount() -> Result<u64> {
+///     let quote: Quote = returns::get_quote()?;
+///     Ok(quote.amount_out)
+/// }
+/// # fn main() {}
+/// ```
+///
+/// Generating from a Shank IDL of a native program:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_path = "../../examples/shank-cpi/idl.json"
+/// );
+/// declare_id!("FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ");
+/// # fn main() {
+/// assert_eq!(instruction::Increment { amount: 1 }.data(), [1, 1, 0, 0, 0, 0, 0, 0, 0]);
+/// # }
+/// ```
+///
+/// Generating from a Codama root node, which is converted to the same IDL:
+///
+/// ```
+/// anchor_generate_cpi_interface::generate_cpi_interface!(
+///     idl_path = "../../examples/shank-cpi/codama.json"
+/// );
+/// declare_id!("FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ");
+/// # fn main() {
+/// assert_eq!(instruction::Increment { amount: 1 }.data(), [1, 1, 0, 0, 0, 0, 0, 0, 0]);
+/// let _key = Key::Counter;
+/// # }
+/// ```
 #[proc_macro]
 pub fn generate_cpi_interface(input: proc_macro::TokenStream) -> TokenStream {
     let attr_args = parse_macro_input!(input as syn::AttributeArgs);
@@ -49,5 +200,8 @@ pub fn generate_cpi_interface(input: proc_macro::TokenStream) -> TokenStream {
             return TokenStream::from(e.write_errors());
         }
     };
-    parsed.to_generator().generate_cpi_interface().into()
+    match parsed.to_generator() {
+        Ok(generator) => generator.generate_cpi_interface().into(),
+        Err(e) => e.write_errors().into(),
+    }
 }
@@ -0,0 +1,153 @@
+//! Enums are generated with the payloads of their variants, and serialized as Anchor does.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+
+use anchor_lang::InstructionData;
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_json = r#"{
+        "version": "0.1.0",
+        "name": "orders",
+        "instructions": [{
+            "name": "place",
+            "accounts": [{ "name": "book", "isMut": true, "isSigner": false }],
+            "args": [
+                { "name": "order", "type": { "defined": "Order" } },
+                { "name": "condition", "type": { "defined": "Condition" } }
+            ]
+        }],
+        "types": [
+            {
+                "name": "Side",
+                "type": { "kind": "enum", "variants": [{ "name": "Buy" }, { "name": "Sell" }] }
+            },
+            {
+                "name": "Order",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Market" },
+                        {
+                            "name": "Limit",
+                            "fields": [
+                                { "name": "side", "type": { "defined": "Side" } },
+                                { "name": "price", "type": "u64" },
+                                { "name": "size", "type": "u64" }
+                            ]
+                        },
+                        { "name": "Pegged", "fields": ["i16", "u8"] }
+                    ]
+                }
+            },
+            {
+                "name": "Memo",
+                "type": {
+                    "kind": "enum",
+                    "variants": [{ "name": "Raw", "fields": ["bytes"] }, { "name": "Empty" }]
+                }
+            },
+            {
+                "name": "Condition",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Always" },
+                        { "name": "PriceAbove", "fields": ["u64"] },
+                        { "name": "All", "fields": [{ "vec": { "defined": "Condition" } }] }
+                    ]
+                }
+            },
+            {
+                "name": "Ticket",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "order", "type": { "defined": "Order" } },
+                        { "name": "memo", "type": { "option": { "defined": "Memo" } } }
+                    ]
+                }
+            }
+        ]
+    }"#
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+#[test]
+fn test_variant_payloads() {
+    let limit = Order::Limit {
+        side: Side::Sell,
+        price: 10,
+        size: 2,
+    };
+    let mut expected = vec![1, 1];
+    expected.extend(10u64.to_le_bytes());
+    expected.extend(2u64.to_le_bytes());
+    assert_eq!(limit.try_to_vec().unwrap(), expected);
+    assert!(matches!(
+        Order::try_from_slice(&expected).unwrap(),
+        Order::Limit {
+            side: Side::Sell,
+            price: 10,
+            size: 2
+        }
+    ));
+
+    let pegged = Order::Pegged(-1, 3);
+    assert_eq!(pegged.try_to_vec().unwrap(), [2, 0xff, 0xff, 3]);
+    assert!(matches!(
+        Order::try_from_slice(&[2, 0xff, 0xff, 3]).unwrap(),
+        Order::Pegged(-1, 3)
+    ));
+
+    assert_eq!(Memo::Raw(vec![7]).try_to_vec().unwrap(), [0, 1, 0, 0, 0, 7]);
+}
+
+#[test]
+fn test_default_is_first_variant() {
+    assert!(matches!(Order::default(), Order::Market));
+    assert!(matches!(Side::default(), Side::Buy));
+
+    let ticket = Ticket {
+        order: Order::Market,
+        memo: Some(Memo::Empty),
+    };
+    assert_eq!(ticket.try_to_vec().unwrap(), [0, 1, 1]);
+}
+
+#[test]
+fn test_recursive_enum() {
+    let condition = Condition::All(vec![Condition::PriceAbove(5), Condition::Always]);
+    let mut expected = vec![2, 2, 0, 0, 0, 1];
+    expected.extend(5u64.to_le_bytes());
+    expected.push(0);
+    assert_eq!(condition.try_to_vec().unwrap(), expected);
+
+    let decoded = Condition::try_from_slice(&expected).unwrap();
+    assert_eq!(decoded.try_to_vec().unwrap(), expected);
+
+    let error = Condition::try_from_slice(&[3]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_instruction_with_enum_args() {
+    let data = instruction::Place {
+        _order: Order::Pegged(1, 2),
+        _condition: Condition::Always,
+    }
+    .data();
+    assert_eq!(data[8..], [2, 1, 0, 2, 0]);
+}
+
+#[test]
+fn test_len_counts_largest_payload() {
+    assert_eq!(Side::LEN, 1);
+    let limit = Order::Limit {
+        side: Side::Buy,
+        price: 0,
+        size: 0,
+    };
+    assert_eq!(Order::LEN, limit.try_to_vec().unwrap().len());
+    assert_eq!(Order::LEN, 1 + 1 + 8 + 8);
+    assert!(Order::Pegged(0, 0).try_to_vec().unwrap().len() < Order::LEN);
+}
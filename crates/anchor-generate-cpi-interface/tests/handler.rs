@@ -0,0 +1,50 @@
+//! Instructions fail until a handler is registered. No test of this file registers one, since
+//! the handler is registered for the whole process.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+
+use anchor_lang::{error::ErrorCode, InstructionData};
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_json = r#"{
+        "version": "0.1.0",
+        "name": "counter",
+        "instructions": [{
+            "name": "increment",
+            "accounts": [{ "name": "counter", "isMut": true, "isSigner": false }],
+            "args": [{ "name": "amount", "type": "u64" }]
+        }]
+    }"#,
+    handler,
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+#[test]
+fn test_no_handler() {
+    assert_eq!(
+        handler::handler().err(),
+        Some(ErrorCode::InstructionFallbackNotFound.into())
+    );
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::default();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let counter = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let data = instruction::Increment { amount: 1 }.data();
+    assert_eq!(
+        entry(&ID, &[counter], &data),
+        Err(ProgramError::Custom(
+            ErrorCode::InstructionFallbackNotFound.into()
+        ))
+    );
+}
@@ -0,0 +1,37 @@
+//! Items generated into a module leave the crate root to the crate itself.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_path = "../../examples/govern-cpi/idl.json",
+    module = "governance",
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+/// Not in conflict with `anchor_lang::Result`.
+pub type Result<T> = std::result::Result<T, String>;
+
+/// Not in conflict with `anchor_lang::context::Context`.
+pub struct Context {
+    pub name: &'static str,
+}
+
+fn context() -> Result<Context> {
+    Ok(Context { name: "root" })
+}
+
+#[test]
+fn test_root_items() {
+    assert_eq!(context().unwrap().name, "root");
+}
+
+#[test]
+fn test_generated_items() {
+    use anchor_lang::Discriminator;
+
+    assert_eq!(governance::GovernanceParameters::LEN, 32);
+    assert_eq!(
+        instruction::SetGovernanceParams::DISCRIMINATOR,
+        anchor_idl::anchor_discriminator("global", "set_governance_params")
+    );
+}
@@ -0,0 +1,108 @@
+//! Fields of packed structs are read and written by value, since they may be misaligned.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+// legacy zero copy structs are `repr(packed)`
+#![allow(clippy::repr_packed_without_abi)]
+
+use std::ptr::addr_of;
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_json = r#"{
+        "version": "0.1.0",
+        "name": "vaults",
+        "instructions": [{
+            "name": "deposit",
+            "accounts": [{ "name": "vault", "isMut": true, "isSigner": false }],
+            "args": [{ "name": "amount", "type": "u64" }]
+        }],
+        "accounts": [{
+            "name": "Vault",
+            "type": {
+                "kind": "struct",
+                "fields": [
+                    { "name": "bump", "type": "u8" },
+                    { "name": "amount", "type": "u64" },
+                    { "name": "owner", "type": "publicKey" },
+                    { "name": "history", "type": { "array": ["u64", 3] } },
+                    { "name": "checkpoints", "type": { "array": [{ "defined": "Checkpoint" }, 2] } }
+                ]
+            }
+        }],
+        "types": [{
+            "name": "Checkpoint",
+            "type": {
+                "kind": "struct",
+                "fields": [{ "name": "slot", "type": "u64" }, { "name": "amount", "type": "u64" }]
+            }
+        }]
+    }"#,
+    zero_copy_unsafe(Vault, Checkpoint),
+    packed_representation(Vault),
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+fn vault() -> Vault {
+    Vault {
+        bump: 1,
+        amount: 2,
+        owner: Pubkey::default(),
+        history: [0; 3],
+        checkpoints: [Checkpoint::default(); 2],
+    }
+}
+
+#[test]
+fn test_misaligned_fields() {
+    let vault = vault();
+    let base = &vault as *const Vault as usize;
+    assert_eq!(addr_of!(vault.amount) as usize - base, 1);
+    assert_eq!(addr_of!(vault.owner) as usize - base, 9);
+    assert_eq!(addr_of!(vault.history) as usize - base, 41);
+    assert_eq!(addr_of!(vault.checkpoints) as usize - base, 65);
+    assert_eq!(std::mem::size_of::<Vault>(), 1 + 8 + 32 + 8 * 3 + 16 * 2);
+}
+
+#[test]
+fn test_accessors() {
+    let mut vault = vault();
+    assert_eq!(vault.amount(), 2);
+    vault.set_amount(u64::MAX - 1);
+    assert_eq!(vault.amount(), u64::MAX - 1);
+    assert_eq!(vault.bump(), 1);
+
+    let owner = Pubkey::new_unique();
+    vault.set_owner(owner);
+    assert_eq!(vault.owner(), owner);
+
+    vault.set_history_at(2, 7);
+    assert_eq!(vault.history_at(2), 7);
+    assert_eq!(vault.history(), [0, 0, 7]);
+    vault.set_history([1, 2, 3]);
+    assert_eq!(vault.history_at(0), 1);
+
+    let bytes = unsafe {
+        std::slice::from_raw_parts(
+            &vault as *const Vault as *const u8,
+            std::mem::size_of::<Vault>(),
+        )
+    };
+    assert_eq!(bytes[1..9], (u64::MAX - 1).to_le_bytes());
+    assert_eq!(bytes[9..41], owner.to_bytes());
+}
+
+#[test]
+fn test_defined_element_accessors() {
+    let mut vault = vault();
+    vault.set_checkpoints_at(
+        1,
+        Checkpoint {
+            slot: 42,
+            amount: 7,
+        },
+    );
+    // legacy zero copy structs are packed as well
+    let Checkpoint { slot, amount } = vault.checkpoints_at(1);
+    assert_eq!((slot, amount), (42, 7));
+    let Checkpoint { slot, .. } = vault.checkpoints()[0];
+    assert_eq!(slot, 0);
+}
@@ -0,0 +1,98 @@
+//! Values returned by instructions are decoded from the return data of the program.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+
+use std::sync::Mutex;
+
+use anchor_lang::{
+    error::ErrorCode,
+    solana_program::program_stubs::{set_syscall_stubs, SyscallStubs},
+};
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_json = r#"{
+        "version": "0.1.0",
+        "name": "quoter",
+        "instructions": [
+            {
+                "name": "quote",
+                "accounts": [{ "name": "pool", "isMut": false, "isSigner": false }],
+                "args": [{ "name": "amount", "type": "u64" }],
+                "returns": { "defined": "Estimate" }
+            },
+            {
+                "name": "fee",
+                "accounts": [{ "name": "pool", "isMut": false, "isSigner": false }],
+                "args": [],
+                "returns": "u16"
+            }
+        ],
+        "types": [{
+            "name": "Estimate",
+            "type": {
+                "kind": "struct",
+                "fields": [
+                    { "name": "amountOut", "type": "u64" },
+                    { "name": "fee", "type": "u16" }
+                ]
+            }
+        }]
+    }"#,
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+/// Return data of the last instruction invoked, as the runtime would report it.
+static RETURN_DATA: Mutex<Option<(Pubkey, Vec<u8>)>> = Mutex::new(None);
+
+/// Tests set the return data, which is global to the process, one at a time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+struct ReturnDataStubs;
+
+impl SyscallStubs for ReturnDataStubs {
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.lock().unwrap().clone()
+    }
+}
+
+fn with_return_data<T>(return_data: Option<(Pubkey, Vec<u8>)>, f: impl FnOnce() -> T) -> T {
+    let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    set_syscall_stubs(Box::new(ReturnDataStubs));
+    *RETURN_DATA.lock().unwrap() = return_data;
+    f()
+}
+
+#[test]
+fn test_decode() {
+    let estimate = Estimate {
+        amount_out: 990,
+        fee: 10,
+    };
+    let decoded = with_return_data(Some((ID, estimate.try_to_vec().unwrap())), returns::quote);
+    let decoded = decoded.unwrap();
+    assert_eq!(decoded.amount_out, 990);
+    assert_eq!(decoded.fee, 10);
+
+    let fee = with_return_data(Some((ID, 30u16.to_le_bytes().to_vec())), returns::fee);
+    assert_eq!(fee.unwrap(), 30);
+}
+
+#[test]
+fn test_other_program() {
+    let other = Pubkey::new_unique();
+    let error =
+        with_return_data(Some((other, 30u16.to_le_bytes().to_vec())), returns::fee).unwrap_err();
+    assert_eq!(error, ErrorCode::InvalidProgramId.into());
+}
+
+#[test]
+fn test_missing_return_data() {
+    let error = with_return_data(None, returns::fee).unwrap_err();
+    assert_eq!(error, ErrorCode::InstructionDidNotDeserialize.into());
+}
+
+#[test]
+fn test_invalid_return_data() {
+    let error = with_return_data(Some((ID, vec![1])), returns::quote).unwrap_err();
+    assert_eq!(error, ErrorCode::InstructionDidNotDeserialize.into());
+}
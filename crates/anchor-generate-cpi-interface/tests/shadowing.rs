@@ -0,0 +1,20 @@
+//! Items of the crate shadow the generated items re-exported at its root.
+#![allow(unknown_lints)]
+#![allow(unexpected_cfgs)]
+
+anchor_generate_cpi_interface::generate_cpi_interface!(
+    idl_path = "../../examples/govern-cpi/idl.json",
+);
+declare_id!("GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps");
+
+/// Shadows the `Governor` account of the IDL.
+pub struct Governor;
+
+#[test]
+fn test_shadowed_items() {
+    let _: Governor = Governor;
+    let governor = state::Governor::default();
+    assert_eq!(governor.proposal_count, 0);
+    // the other accounts are still re-exported
+    let _: Proposal = Proposal::default();
+}
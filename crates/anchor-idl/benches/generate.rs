@@ -0,0 +1,124 @@
+//! Measures code generation over large IDLs.
+//!
+//! Run with `cargo bench -p anchor-idl`. Generation should grow roughly linearly with the
+//! number of types, however deeply they are nested.
+
+use std::time::{Duration, Instant};
+
+use anchor_idl::GeneratorOptions;
+use serde_json::{json, Value};
+
+/// An IDL of `count` structs, each containing the previous one, so that types are nested up to
+/// `count` deep, along with accounts, instructions and events referencing them.
+fn nested_idl(count: usize) -> String {
+    let name = |index: usize| format!("Type{}", index);
+    let types = (0..count)
+        .map(|index| {
+            let mut fields = vec![
+                json!({ "name": "value", "type": "u64" }),
+                json!({ "name": "key", "type": "publicKey" }),
+                json!({ "name": "flags", "type": { "array": ["u8", 4] } }),
+            ];
+            if index > 0 {
+                fields.push(json!({ "name": "previous", "type": { "defined": name(index - 1) } }));
+                fields.push(json!({
+                    "name": "maybePrevious",
+                    "type": { "option": { "defined": name(index - 1) } }
+                }));
+                fields.push(json!({
+                    "name": "halves",
+                    "type": { "vec": { "defined": name(index / 2) } }
+                }));
+            }
+            json!({ "name": name(index), "type": { "kind": "struct", "fields": fields } })
+        })
+        .collect::<Vec<_>>();
+    let accounts = (0..count / 10)
+        .map(|index| {
+            json!({
+                "name": format!("Account{}", index),
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "authority", "type": "publicKey" },
+                        { "name": "data", "type": { "defined": name(index * 10) } }
+                    ]
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let instructions = (0..count / 10)
+        .map(|index| {
+            json!({
+                "name": format!("instruction{}", index),
+                "accounts": [{ "name": "authority", "isMut": false, "isSigner": true }],
+                "args": [{ "name": "data", "type": { "defined": name(index * 10) } }]
+            })
+        })
+        .collect::<Vec<_>>();
+    let events = (0..count / 100)
+        .map(|index| {
+            json!({
+                "name": format!("Event{}", index),
+                "fields": [{ "name": "data", "type": { "defined": name(index * 100) }, "index": false }]
+            })
+        })
+        .collect::<Vec<_>>();
+    let idl: Value = json!({
+        "version": "0.1.0",
+        "name": "nested",
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "events": events
+    });
+    idl.to_string()
+}
+
+/// Runs `f` until `budget` is spent, and prints its mean duration.
+fn bench(name: &str, budget: Duration, mut f: impl FnMut()) {
+    let start = Instant::now();
+    let mut iterations = 0;
+    while iterations == 0 || start.elapsed() < budget {
+        f();
+        iterations += 1;
+    }
+    println!(
+        "{:<40} {:>12.3?} ({} iterations)",
+        name,
+        start.elapsed() / iterations,
+        iterations
+    );
+}
+
+fn bench_idl(name: &str, idl_json: String) {
+    let options = GeneratorOptions {
+        idl_json: Some(idl_json),
+        ..Default::default()
+    };
+    bench(
+        &format!("{}: to_generator", name),
+        Duration::from_secs(2),
+        || {
+            options.to_generator().unwrap();
+        },
+    );
+    let generator = options.to_generator().unwrap();
+    bench(
+        &format!("{}: generate_cpi_interface", name),
+        Duration::from_secs(2),
+        || {
+            generator.generate_cpi_interface();
+        },
+    );
+}
+
+fn main() {
+    bench_idl(
+        "whirlpools",
+        include_str!("../../../examples/whirlpools/idl.json").to_string(),
+    );
+    for count in [100, 500, 2000] {
+        bench_idl(&format!("nested, {} types", count), nested_idl(count));
+    }
+}
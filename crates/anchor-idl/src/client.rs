@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::IdlTypeDefinition;
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{generate_account_filters, DiscriminatorStrategy, StructOpts, TypeTable};
+
+/// Generates the `fetch_*` functions of a single account.
+pub fn generate_account_fetchers(
+    account: &IdlTypeDefinition,
+    discriminators: &DiscriminatorStrategy,
+) -> TokenStream {
+    let struct_name = format_ident!("{}", account.name);
+    let snake_name = account.name.to_snake_case();
+    let fetch_name = format_ident!("fetch_{}", snake_name);
+    let fetch_multiple_name = format_ident!("fetch_multiple_{}", snake_name);
+    let get_all_name = format_ident!("get_all_{}", snake_name);
+    let get_all_with_filters_name = format_ident!("get_all_{}_with_filters", snake_name);
+
+    let fetch_doc = format!(" Fetches and decodes the [{}] at `address`.", account.name);
+    let fetch_multiple_doc = format!(
+        " Fetches and decodes the [{}] accounts at `addresses`, returning `None` for those which do not exist.",
+        account.name
+    );
+    let get_all_doc = format!(
+        " Fetches and decodes every [{}] account of the program.",
+        account.name
+    );
+    let get_all_with_filters_doc = format!(
+        " Fetches and decodes every [{}] account of the program which matches all of `filters`.",
+        account.name
+    );
+    let get_all_with_filters = match discriminators {
+        DiscriminatorStrategy::Anchor => quote! {
+            let filters = std::iter::once(#struct_name::discriminator_filter())
+                .chain(filters.iter().cloned())
+                .collect::<Vec<_>>();
+            rpc.get_program_accounts(&ID, &filters)
+                .await?
+                .iter()
+                .map(|(address, account)| Ok((*address, decode(address, account)?)))
+                .collect()
+        },
+        // without a discriminator, accounts of other types can only be told apart by
+        // failing to decode
+        DiscriminatorStrategy::Shank { .. } => quote! {
+            Ok(rpc
+                .get_program_accounts(&ID, filters)
+                .await?
+                .iter()
+                .filter_map(|(address, account)| Some((*address, decode(address, account).ok()?)))
+                .collect())
+        },
+    };
+
+    quote! {
+        #[doc = #fetch_doc]
+        pub async fn #fetch_name(
+            rpc: &impl RpcLike,
+            address: &Pubkey,
+        ) -> std::result::Result<#struct_name, ClientError> {
+            let account = rpc
+                .get_account(address)
+                .await?
+                .ok_or(ClientError::AccountNotFound(*address))?;
+            decode(address, &account)
+        }
+
+        #[doc = #fetch_multiple_doc]
+        pub async fn #fetch_multiple_name(
+            rpc: &impl RpcLike,
+            addresses: &[Pubkey],
+        ) -> std::result::Result<Vec<Option<#struct_name>>, ClientError> {
+            ::anchor_gen::client::get_multiple_accounts(rpc, addresses)
+                .await?
+                .iter()
+                .zip(addresses)
+                .map(|(account, address)| {
+                    account
+                        .as_ref()
+                        .map(|account| decode(address, account))
+                        .transpose()
+                })
+                .collect()
+        }
+
+        #[doc = #get_all_doc]
+        pub async fn #get_all_name(
+            rpc: &impl RpcLike,
+        ) -> std::result::Result<Vec<(Pubkey, #struct_name)>, ClientError> {
+            #get_all_with_filters_name(rpc, &[]).await
+        }
+
+        #[doc = #get_all_with_filters_doc]
+        pub async fn #get_all_with_filters_name(
+            rpc: &impl RpcLike,
+            filters: &[Memcmp],
+        ) -> std::result::Result<Vec<(Pubkey, #struct_name)>, ClientError> {
+            #get_all_with_filters
+        }
+    }
+}
+
+/// Generates the `client` module, which fetches accounts through an `anchor_gen::client::RpcLike`
+/// and builds filters for `getProgramAccounts`.
+pub fn generate_client(
+    types: &TypeTable,
+    account_defs: &[IdlTypeDefinition],
+    struct_opts: &BTreeMap<String, StructOpts>,
+    discriminators: &DiscriminatorStrategy,
+) -> TokenStream {
+    let fetchers = account_defs
+        .iter()
+        .map(|account| generate_account_fetchers(account, discriminators));
+    let filters = account_defs
+        .iter()
+        .map(|account| generate_account_filters(types, account, struct_opts, discriminators));
+    let use_discriminator = match discriminators {
+        DiscriminatorStrategy::Anchor => quote! {
+            use anchor_lang::Discriminator;
+        },
+        DiscriminatorStrategy::Shank { .. } => quote! {},
+    };
+    quote! {
+        pub mod client {
+            //! Functions for fetching and decoding accounts over RPC.
+            use super::*;
+            #use_discriminator
+            use ::anchor_gen::client::{ClientError, Memcmp, RpcAccount, RpcLike};
+
+            /// Decodes an account, checking that it is owned by this program.
+            fn decode<T: AccountDeserialize>(
+                address: &Pubkey,
+                account: &RpcAccount,
+            ) -> std::result::Result<T, ClientError> {
+                if account.owner != ID {
+                    return Err(ClientError::InvalidOwner {
+                        address: *address,
+                        owner: account.owner,
+                        expected: ID,
+                    });
+                }
+                T::try_deserialize(&mut account.data.as_slice()).map_err(|error| {
+                    ClientError::Decode {
+                        address: *address,
+                        error: Box::new(error),
+                    }
+                })
+            }
+
+            #(#filters)*
+
+            #(#fetchers)*
+        }
+    }
+}
@@ -0,0 +1,1093 @@
+use std::{collections::BTreeMap, fmt};
+
+use anchor_syn::idl::{
+    EnumFields, Idl, IdlAccount, IdlAccountItem, IdlEnumVariant, IdlErrorCode, IdlField,
+    IdlInstruction, IdlPda, IdlSeed, IdlSeedAccount, IdlSeedArg, IdlSeedConst, IdlType,
+    IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+use heck::{ToPascalCase, ToSnakeCase};
+use serde_json::{json, Map, Value};
+
+use crate::DiscriminatorStrategy;
+
+/// An error converting a Codama root node to an [Idl].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodamaError {
+    /// Path of the offending node, e.g. `program.instructions[0].arguments[1].type`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for CodamaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for CodamaError {}
+
+fn error(path: &str, message: impl Into<String>) -> CodamaError {
+    CodamaError {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Whether a JSON document is a Codama root node rather than an Anchor IDL.
+pub fn is_codama_root(value: &Value) -> bool {
+    value.get("kind").and_then(Value::as_str) == Some("rootNode")
+}
+
+/// Converts a Codama root node to an [Idl], from its program's accounts, instructions, defined
+/// types, errors and PDAs. Additional programs are ignored.
+///
+/// Programs whose `origin` is `"anchor"` keep Anchor discriminators, while every other program is
+/// read as a native program with Shank discriminators, see [DiscriminatorStrategy]. The
+/// discriminators of instructions and accounts are checked against the strategy and removed from
+/// their arguments and fields, since they are generated from it. Defined types which are neither
+/// structs nor enums are inlined where they are used.
+///
+/// ```
+/// let root: serde_json::Value = serde_json::from_str(include_str!(
+///     "../../../examples/shank-cpi/codama.json"
+/// )).unwrap();
+/// let idl = anchor_idl::codama_to_idl(&root).unwrap();
+/// let expected: anchor_idl::Idl = serde_json::from_str(include_str!(
+///     "../../../examples/shank-cpi/idl.json"
+/// )).unwrap();
+/// assert_eq!(idl.name, expected.name);
+/// // Codama names instructions in camel case, and Shank in pascal case
+/// for (ix, expected) in idl.instructions.iter().zip(&expected.instructions) {
+///     assert!(ix.name.eq_ignore_ascii_case(&expected.name));
+///     assert_eq!(ix.accounts, expected.accounts);
+///     assert_eq!(ix.args, expected.args);
+/// }
+/// assert_eq!(idl.accounts, expected.accounts);
+/// assert_eq!(idl.types, expected.types);
+/// assert_eq!(idl.errors, expected.errors);
+/// assert!(matches!(
+///     anchor_idl::DiscriminatorStrategy::detect(&idl),
+///     anchor_idl::DiscriminatorStrategy::Shank { .. }
+/// ));
+/// ```
+///
+/// Instruction accounts defaulting to a PDA whose seeds are constants, accounts or arguments
+/// keep their seeds:
+///
+/// ```
+/// # use anchor_idl::{IdlAccountItem, IdlSeed, IdlType};
+/// let root: serde_json::Value = serde_json::from_str(r#"{
+///     "kind": "rootNode",
+///     "program": {
+///         "kind": "programNode",
+///         "name": "vault",
+///         "version": "0.1.0",
+///         "instructions": [{
+///             "kind": "instructionNode",
+///             "name": "deposit",
+///             "accounts": [
+///                 { "kind": "instructionAccountNode", "name": "owner", "isWritable": false, "isSigner": true },
+///                 {
+///                     "kind": "instructionAccountNode",
+///                     "name": "vault",
+///                     "isWritable": true,
+///                     "isSigner": false,
+///                     "defaultValue": {
+///                         "kind": "pdaValueNode",
+///                         "pda": { "kind": "pdaLinkNode", "name": "vault" },
+///                         "seeds": [{
+///                             "kind": "pdaSeedValueNode",
+///                             "name": "owner",
+///                             "value": { "kind": "accountValueNode", "name": "owner" }
+///                         }]
+///                     }
+///                 }
+///             ],
+///             "arguments": []
+///         }],
+///         "pdas": [{
+///             "kind": "pdaNode",
+///             "name": "vault",
+///             "seeds": [
+///                 {
+///                     "kind": "constantPdaSeedNode",
+///                     "type": { "kind": "stringTypeNode", "encoding": "utf8" },
+///                     "value": { "kind": "stringValueNode", "string": "vault" }
+///                 },
+///                 { "kind": "variablePdaSeedNode", "name": "owner", "type": { "kind": "publicKeyTypeNode" } }
+///             ]
+///         }]
+///     }
+/// }"#).unwrap();
+/// let idl = anchor_idl::codama_to_idl(&root).unwrap();
+/// let seeds = match &idl.instructions[0].accounts[1] {
+///     IdlAccountItem::IdlAccount(account) => &account.pda.as_ref().unwrap().seeds,
+///     _ => unreachable!(),
+/// };
+/// assert!(matches!(&seeds[0], IdlSeed::Const(seed) if seed.value == "vault"));
+/// assert!(matches!(
+///     &seeds[1],
+///     IdlSeed::Account(seed) if seed.path == "owner" && seed.ty == IdlType::PublicKey
+/// ));
+/// ```
+pub fn codama_to_idl(root: &Value) -> Result<Idl, CodamaError> {
+    if !is_codama_root(root) {
+        return Err(error("root", "expected a `rootNode`"));
+    }
+    let program = field(root, "root", "program")?;
+    let path = "program";
+    let discriminators = match program.get("origin").and_then(Value::as_str) {
+        Some("anchor") => DiscriminatorStrategy::Anchor,
+        _ => DiscriminatorStrategy::Shank {
+            discriminants: BTreeMap::new(),
+        },
+    };
+    let mut converter = Converter::new(program)?;
+
+    let mut types = vec![];
+    for (index, def) in list(program, "definedTypes").iter().enumerate() {
+        let def_path = format!("{}.definedTypes[{}]", path, index);
+        if let Some(def) = converter.type_definition(def, &def_path)? {
+            types.push(def);
+        }
+    }
+    let accounts = list(program, "accounts")
+        .iter()
+        .enumerate()
+        .map(|(index, account)| {
+            let account_path = format!("{}.accounts[{}]", path, index);
+            converter.account(account, &account_path, &discriminators)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let instructions = list(program, "instructions")
+        .iter()
+        .enumerate()
+        .map(|(index, ix)| {
+            let ix_path = format!("{}.instructions[{}]", path, index);
+            converter.instruction(ix, index, &ix_path, &discriminators)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let errors = list(program, "errors")
+        .iter()
+        .enumerate()
+        .map(|(index, error)| {
+            let error_path = format!("{}.errors[{}]", path, index);
+            let code = field(error, &error_path, "code")?
+                .as_u64()
+                .and_then(|code| u32::try_from(code).ok())
+                .ok_or_else(|| self::error(&error_path, "expected a `u32` error code"))?;
+            let msg = error
+                .get("message")
+                .and_then(Value::as_str)
+                .filter(|msg| !msg.is_empty())
+                .map(str::to_string);
+            Ok(IdlErrorCode {
+                code,
+                name: str_field(error, &error_path, "name")?.to_pascal_case(),
+                msg,
+            })
+        })
+        .collect::<Result<Vec<_>, CodamaError>>()?;
+
+    let origin = match discriminators {
+        DiscriminatorStrategy::Anchor => "anchor",
+        DiscriminatorStrategy::Shank { .. } => "shank",
+    };
+    let mut metadata = json!({
+        "address": program.get("publicKey").cloned().unwrap_or(Value::Null),
+        "origin": origin,
+    });
+    if !converter.discriminants.is_empty() {
+        metadata["discriminants"] = Value::Object(converter.discriminants);
+    }
+    Ok(Idl {
+        version: str_field(program, path, "version")?.to_string(),
+        name: str_field(program, path, "name")?.to_snake_case(),
+        docs: docs(program),
+        constants: vec![],
+        instructions,
+        accounts,
+        types,
+        events: None,
+        errors: (!errors.is_empty()).then_some(errors),
+        metadata: Some(metadata),
+    })
+}
+
+/// Converts the nodes of a program, looking up the types and PDAs they refer to.
+struct Converter<'a> {
+    /// Defined types which are neither structs nor enums, by name, along with their paths.
+    aliases: BTreeMap<&'a str, (&'a Value, String)>,
+    /// Aliases being inlined, to detect aliases which refer to themselves.
+    expanding: Vec<&'a str>,
+    pdas: BTreeMap<&'a str, &'a Value>,
+    /// Discriminants of the instructions which are not their positions, by name, see
+    /// [DiscriminatorStrategy::shank].
+    discriminants: Map<String, Value>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(program: &'a Value) -> Result<Self, CodamaError> {
+        let mut aliases = BTreeMap::new();
+        for (index, def) in list(program, "definedTypes").iter().enumerate() {
+            let path = format!("program.definedTypes[{}]", index);
+            let ty = field(def, &path, "type")?;
+            if !matches!(
+                ty.get("kind").and_then(Value::as_str),
+                Some("structTypeNode" | "enumTypeNode")
+            ) {
+                aliases.insert(
+                    str_field(def, &path, "name")?,
+                    (ty, format!("{}.type", path)),
+                );
+            }
+        }
+        let pdas = list(program, "pdas")
+            .iter()
+            .filter_map(|pda| Some((pda.get("name")?.as_str()?, pda)))
+            .collect();
+        Ok(Converter {
+            aliases,
+            expanding: vec![],
+            pdas,
+            discriminants: Map::new(),
+        })
+    }
+
+    /// Converts a defined type, or returns `None` if it is an alias.
+    fn type_definition(
+        &mut self,
+        def: &'a Value,
+        path: &str,
+    ) -> Result<Option<IdlTypeDefinition>, CodamaError> {
+        let name = str_field(def, path, "name")?;
+        if self.aliases.contains_key(name) {
+            return Ok(None);
+        }
+        let ty_path = format!("{}.type", path);
+        let ty = field(def, path, "type")?;
+        let ty = if kind(ty, &ty_path)? == "structTypeNode" {
+            IdlTypeDefinitionTy::Struct {
+                fields: self.fields(ty, &ty_path)?,
+            }
+        } else {
+            IdlTypeDefinitionTy::Enum {
+                variants: self.variants(ty, &ty_path)?,
+            }
+        };
+        Ok(Some(IdlTypeDefinition {
+            name: name.to_pascal_case(),
+            docs: docs(def),
+            ty,
+        }))
+    }
+
+    fn account(
+        &mut self,
+        account: &'a Value,
+        path: &str,
+        discriminators: &DiscriminatorStrategy,
+    ) -> Result<IdlTypeDefinition, CodamaError> {
+        let name = str_field(account, path, "name")?.to_pascal_case();
+        let data_path = format!("{}.data", path);
+        let data = field(account, path, "data")?;
+        if kind(data, &data_path)? != "structTypeNode" {
+            return Err(error(&data_path, "expected a `structTypeNode`"));
+        }
+        let mut fields = self.fields(data, &data_path)?;
+        // accounts without a discriminator keep the fields which tell them apart
+        if *discriminators == DiscriminatorStrategy::Anchor {
+            if let Some((index, discriminator)) =
+                find_discriminator(account, path, list(data, "fields"))?
+            {
+                let expected = discriminators.account_discriminator(&name);
+                if discriminator != expected {
+                    return Err(error(
+                        path,
+                        format!(
+                            "discriminator {:?} of account `{}` does not match {:?}, the \
+                             discriminator derived from its name",
+                            discriminator, name, expected
+                        ),
+                    ));
+                }
+                if let Some(index) = index {
+                    fields.remove(index);
+                }
+            }
+        }
+        Ok(IdlTypeDefinition {
+            name,
+            docs: docs(account),
+            ty: IdlTypeDefinitionTy::Struct { fields },
+        })
+    }
+
+    fn instruction(
+        &mut self,
+        ix: &'a Value,
+        position: usize,
+        path: &str,
+        discriminators: &DiscriminatorStrategy,
+    ) -> Result<IdlInstruction, CodamaError> {
+        let name = str_field(ix, path, "name")?.to_string();
+        let arguments = list(ix, "arguments");
+        let mut args = arguments
+            .iter()
+            .enumerate()
+            .map(|(index, arg)| self.field(arg, &format!("{}.arguments[{}]", path, index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some((index, discriminator)) = find_discriminator(ix, path, arguments)? {
+            let expected = discriminators.instruction_discriminator(position, &name);
+            match (discriminators, discriminator.as_slice()) {
+                (DiscriminatorStrategy::Shank { .. }, &[discriminant]) => {
+                    if discriminator != expected {
+                        self.discriminants.insert(name.clone(), discriminant.into());
+                    }
+                }
+                (DiscriminatorStrategy::Shank { .. }, _) => {
+                    return Err(error(
+                        path,
+                        format!(
+                            "discriminator {:?} of instruction `{}` is not a `u8`",
+                            discriminator, name
+                        ),
+                    ));
+                }
+                (DiscriminatorStrategy::Anchor, _) if discriminator != expected => {
+                    return Err(error(
+                        path,
+                        format!(
+                            "discriminator {:?} of instruction `{}` does not match {:?}, the \
+                             discriminator derived from its name",
+                            discriminator, name, expected
+                        ),
+                    ));
+                }
+                (DiscriminatorStrategy::Anchor, _) => {}
+            }
+            if let Some(index) = index {
+                args.remove(index);
+            }
+        }
+        let accounts = list(ix, "accounts")
+            .iter()
+            .enumerate()
+            .map(|(index, account)| {
+                let account_path = format!("{}.accounts[{}]", path, index);
+                self.instruction_account(account, &account_path)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(IdlInstruction {
+            name,
+            docs: docs(ix),
+            accounts,
+            args,
+            returns: None,
+        })
+    }
+
+    fn instruction_account(
+        &self,
+        account: &'a Value,
+        path: &str,
+    ) -> Result<IdlAccountItem, CodamaError> {
+        let flag = |key: &str| account.get(key) == Some(&Value::Bool(true));
+        // signers which may also be non-signers are passed as plain accounts
+        Ok(IdlAccountItem::IdlAccount(IdlAccount {
+            name: str_field(account, path, "name")?.to_string(),
+            is_mut: flag("isWritable"),
+            is_signer: flag("isSigner"),
+            is_optional: flag("isOptional").then_some(true),
+            docs: docs(account),
+            pda: account
+                .get("defaultValue")
+                .and_then(|value| self.pda(value)),
+            relations: vec![],
+        }))
+    }
+
+    /// Converts the `pdaValueNode` defaulting an instruction account, if each of its seeds is
+    /// either a constant or bound to an account or argument of the instruction.
+    fn pda(&self, value: &'a Value) -> Option<IdlPda> {
+        if value.get("kind")?.as_str()? != "pdaValueNode" {
+            return None;
+        }
+        let pda = value.get("pda")?;
+        let pda = match pda.get("kind")?.as_str()? {
+            "pdaLinkNode" => *self.pdas.get(pda.get("name")?.as_str()?)?,
+            _ => pda,
+        };
+        let bindings = list(value, "seeds");
+        let seeds = list(pda, "seeds")
+            .iter()
+            .map(|seed| match seed.get("kind")?.as_str()? {
+                "constantPdaSeedNode" => constant_seed(seed.get("type")?, seed.get("value")?),
+                "variablePdaSeedNode" => {
+                    let name = seed.get("name")?.as_str()?;
+                    let ty = seed_type(seed.get("type")?)?;
+                    let bound = bindings
+                        .iter()
+                        .find(|binding| binding.get("name").and_then(Value::as_str) == Some(name))?
+                        .get("value")?;
+                    let path = bound.get("name")?.as_str()?.to_string();
+                    match bound.get("kind")?.as_str()? {
+                        "accountValueNode" => Some(IdlSeed::Account(IdlSeedAccount {
+                            ty,
+                            account: None,
+                            path,
+                        })),
+                        "argumentValueNode" => Some(IdlSeed::Arg(IdlSeedArg { ty, path })),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(IdlPda {
+            seeds,
+            program_id: None,
+        })
+    }
+
+    fn fields(&mut self, node: &'a Value, path: &str) -> Result<Vec<IdlField>, CodamaError> {
+        list(node, "fields")
+            .iter()
+            .enumerate()
+            .map(|(index, field)| self.field(field, &format!("{}.fields[{}]", path, index)))
+            .collect()
+    }
+
+    /// Converts a `structFieldTypeNode` or an `instructionArgumentNode`.
+    fn field(&mut self, node: &'a Value, path: &str) -> Result<IdlField, CodamaError> {
+        Ok(IdlField {
+            name: str_field(node, path, "name")?.to_string(),
+            docs: docs(node),
+            ty: self.ty(field(node, path, "type")?, &format!("{}.type", path))?,
+        })
+    }
+
+    fn variants(
+        &mut self,
+        node: &'a Value,
+        path: &str,
+    ) -> Result<Vec<IdlEnumVariant>, CodamaError> {
+        if let Some(size) = node.get("size") {
+            if number_type(size, &format!("{}.size", path))? != IdlType::U8 {
+                return Err(error(path, "enums must be prefixed by a `u8`"));
+            }
+        }
+        list(node, "variants")
+            .iter()
+            .enumerate()
+            .map(|(index, variant)| {
+                let path = format!("{}.variants[{}]", path, index);
+                let discriminator = variant.get("discriminator").and_then(Value::as_u64);
+                if discriminator.is_some_and(|discriminator| discriminator != index as u64) {
+                    return Err(error(
+                        &path,
+                        "variants must be discriminated by their position",
+                    ));
+                }
+                let fields = match kind(variant, &path)? {
+                    "enumEmptyVariantTypeNode" => None,
+                    "enumStructVariantTypeNode" => {
+                        let struct_path = format!("{}.struct", path);
+                        Some(EnumFields::Named(
+                            self.fields(field(variant, &path, "struct")?, &struct_path)?,
+                        ))
+                    }
+                    "enumTupleVariantTypeNode" => {
+                        let tuple_path = format!("{}.tuple", path);
+                        let tuple = field(variant, &path, "tuple")?;
+                        Some(EnumFields::Tuple(
+                            list(tuple, "items")
+                                .iter()
+                                .enumerate()
+                                .map(|(index, item)| {
+                                    self.ty(item, &format!("{}.items[{}]", tuple_path, index))
+                                })
+                                .collect::<Result<Vec<_>, _>>()?,
+                        ))
+                    }
+                    other => return Err(unsupported(&path, other)),
+                };
+                Ok(IdlEnumVariant {
+                    name: str_field(variant, &path, "name")?.to_pascal_case(),
+                    fields,
+                })
+            })
+            .collect()
+    }
+
+    fn ty(&mut self, node: &'a Value, path: &str) -> Result<IdlType, CodamaError> {
+        let ty = match kind(node, path)? {
+            "numberTypeNode" => number_type(node, path)?,
+            "amountTypeNode" | "dateTimeTypeNode" | "solAmountTypeNode" => {
+                number_type(field(node, path, "number")?, &format!("{}.number", path))?
+            }
+            "booleanTypeNode" => {
+                if let Some(size) = node.get("size") {
+                    if number_type(size, &format!("{}.size", path))? != IdlType::U8 {
+                        return Err(error(path, "booleans must be a `u8`"));
+                    }
+                }
+                IdlType::Bool
+            }
+            "publicKeyTypeNode" => IdlType::PublicKey,
+            "sizePrefixTypeNode" => {
+                let prefix =
+                    number_type(field(node, path, "prefix")?, &format!("{}.prefix", path))?;
+                if prefix != IdlType::U32 {
+                    return Err(error(path, "strings and bytes must be prefixed by a `u32`"));
+                }
+                let inner_path = format!("{}.type", path);
+                match kind(field(node, path, "type")?, &inner_path)? {
+                    "stringTypeNode" => IdlType::String,
+                    "bytesTypeNode" => IdlType::Bytes,
+                    other => return Err(unsupported(&inner_path, other)),
+                }
+            }
+            "fixedSizeTypeNode" => {
+                let size = usize_field(node, path, "size")?;
+                let inner_path = format!("{}.type", path);
+                match kind(field(node, path, "type")?, &inner_path)? {
+                    "bytesTypeNode" | "stringTypeNode" => {
+                        IdlType::Array(Box::new(IdlType::U8), size)
+                    }
+                    other => return Err(unsupported(&inner_path, other)),
+                }
+            }
+            "arrayTypeNode" => {
+                let item = self.ty(field(node, path, "item")?, &format!("{}.item", path))?;
+                let count_path = format!("{}.count", path);
+                let count = field(node, path, "count")?;
+                match kind(count, &count_path)? {
+                    "fixedCountNode" => {
+                        IdlType::Array(Box::new(item), usize_field(count, &count_path, "value")?)
+                    }
+                    "prefixedCountNode" => {
+                        let prefix_path = format!("{}.prefix", count_path);
+                        if number_type(field(count, &count_path, "prefix")?, &prefix_path)?
+                            != IdlType::U32
+                        {
+                            return Err(error(&count_path, "vectors must be prefixed by a `u32`"));
+                        }
+                        IdlType::Vec(Box::new(item))
+                    }
+                    other => return Err(unsupported(&count_path, other)),
+                }
+            }
+            "optionTypeNode" => {
+                if node.get("fixed") == Some(&Value::Bool(true)) {
+                    return Err(error(
+                        path,
+                        "fixed size options have no equivalent in an Anchor IDL",
+                    ));
+                }
+                if let Some(prefix) = node.get("prefix") {
+                    if number_type(prefix, &format!("{}.prefix", path))? != IdlType::U8 {
+                        return Err(error(path, "options must be prefixed by a `u8`"));
+                    }
+                }
+                IdlType::Option(Box::new(
+                    self.ty(field(node, path, "item")?, &format!("{}.item", path))?,
+                ))
+            }
+            "definedTypeLinkNode" => {
+                let name = str_field(node, path, "name")?;
+                match self.aliases.get(name) {
+                    Some((alias, alias_path)) => {
+                        if self.expanding.contains(&name) {
+                            return Err(error(
+                                alias_path,
+                                format!("type `{}` is an alias of itself", name),
+                            ));
+                        }
+                        let alias_path = alias_path.clone();
+                        self.expanding.push(name);
+                        let ty = self.ty(alias, &alias_path);
+                        self.expanding.pop();
+                        ty?
+                    }
+                    None => IdlType::Defined(name.to_pascal_case()),
+                }
+            }
+            other => return Err(unsupported(path, other)),
+        };
+        Ok(ty)
+    }
+}
+
+fn unsupported(path: &str, kind: &str) -> CodamaError {
+    error(
+        path,
+        format!("`{}` has no equivalent in an Anchor IDL", kind),
+    )
+}
+
+/// Bytes of a discriminator, along with the index of the field or argument holding it, if any.
+type Discriminator = (Option<usize>, Vec<u8>);
+
+/// Finds the discriminator at the start of an account or instruction.
+fn find_discriminator(
+    node: &Value,
+    path: &str,
+    fields: &[Value],
+) -> Result<Option<Discriminator>, CodamaError> {
+    for (index, discriminator) in list(node, "discriminators").iter().enumerate() {
+        let path = format!("{}.discriminators[{}]", path, index);
+        if discriminator
+            .get("offset")
+            .and_then(Value::as_u64)
+            .unwrap_or(0)
+            != 0
+        {
+            continue;
+        }
+        match kind(discriminator, &path)? {
+            "fieldDiscriminatorNode" => {
+                let name = str_field(discriminator, &path, "name")?;
+                let index = fields
+                    .iter()
+                    .position(|field| field.get("name").and_then(Value::as_str) == Some(name))
+                    .ok_or_else(|| error(&path, format!("no field named `{}`", name)))?;
+                let field = &fields[index];
+                let value = field.get("defaultValue").ok_or_else(|| {
+                    error(
+                        &path,
+                        format!("discriminator field `{}` has no default value", name),
+                    )
+                })?;
+                let bytes = value_bytes(field.get("type").unwrap_or(&Value::Null), value, &path)?;
+                return Ok(Some((Some(index), bytes)));
+            }
+            "constantDiscriminatorNode" => {
+                let constant = field(discriminator, &path, "constant")?;
+                let ty = constant.get("type").unwrap_or(&Value::Null);
+                let bytes = value_bytes(ty, field(constant, &path, "value")?, &path)?;
+                return Ok(Some((None, bytes)));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Serializes the value of a discriminator.
+fn value_bytes(ty: &Value, value: &Value, path: &str) -> Result<Vec<u8>, CodamaError> {
+    match kind(value, path)? {
+        "bytesValueNode" => {
+            let data = str_field(value, path, "data")?;
+            let bytes = match str_field(value, path, "encoding")? {
+                "base16" => decode_base16(data),
+                "base58" => decode_base58(data),
+                "base64" => base64::decode(data).ok(),
+                "utf8" => Some(data.as_bytes().to_vec()),
+                other => return Err(error(path, format!("unknown encoding `{}`", other))),
+            };
+            bytes.ok_or_else(|| error(path, format!("invalid bytes `{}`", data)))
+        }
+        "numberValueNode" => {
+            let number = field(value, path, "number")?
+                .as_u64()
+                .ok_or_else(|| error(path, "expected an unsigned integer"))?;
+            let size = match number_type(ty, path)? {
+                IdlType::U8 => 1,
+                IdlType::U16 => 2,
+                IdlType::U32 => 4,
+                IdlType::U64 => 8,
+                _ => return Err(error(path, "discriminators must be unsigned integers")),
+            };
+            Ok(number.to_le_bytes()[..size].to_vec())
+        }
+        other => Err(unsupported(path, other)),
+    }
+}
+
+/// Converts the type and value of a constant PDA seed.
+fn constant_seed(ty: &Value, value: &Value) -> Option<IdlSeed> {
+    let (ty, value) = match value.get("kind")?.as_str()? {
+        "stringValueNode" => (IdlType::String, value.get("string")?.clone()),
+        "publicKeyValueNode" => (IdlType::PublicKey, value.get("publicKey")?.clone()),
+        "numberValueNode" => (number_type(ty, "").ok()?, value.get("number")?.clone()),
+        "bytesValueNode" => {
+            let bytes = value_bytes(ty, value, "").ok()?;
+            (IdlType::Bytes, json!(bytes))
+        }
+        _ => return None,
+    };
+    Some(IdlSeed::Const(IdlSeedConst { ty, value }))
+}
+
+/// Converts the type of a variable PDA seed, which is not prefixed by its length.
+fn seed_type(ty: &Value) -> Option<IdlType> {
+    match ty.get("kind")?.as_str()? {
+        "publicKeyTypeNode" => Some(IdlType::PublicKey),
+        "stringTypeNode" => Some(IdlType::String),
+        "bytesTypeNode" => Some(IdlType::Bytes),
+        _ => number_type(ty, "").ok(),
+    }
+}
+
+fn number_type(node: &Value, path: &str) -> Result<IdlType, CodamaError> {
+    match kind(node, path)? {
+        "numberTypeNode" => {}
+        other => {
+            return Err(error(
+                path,
+                format!("expected a `numberTypeNode`, found `{}`", other),
+            ))
+        }
+    }
+    if node.get("endian").and_then(Value::as_str) == Some("be") {
+        return Err(error(
+            path,
+            "big endian numbers have no equivalent in an Anchor IDL",
+        ));
+    }
+    let format = str_field(node, path, "format")?;
+    Ok(match format {
+        "u8" => IdlType::U8,
+        "i8" => IdlType::I8,
+        "u16" => IdlType::U16,
+        "i16" => IdlType::I16,
+        "u32" => IdlType::U32,
+        "i32" => IdlType::I32,
+        "f32" => IdlType::F32,
+        "u64" => IdlType::U64,
+        "i64" => IdlType::I64,
+        "f64" => IdlType::F64,
+        "u128" => IdlType::U128,
+        "i128" => IdlType::I128,
+        other => {
+            return Err(error(
+                path,
+                format!("`{}` numbers have no equivalent in an Anchor IDL", other),
+            ))
+        }
+    })
+}
+
+fn decode_base16(data: &str) -> Option<Vec<u8>> {
+    if data.len() % 2 == 1 {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(data.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) fn decode_base58(data: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // little endian digits in base 256
+    let mut bytes: Vec<u8> = vec![];
+    for c in data.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in &mut bytes {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // each leading `1` encodes a leading zero
+    let zeros = data.bytes().take_while(|&c| c == b'1').count();
+    bytes.resize(bytes.len() + zeros, 0);
+    bytes.reverse();
+    Some(bytes)
+}
+
+fn kind<'v>(node: &'v Value, path: &str) -> Result<&'v str, CodamaError> {
+    str_field(node, path, "kind")
+}
+
+fn field<'v>(node: &'v Value, path: &str, key: &str) -> Result<&'v Value, CodamaError> {
+    node.get(key)
+        .ok_or_else(|| error(path, format!("missing `{}`", key)))
+}
+
+fn str_field<'v>(node: &'v Value, path: &str, key: &str) -> Result<&'v str, CodamaError> {
+    field(node, path, key)?
+        .as_str()
+        .ok_or_else(|| error(path, format!("expected `{}` to be a string", key)))
+}
+
+fn usize_field(node: &Value, path: &str, key: &str) -> Result<usize, CodamaError> {
+    field(node, path, key)?
+        .as_u64()
+        .map(|value| value as usize)
+        .ok_or_else(|| error(path, format!("expected `{}` to be an integer", key)))
+}
+
+/// A list of nodes, empty if it is missing.
+fn list<'v>(node: &'v Value, key: &str) -> &'v [Value] {
+    node.get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn docs(node: &Value) -> Option<Vec<String>> {
+    let docs = list(node, "docs")
+        .iter()
+        .filter_map(|doc| doc.as_str().map(str::to_string))
+        .collect::<Vec<_>>();
+    (!docs.is_empty()).then_some(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root node whose program has the given nodes along with its name and version.
+    fn root(program: Value) -> Value {
+        let mut program_node = json!({ "kind": "programNode", "name": "test", "version": "0.1.0" });
+        program_node
+            .as_object_mut()
+            .unwrap()
+            .extend(program.as_object().unwrap().clone());
+        json!({ "kind": "rootNode", "program": program_node })
+    }
+
+    /// A root node with a single instruction taking an argument of type `ty`.
+    fn with_argument(ty: Value) -> Value {
+        root(json!({
+            "instructions": [{
+                "kind": "instructionNode",
+                "name": "run",
+                "accounts": [],
+                "arguments": [{ "kind": "instructionArgumentNode", "name": "value", "type": ty }]
+            }]
+        }))
+    }
+
+    fn u8_type() -> Value {
+        json!({ "kind": "numberTypeNode", "format": "u8", "endian": "le" })
+    }
+
+    fn u8_value(number: u64) -> Value {
+        json!({ "kind": "numberValueNode", "number": number })
+    }
+
+    #[test]
+    fn test_not_a_root_node() {
+        let idl: Value =
+            serde_json::from_str(include_str!("../../../examples/shank-cpi/idl.json")).unwrap();
+        assert!(!is_codama_root(&idl));
+        assert_eq!(
+            codama_to_idl(&idl).unwrap_err(),
+            error("root", "expected a `rootNode`")
+        );
+    }
+
+    #[test]
+    fn test_types_without_anchor_equivalent() {
+        let unsupported = [
+            (
+                json!({ "kind": "mapTypeNode", "key": u8_type(), "value": u8_type() }),
+                "program.instructions[0].arguments[0].type",
+                "`mapTypeNode` has no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({ "kind": "tupleTypeNode", "items": [u8_type()] }),
+                "program.instructions[0].arguments[0].type",
+                "`tupleTypeNode` has no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({ "kind": "numberTypeNode", "format": "shortU16", "endian": "le" }),
+                "program.instructions[0].arguments[0].type",
+                "`shortU16` numbers have no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({ "kind": "numberTypeNode", "format": "u32", "endian": "be" }),
+                "program.instructions[0].arguments[0].type",
+                "big endian numbers have no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({ "kind": "optionTypeNode", "fixed": true, "item": u8_type() }),
+                "program.instructions[0].arguments[0].type",
+                "fixed size options have no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({
+                    "kind": "arrayTypeNode",
+                    "item": u8_type(),
+                    "count": { "kind": "remainderCountNode" }
+                }),
+                "program.instructions[0].arguments[0].type.count",
+                "`remainderCountNode` has no equivalent in an Anchor IDL",
+            ),
+            (
+                json!({
+                    "kind": "sizePrefixTypeNode",
+                    "prefix": u8_type(),
+                    "type": { "kind": "stringTypeNode", "encoding": "utf8" }
+                }),
+                "program.instructions[0].arguments[0].type",
+                "strings and bytes must be prefixed by a `u32`",
+            ),
+            (
+                json!({ "kind": "stringTypeNode", "encoding": "utf8" }),
+                "program.instructions[0].arguments[0].type",
+                "`stringTypeNode` has no equivalent in an Anchor IDL",
+            ),
+        ];
+        for (ty, path, message) in unsupported {
+            assert_eq!(
+                codama_to_idl(&with_argument(ty)).unwrap_err(),
+                error(path, message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_supported_types() {
+        let ty = json!({
+            "kind": "optionTypeNode",
+            "prefix": u8_type(),
+            "item": {
+                "kind": "arrayTypeNode",
+                "item": { "kind": "amountTypeNode", "decimals": 9, "number": u8_type() },
+                "count": {
+                    "kind": "prefixedCountNode",
+                    "prefix": { "kind": "numberTypeNode", "format": "u32", "endian": "le" }
+                }
+            }
+        });
+        let idl = codama_to_idl(&with_argument(ty)).unwrap();
+        assert_eq!(
+            idl.instructions[0].args[0].ty,
+            IdlType::Option(Box::new(IdlType::Vec(Box::new(IdlType::U8))))
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        let alias =
+            |name: &str, ty: Value| json!({ "kind": "definedTypeNode", "name": name, "type": ty });
+        let link = |name: &str| json!({ "kind": "definedTypeLinkNode", "name": name });
+        let mut root = with_argument(link("amount"));
+        root["program"]["definedTypes"] = json!([alias("amount", u8_type())]);
+        let idl = codama_to_idl(&root).unwrap();
+        assert!(idl.types.is_empty());
+        assert_eq!(idl.instructions[0].args[0].ty, IdlType::U8);
+
+        root["program"]["definedTypes"] = json!([
+            alias("amount", link("balance")),
+            alias("balance", link("amount"))
+        ]);
+        assert_eq!(
+            codama_to_idl(&root).unwrap_err(),
+            error(
+                "program.definedTypes[0].type",
+                "type `amount` is an alias of itself"
+            )
+        );
+    }
+
+    #[test]
+    fn test_enum_variants_by_position() {
+        let root = root(json!({
+            "definedTypes": [{
+                "kind": "definedTypeNode",
+                "name": "side",
+                "type": {
+                    "kind": "enumTypeNode",
+                    "variants": [
+                        { "kind": "enumEmptyVariantTypeNode", "name": "bid", "discriminator": 1 },
+                        { "kind": "enumEmptyVariantTypeNode", "name": "ask", "discriminator": 0 }
+                    ]
+                }
+            }]
+        }));
+        assert_eq!(
+            codama_to_idl(&root).unwrap_err(),
+            error(
+                "program.definedTypes[0].type.variants[0]",
+                "variants must be discriminated by their position"
+            )
+        );
+    }
+
+    #[test]
+    fn test_instruction_discriminators() {
+        let root = |discriminator: u64| {
+            root(json!({
+                "instructions": [{
+                    "kind": "instructionNode",
+                    "name": "run",
+                    "accounts": [],
+                    "arguments": [{
+                        "kind": "instructionArgumentNode",
+                        "name": "discriminator",
+                        "type": u8_type(),
+                        "defaultValue": u8_value(discriminator)
+                    }],
+                    "discriminators": [{ "kind": "fieldDiscriminatorNode", "name": "discriminator" }]
+                }]
+            }))
+        };
+        // the discriminator is generated from the position of the instruction
+        let idl = codama_to_idl(&root(0)).unwrap();
+        assert!(idl.instructions[0].args.is_empty());
+        assert!(idl.metadata.unwrap().get("discriminants").is_none());
+
+        // unless it is recorded
+        let idl = codama_to_idl(&root(3)).unwrap();
+        assert!(idl.instructions[0].args.is_empty());
+        assert_eq!(
+            idl.metadata.as_ref().unwrap()["discriminants"],
+            json!({ "run": 3 })
+        );
+        assert_eq!(
+            DiscriminatorStrategy::detect(&idl).instruction_discriminator(0, "run"),
+            [3]
+        );
+    }
+
+    #[test]
+    fn test_account_discriminators() {
+        let root = |discriminator: &[u8]| {
+            root(json!({
+                "origin": "anchor",
+                "accounts": [{
+                    "kind": "accountNode",
+                    "name": "vault",
+                    "data": {
+                        "kind": "structTypeNode",
+                        "fields": [{ "kind": "structFieldTypeNode", "name": "amount", "type": u8_type() }]
+                    },
+                    "discriminators": [{
+                        "kind": "constantDiscriminatorNode",
+                        "offset": 0,
+                        "constant": {
+                            "kind": "constantValueNode",
+                            "type": { "kind": "bytesTypeNode" },
+                            "value": {
+                                "kind": "bytesValueNode",
+                                "encoding": "base64",
+                                "data": base64::encode(discriminator)
+                            }
+                        }
+                    }]
+                }]
+            }))
+        };
+        let expected = DiscriminatorStrategy::Anchor.account_discriminator("Vault");
+        let idl = codama_to_idl(&root(&expected)).unwrap();
+        assert_eq!(idl.accounts[0].name, "Vault");
+        assert_eq!(
+            DiscriminatorStrategy::detect(&idl),
+            DiscriminatorStrategy::Anchor
+        );
+
+        let error = codama_to_idl(&root(&[0; 8])).unwrap_err();
+        assert_eq!(error.path, "program.accounts[0]");
+        assert!(error.message.contains("does not match"));
+    }
+}
@@ -0,0 +1,1183 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{anchor_discriminator, get_fixed_size, ty_to_rust_type, DiscriminatorStrategy};
+use anchor_syn::idl::{
+    EnumFields, Idl, IdlAccount, IdlAccountItem, IdlEvent, IdlInstruction, IdlType,
+    IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+
+/// Whether a change to an IDL breaks existing clients or on-chain data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// Existing clients or accounts can no longer be used with the new IDL.
+    Breaking,
+    /// The new IDL can be used in place of the old one.
+    Compatible,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compatibility::Breaking => write!(f, "breaking"),
+            Compatibility::Compatible => write!(f, "compatible"),
+        }
+    }
+}
+
+/// A single difference between two versions of an IDL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlChange {
+    /// Whether the change is breaking.
+    pub compatibility: Compatibility,
+    /// The changed item, e.g. `account Whirlpool` or `instruction swap`.
+    pub item: String,
+    /// Description of the change.
+    pub message: String,
+}
+
+impl fmt::Display for IdlChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.compatibility, self.item, self.message)
+    }
+}
+
+/// Differences between two versions of an IDL.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IdlDiff {
+    pub changes: Vec<IdlChange>,
+}
+
+impl IdlDiff {
+    /// Whether any of the changes is breaking.
+    pub fn is_breaking(&self) -> bool {
+        self.breaking_changes().next().is_some()
+    }
+
+    /// The breaking changes.
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &IdlChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.compatibility == Compatibility::Breaking)
+    }
+
+    fn push(&mut self, compatibility: Compatibility, item: &str, message: String) {
+        self.changes.push(IdlChange {
+            compatibility,
+            item: item.to_string(),
+            message,
+        });
+    }
+
+    fn breaking(&mut self, item: &str, message: String) {
+        self.push(Compatibility::Breaking, item, message);
+    }
+
+    fn compatible(&mut self, item: &str, message: String) {
+        self.push(Compatibility::Compatible, item, message);
+    }
+}
+
+fn format_discriminator(discriminator: &[u8]) -> String {
+    if discriminator.is_empty() {
+        return "none".to_string();
+    }
+    discriminator
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Compares two versions of an IDL, classifying every difference as breaking or compatible.
+///
+/// Layout changes of accounts, types, instruction arguments and events, changes to the
+/// accounts of instructions, changed discriminators and removed or renamed errors are breaking.
+/// Additions of new items, renamed fields and changed error messages are compatible.
+///
+/// Discriminators follow the [DiscriminatorStrategy] detected from each IDL, so that moving the
+/// instructions of a Shank IDL is breaking, while renaming them is not.
+///
+/// ```
+/// use anchor_idl::{diff_idls, Idl};
+///
+/// let old: Idl = serde_json::from_str(include_str!("../../../examples/govern-cpi/idl.json")).unwrap();
+/// let mut new = old.clone();
+/// assert!(diff_idls(&old, &new).changes.is_empty());
+///
+/// new.instructions.retain(|ix| ix.name != "activateProposal");
+/// let diff = diff_idls(&old, &new);
+/// assert!(diff.is_breaking());
+/// assert_eq!(diff.changes[0].to_string(), "breaking: instruction activateProposal: removed");
+/// ```
+pub fn diff_idls(old: &Idl, new: &Idl) -> IdlDiff {
+    let mut diff = IdlDiff::default();
+    let old_defs = old
+        .types
+        .iter()
+        .chain(old.accounts.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+    let new_defs = new
+        .types
+        .iter()
+        .chain(new.accounts.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut ctx = DiffContext {
+        old_defs: &old_defs,
+        new_defs: &new_defs,
+        renames: BTreeMap::new(),
+        old_discriminators: DiscriminatorStrategy::detect(old),
+        new_discriminators: DiscriminatorStrategy::detect(new),
+    };
+    // renamed types must be known before any fields referencing them are compared
+    let account_renames = find_renames(&ctx, &old.accounts, &new.accounts);
+    let type_renames = find_renames(&ctx, &old.types, &new.types);
+    ctx.renames.extend(account_renames.clone());
+    ctx.renames.extend(type_renames.clone());
+
+    diff_defs(
+        &mut diff,
+        &ctx,
+        "account",
+        &old.accounts,
+        &new.accounts,
+        &account_renames,
+    );
+    diff_defs(
+        &mut diff,
+        &ctx,
+        "type",
+        &old.types,
+        &new.types,
+        &type_renames,
+    );
+    diff_instructions(&mut diff, &ctx, &old.instructions, &new.instructions);
+    diff_events(
+        &mut diff,
+        &ctx,
+        old.events.as_deref().unwrap_or_default(),
+        new.events.as_deref().unwrap_or_default(),
+    );
+    diff_errors(
+        &mut diff,
+        old.errors.as_deref().unwrap_or_default(),
+        new.errors.as_deref().unwrap_or_default(),
+    );
+    diff
+}
+
+struct DiffContext<'a> {
+    old_defs: &'a [IdlTypeDefinition],
+    new_defs: &'a [IdlTypeDefinition],
+    /// Old names of renamed types, mapped to their new names.
+    renames: BTreeMap<String, String>,
+    old_discriminators: DiscriminatorStrategy,
+    new_discriminators: DiscriminatorStrategy,
+}
+
+impl DiffContext<'_> {
+    fn same_type(&self, old: &IdlType, new: &IdlType) -> bool {
+        match (old, new) {
+            (IdlType::Defined(old), IdlType::Defined(new)) => {
+                old == new || self.renames.get(old) == Some(new)
+            }
+            (IdlType::Option(old), IdlType::Option(new))
+            | (IdlType::Vec(old), IdlType::Vec(new)) => self.same_type(old, new),
+            (IdlType::Array(old, old_len), IdlType::Array(new, new_len)) => {
+                old_len == new_len && self.same_type(old, new)
+            }
+            _ => old == new,
+        }
+    }
+
+    fn same_fields(&self, old: &[Field], new: &[Field]) -> bool {
+        old.len() == new.len()
+            && old
+                .iter()
+                .zip(new)
+                .all(|(old, new)| old.name == new.name && self.same_type(old.ty, new.ty))
+    }
+
+    fn same_def(&self, old: &IdlTypeDefinitionTy, new: &IdlTypeDefinitionTy) -> bool {
+        match (old, new) {
+            (
+                IdlTypeDefinitionTy::Struct { fields: old },
+                IdlTypeDefinitionTy::Struct { fields: new },
+            ) => self.same_fields(&Field::from_idl(old), &Field::from_idl(new)),
+            (
+                IdlTypeDefinitionTy::Enum { variants: old },
+                IdlTypeDefinitionTy::Enum { variants: new },
+            ) => {
+                old.len() == new.len()
+                    && old.iter().zip(new).all(|(old, new)| {
+                        old.name == new.name
+                            && self.same_fields(
+                                &Field::from_variant(&old.fields),
+                                &Field::from_variant(&new.fields),
+                            )
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    fn describe_type_change(&self, old: &IdlType, new: &IdlType) -> String {
+        let sizes = (
+            get_fixed_size(self.old_defs, old),
+            get_fixed_size(self.new_defs, new),
+        );
+        let sizes = match sizes {
+            (Some(old_size), Some(new_size)) if old_size != new_size => {
+                format!(" ({} -> {} bytes)", old_size, new_size)
+            }
+            _ => String::new(),
+        };
+        format!(
+            "`{}` -> `{}`{}",
+            ty_to_rust_type(old),
+            ty_to_rust_type(new),
+            sizes
+        )
+    }
+}
+
+/// A named field of a struct, enum variant, instruction or event.
+struct Field<'a> {
+    name: &'a str,
+    ty: &'a IdlType,
+}
+
+impl<'a> Field<'a> {
+    fn from_idl(fields: &'a [anchor_syn::idl::IdlField]) -> Vec<Self> {
+        fields
+            .iter()
+            .map(|field| Field {
+                name: &field.name,
+                ty: &field.ty,
+            })
+            .collect()
+    }
+
+    fn from_variant(fields: &'a Option<EnumFields>) -> Vec<Self> {
+        match fields {
+            Some(EnumFields::Named(fields)) => Field::from_idl(fields),
+            // tuple fields are named by their index
+            Some(EnumFields::Tuple(types)) => {
+                types.iter().map(|ty| Field { name: "", ty }).collect()
+            }
+            None => vec![],
+        }
+    }
+
+    fn from_event(event: &'a IdlEvent) -> Vec<Self> {
+        event
+            .fields
+            .iter()
+            .map(|field| Field {
+                name: &field.name,
+                ty: &field.ty,
+            })
+            .collect()
+    }
+
+    fn display(&self, index: usize) -> String {
+        if self.name.is_empty() {
+            index.to_string()
+        } else {
+            self.name.to_string()
+        }
+    }
+}
+
+/// Finds removed types whose definition is identical to an added type.
+fn find_renames(
+    ctx: &DiffContext,
+    old: &[IdlTypeDefinition],
+    new: &[IdlTypeDefinition],
+) -> BTreeMap<String, String> {
+    let removed = old
+        .iter()
+        .filter(|old_def| !new.iter().any(|new_def| new_def.name == old_def.name));
+    let mut renames = BTreeMap::new();
+    for old_def in removed {
+        let renamed = new.iter().find(|new_def| {
+            !old.iter().any(|def| def.name == new_def.name)
+                && !renames.values().any(|name| *name == new_def.name)
+                && ctx.same_def(&old_def.ty, &new_def.ty)
+        });
+        if let Some(new_def) = renamed {
+            renames.insert(old_def.name.clone(), new_def.name.clone());
+        }
+    }
+    renames
+}
+
+fn diff_defs(
+    diff: &mut IdlDiff,
+    ctx: &DiffContext,
+    kind: &str,
+    old: &[IdlTypeDefinition],
+    new: &[IdlTypeDefinition],
+    renames: &BTreeMap<String, String>,
+) {
+    for old_def in old {
+        let item = format!("{} {}", kind, old_def.name);
+        let new_def = match new.iter().find(|def| def.name == old_def.name) {
+            Some(new_def) => new_def,
+            None => {
+                match (renames.get(&old_def.name), kind) {
+                    (Some(new_name), "account") => {
+                        let old_discriminator =
+                            ctx.old_discriminators.account_discriminator(&old_def.name);
+                        let new_discriminator =
+                            ctx.new_discriminators.account_discriminator(new_name);
+                        if old_discriminator == new_discriminator {
+                            diff.compatible(
+                                &item,
+                                format!("renamed to `{}` without changing its layout", new_name),
+                            )
+                        } else {
+                            diff.breaking(
+                                &item,
+                                format!(
+                                    "renamed to `{}`, changing the discriminator from {} to {}",
+                                    new_name,
+                                    format_discriminator(&old_discriminator),
+                                    format_discriminator(&new_discriminator),
+                                ),
+                            )
+                        }
+                    }
+                    (Some(new_name), _) => diff.compatible(
+                        &item,
+                        format!("renamed to `{}` without changing its layout", new_name),
+                    ),
+                    (None, _) => diff.breaking(&item, "removed".to_string()),
+                }
+                continue;
+            }
+        };
+        if kind == "account" {
+            let old_discriminator = ctx.old_discriminators.account_discriminator(&old_def.name);
+            let new_discriminator = ctx.new_discriminators.account_discriminator(&new_def.name);
+            if old_discriminator != new_discriminator {
+                diff.breaking(
+                    &item,
+                    format!(
+                        "discriminator changed from {} to {}",
+                        format_discriminator(&old_discriminator),
+                        format_discriminator(&new_discriminator),
+                    ),
+                );
+            }
+        }
+        match (&old_def.ty, &new_def.ty) {
+            (
+                IdlTypeDefinitionTy::Struct { fields: old },
+                IdlTypeDefinitionTy::Struct { fields: new },
+            ) => diff_fields(
+                diff,
+                ctx,
+                &item,
+                "field",
+                &Field::from_idl(old),
+                &Field::from_idl(new),
+            ),
+            (
+                IdlTypeDefinitionTy::Enum { variants: old },
+                IdlTypeDefinitionTy::Enum { variants: new },
+            ) => {
+                for (index, old_variant) in old.iter().enumerate() {
+                    match new
+                        .iter()
+                        .position(|variant| variant.name == old_variant.name)
+                    {
+                        Some(new_index) if new_index != index => diff.breaking(
+                            &item,
+                            format!(
+                                "variant `{}` moved from index {} to {}",
+                                old_variant.name, index, new_index
+                            ),
+                        ),
+                        Some(_) => {}
+                        None => match new.get(index) {
+                            Some(new_variant)
+                                if !old.iter().any(|variant| variant.name == new_variant.name)
+                                    && ctx.same_fields(
+                                        &Field::from_variant(&old_variant.fields),
+                                        &Field::from_variant(&new_variant.fields),
+                                    ) =>
+                            {
+                                diff.compatible(
+                                    &item,
+                                    format!(
+                                        "variant `{}` renamed to `{}`",
+                                        old_variant.name, new_variant.name
+                                    ),
+                                )
+                            }
+                            _ => diff
+                                .breaking(&item, format!("variant `{}` removed", old_variant.name)),
+                        },
+                    }
+                    if let Some(new_variant) =
+                        new.iter().find(|variant| variant.name == old_variant.name)
+                    {
+                        diff_fields(
+                            diff,
+                            ctx,
+                            &format!("{}::{}", item, old_variant.name),
+                            "field",
+                            &Field::from_variant(&old_variant.fields),
+                            &Field::from_variant(&new_variant.fields),
+                        );
+                    }
+                }
+                for (index, new_variant) in new.iter().enumerate().skip(old.len()) {
+                    if !old.iter().any(|variant| variant.name == new_variant.name) {
+                        diff.compatible(
+                            &item,
+                            format!("variant `{}` added at index {}", new_variant.name, index),
+                        );
+                    }
+                }
+            }
+            (IdlTypeDefinitionTy::Struct { .. }, IdlTypeDefinitionTy::Enum { .. }) => {
+                diff.breaking(&item, "changed from a struct to an enum".to_string())
+            }
+            (IdlTypeDefinitionTy::Enum { .. }, IdlTypeDefinitionTy::Struct { .. }) => {
+                diff.breaking(&item, "changed from an enum to a struct".to_string())
+            }
+        }
+    }
+    for new_def in new {
+        let is_new = !old.iter().any(|def| def.name == new_def.name)
+            && !renames.values().any(|name| *name == new_def.name);
+        if is_new {
+            diff.compatible(&format!("{} {}", kind, new_def.name), "added".to_string());
+        }
+    }
+}
+
+/// Compares two lists of fields, which are serialized in order.
+fn diff_fields(
+    diff: &mut IdlDiff,
+    ctx: &DiffContext,
+    item: &str,
+    noun: &str,
+    old: &[Field],
+    new: &[Field],
+) {
+    let mut renamed = vec![];
+    for (index, old_field) in old.iter().enumerate() {
+        let name = old_field.display(index);
+        let new_index = if old_field.name.is_empty() {
+            (index < new.len()).then_some(index)
+        } else {
+            new.iter().position(|field| field.name == old_field.name)
+        };
+        match new_index {
+            Some(new_index) => {
+                if new_index != index {
+                    diff.breaking(
+                        item,
+                        format!(
+                            "{} `{}` moved from position {} to {}",
+                            noun, name, index, new_index
+                        ),
+                    );
+                }
+                let new_field = &new[new_index];
+                if !ctx.same_type(old_field.ty, new_field.ty) {
+                    diff.breaking(
+                        item,
+                        format!(
+                            "{} `{}` changed type: {}",
+                            noun,
+                            name,
+                            ctx.describe_type_change(old_field.ty, new_field.ty)
+                        ),
+                    );
+                }
+            }
+            None => match new.get(index) {
+                Some(new_field)
+                    if !old.iter().any(|field| field.name == new_field.name)
+                        && ctx.same_type(old_field.ty, new_field.ty) =>
+                {
+                    renamed.push(index);
+                    diff.compatible(
+                        item,
+                        format!("{} `{}` renamed to `{}`", noun, name, new_field.name),
+                    );
+                }
+                _ => diff.breaking(item, format!("{} `{}` removed", noun, name)),
+            },
+        }
+    }
+    for (index, new_field) in new.iter().enumerate() {
+        let is_new = if new_field.name.is_empty() {
+            index >= old.len()
+        } else {
+            !old.iter().any(|field| field.name == new_field.name) && !renamed.contains(&index)
+        };
+        if is_new {
+            diff.breaking(
+                item,
+                format!(
+                    "{} `{}` of type `{}` added at position {}",
+                    noun,
+                    new_field.display(index),
+                    ty_to_rust_type(new_field.ty),
+                    index
+                ),
+            );
+        }
+    }
+}
+
+/// Flattens the accounts of an instruction, naming nested accounts by their path.
+fn flatten_accounts<'a>(
+    prefix: &str,
+    items: &'a [IdlAccountItem],
+    accounts: &mut Vec<(String, &'a IdlAccount)>,
+) {
+    for item in items {
+        match item {
+            IdlAccountItem::IdlAccount(account) => {
+                accounts.push((format!("{}{}", prefix, account.name), account))
+            }
+            IdlAccountItem::IdlAccounts(nested) => flatten_accounts(
+                &format!("{}{}.", prefix, nested.name),
+                &nested.accounts,
+                accounts,
+            ),
+        }
+    }
+}
+
+fn diff_instruction_accounts(
+    diff: &mut IdlDiff,
+    item: &str,
+    old: &[IdlAccountItem],
+    new: &[IdlAccountItem],
+) {
+    let mut old_accounts = vec![];
+    flatten_accounts("", old, &mut old_accounts);
+    let mut new_accounts = vec![];
+    flatten_accounts("", new, &mut new_accounts);
+
+    let same_flags = |old: &IdlAccount, new: &IdlAccount| {
+        old.is_mut == new.is_mut
+            && old.is_signer == new.is_signer
+            && old.is_optional.unwrap_or(false) == new.is_optional.unwrap_or(false)
+    };
+
+    let mut renamed = vec![];
+    for (index, (name, old_account)) in old_accounts.iter().enumerate() {
+        let new_index = new_accounts
+            .iter()
+            .position(|(new_name, _)| new_name == name);
+        let new_account = match new_index {
+            Some(new_index) => {
+                if new_index != index {
+                    diff.breaking(
+                        item,
+                        format!(
+                            "account `{}` moved from position {} to {}",
+                            name, index, new_index
+                        ),
+                    );
+                }
+                new_accounts[new_index].1
+            }
+            None => {
+                match new_accounts.get(index) {
+                    Some((new_name, new_account))
+                        if !old_accounts.iter().any(|(name, _)| name == new_name)
+                            && same_flags(old_account, new_account) =>
+                    {
+                        renamed.push(index);
+                        diff.compatible(
+                            item,
+                            format!("account `{}` renamed to `{}`", name, new_name),
+                        );
+                    }
+                    _ => diff.breaking(item, format!("account `{}` removed", name)),
+                }
+                continue;
+            }
+        };
+        match (old_account.is_mut, new_account.is_mut) {
+            (false, true) => diff.breaking(item, format!("account `{}` is now writable", name)),
+            (true, false) => {
+                diff.compatible(item, format!("account `{}` is no longer writable", name))
+            }
+            _ => {}
+        }
+        match (old_account.is_signer, new_account.is_signer) {
+            (false, true) => diff.breaking(item, format!("account `{}` is now a signer", name)),
+            (true, false) => {
+                diff.compatible(item, format!("account `{}` is no longer a signer", name))
+            }
+            _ => {}
+        }
+        match (
+            old_account.is_optional.unwrap_or(false),
+            new_account.is_optional.unwrap_or(false),
+        ) {
+            (false, true) => diff.compatible(item, format!("account `{}` is now optional", name)),
+            (true, false) => {
+                diff.breaking(item, format!("account `{}` is no longer optional", name))
+            }
+            _ => {}
+        }
+    }
+    for (index, (name, _)) in new_accounts.iter().enumerate() {
+        if !old_accounts.iter().any(|(old_name, _)| old_name == name) && !renamed.contains(&index) {
+            diff.breaking(
+                item,
+                format!("account `{}` added at position {}", name, index),
+            );
+        }
+    }
+}
+
+fn diff_instructions(
+    diff: &mut IdlDiff,
+    ctx: &DiffContext,
+    old: &[IdlInstruction],
+    new: &[IdlInstruction],
+) {
+    let is_added = |ix: &IdlInstruction| !old.iter().any(|old_ix| old_ix.name == ix.name);
+    let old_discriminator = |index: usize, name: &str| {
+        ctx.old_discriminators
+            .instruction_discriminator(index, name)
+    };
+    let new_discriminator = |index: usize, name: &str| {
+        ctx.new_discriminators
+            .instruction_discriminator(index, name)
+    };
+    let mut renamed = vec![];
+    for (old_index, old_ix) in old.iter().enumerate() {
+        let item = format!("instruction {}", old_ix.name);
+        let old_discriminator = old_discriminator(old_index, &old_ix.name);
+        let (new_index, new_ix) = match new
+            .iter()
+            .enumerate()
+            .find(|(_, ix)| ix.name == old_ix.name)
+        {
+            Some(found) => found,
+            None => {
+                let new_ix = new.iter().enumerate().find(|(_, ix)| {
+                    is_added(ix)
+                        && !renamed.contains(&ix.name)
+                        && ctx
+                            .same_fields(&Field::from_idl(&old_ix.args), &Field::from_idl(&ix.args))
+                        && ix.accounts == old_ix.accounts
+                });
+                match new_ix {
+                    Some((new_index, new_ix)) => {
+                        renamed.push(new_ix.name.clone());
+                        let new_discriminator = new_discriminator(new_index, &new_ix.name);
+                        if old_discriminator == new_discriminator {
+                            diff.compatible(
+                                &item,
+                                format!(
+                                    "renamed to `{}` without changing its discriminator",
+                                    new_ix.name
+                                ),
+                            )
+                        } else {
+                            diff.breaking(
+                                &item,
+                                format!(
+                                    "renamed to `{}`, changing the discriminator from {} to {}",
+                                    new_ix.name,
+                                    format_discriminator(&old_discriminator),
+                                    format_discriminator(&new_discriminator),
+                                ),
+                            )
+                        }
+                    }
+                    None => diff.breaking(&item, "removed".to_string()),
+                }
+                continue;
+            }
+        };
+        let new_discriminator = new_discriminator(new_index, &new_ix.name);
+        if old_discriminator != new_discriminator {
+            let moved = if old_index != new_index {
+                format!("moved from position {} to {}, ", old_index, new_index)
+            } else {
+                String::new()
+            };
+            diff.breaking(
+                &item,
+                format!(
+                    "{}changing the discriminator from {} to {}",
+                    moved,
+                    format_discriminator(&old_discriminator),
+                    format_discriminator(&new_discriminator),
+                ),
+            );
+        }
+        diff_fields(
+            diff,
+            ctx,
+            &item,
+            "argument",
+            &Field::from_idl(&old_ix.args),
+            &Field::from_idl(&new_ix.args),
+        );
+        diff_instruction_accounts(diff, &item, &old_ix.accounts, &new_ix.accounts);
+        match (&old_ix.returns, &new_ix.returns) {
+            (Some(old), Some(new)) if !ctx.same_type(old, new) => diff.breaking(
+                &item,
+                format!(
+                    "return type changed: {}",
+                    ctx.describe_type_change(old, new)
+                ),
+            ),
+            (Some(_), None) => diff.breaking(&item, "no longer returns a value".to_string()),
+            (None, Some(new)) => {
+                diff.compatible(&item, format!("now returns `{}`", ty_to_rust_type(new)))
+            }
+            _ => {}
+        }
+    }
+    for (new_index, new_ix) in new.iter().enumerate() {
+        if is_added(new_ix) && !renamed.contains(&new_ix.name) {
+            diff.compatible(
+                &format!("instruction {}", new_ix.name),
+                format!(
+                    "added with discriminator {}",
+                    format_discriminator(&new_discriminator(new_index, &new_ix.name))
+                ),
+            );
+        }
+    }
+}
+
+fn diff_events(diff: &mut IdlDiff, ctx: &DiffContext, old: &[IdlEvent], new: &[IdlEvent]) {
+    let is_added = |event: &IdlEvent| !old.iter().any(|old_event| old_event.name == event.name);
+    let mut renamed = vec![];
+    for old_event in old {
+        let item = format!("event {}", old_event.name);
+        let old_fields = Field::from_event(old_event);
+        match new.iter().find(|event| event.name == old_event.name) {
+            Some(new_event) => diff_fields(
+                diff,
+                ctx,
+                &item,
+                "field",
+                &old_fields,
+                &Field::from_event(new_event),
+            ),
+            None => {
+                let new_event = new.iter().find(|event| {
+                    is_added(event)
+                        && !renamed.contains(&event.name)
+                        && ctx.same_fields(&old_fields, &Field::from_event(event))
+                });
+                match new_event {
+                    Some(new_event) => {
+                        renamed.push(new_event.name.clone());
+                        diff.breaking(
+                            &item,
+                            format!(
+                                "renamed to `{}`, changing the discriminator from {} to {}",
+                                new_event.name,
+                                format_discriminator(&anchor_discriminator(
+                                    "event",
+                                    &old_event.name
+                                )),
+                                format_discriminator(&anchor_discriminator(
+                                    "event",
+                                    &new_event.name
+                                )),
+                            ),
+                        )
+                    }
+                    None => diff.breaking(&item, "removed".to_string()),
+                }
+            }
+        }
+    }
+    for new_event in new {
+        if is_added(new_event) && !renamed.contains(&new_event.name) {
+            diff.compatible(&format!("event {}", new_event.name), "added".to_string());
+        }
+    }
+}
+
+fn diff_errors(
+    diff: &mut IdlDiff,
+    old: &[anchor_syn::idl::IdlErrorCode],
+    new: &[anchor_syn::idl::IdlErrorCode],
+) {
+    for old_error in old {
+        let item = format!("error {}", old_error.code);
+        match new.iter().find(|error| error.code == old_error.code) {
+            Some(new_error) => {
+                if new_error.name != old_error.name {
+                    diff.breaking(
+                        &item,
+                        format!("renamed from `{}` to `{}`", old_error.name, new_error.name),
+                    );
+                }
+                if new_error.msg != old_error.msg {
+                    diff.compatible(
+                        &item,
+                        format!(
+                            "message changed from {:?} to {:?}",
+                            old_error.msg.as_deref().unwrap_or_default(),
+                            new_error.msg.as_deref().unwrap_or_default()
+                        ),
+                    );
+                }
+            }
+            None => diff.breaking(&item, format!("`{}` removed", old_error.name)),
+        }
+    }
+    for new_error in new {
+        if !old.iter().any(|error| error.code == new_error.code) {
+            diff.compatible(
+                &format!("error {}", new_error.code),
+                format!("`{}` added", new_error.name),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn base() -> Value {
+        json!({
+            "version": "0.1.0",
+            "name": "vault",
+            "instructions": [
+                {
+                    "name": "deposit",
+                    "accounts": [
+                        { "name": "vault", "isMut": true, "isSigner": false },
+                        { "name": "owner", "isMut": false, "isSigner": true }
+                    ],
+                    "args": [{ "name": "amount", "type": "u64" }]
+                },
+                {
+                    "name": "withdraw",
+                    "accounts": [
+                        { "name": "vault", "isMut": true, "isSigner": false },
+                        { "name": "owner", "isMut": false, "isSigner": true }
+                    ],
+                    "args": [{ "name": "amount", "type": "u64" }, { "name": "all", "type": "bool" }]
+                }
+            ],
+            "accounts": [{
+                "name": "Vault",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "owner", "type": "publicKey" },
+                        { "name": "amount", "type": "u64" },
+                        { "name": "bump", "type": "u8" },
+                        { "name": "limits", "type": { "defined": "Limits" } }
+                    ]
+                }
+            }],
+            "types": [
+                {
+                    "name": "Side",
+                    "type": { "kind": "enum", "variants": [{ "name": "Bid" }, { "name": "Ask" }] }
+                },
+                {
+                    "name": "Limits",
+                    "type": { "kind": "struct", "fields": [{ "name": "max", "type": "u32" }] }
+                }
+            ],
+            "events": [{
+                "name": "Deposited",
+                "fields": [
+                    { "name": "vault", "type": "publicKey", "index": false },
+                    { "name": "amount", "type": "u64", "index": false }
+                ]
+            }],
+            "errors": [
+                { "code": 6000, "name": "InsufficientFunds", "msg": "Insufficient funds" },
+                { "code": 6001, "name": "Locked" }
+            ]
+        })
+    }
+
+    /// The changes from the base IDL to the IDL changed by `change`.
+    fn changes(change: impl FnOnce(&mut Value)) -> Vec<String> {
+        changes_from(base(), change)
+    }
+
+    fn changes_from(old: Value, change: impl FnOnce(&mut Value)) -> Vec<String> {
+        let mut new = old.clone();
+        change(&mut new);
+        diff_idls(
+            &serde_json::from_value(old).unwrap(),
+            &serde_json::from_value(new).unwrap(),
+        )
+        .changes
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    }
+
+    fn fields(value: &mut Value) -> &mut Vec<Value> {
+        value["type"]["fields"].as_array_mut().unwrap()
+    }
+
+    #[test]
+    fn test_unchanged() {
+        assert!(changes(|_| {}).is_empty());
+    }
+
+    #[test]
+    fn test_field_reorder() {
+        let changes = changes(|idl| fields(&mut idl["accounts"][0]).swap(1, 2));
+        assert_eq!(
+            changes,
+            [
+                "breaking: account Vault: field `amount` moved from position 1 to 2",
+                "breaking: account Vault: field `bump` moved from position 2 to 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_type_change() {
+        let changes = changes(|idl| {
+            idl["types"][1]["type"]["fields"][0]["type"] = json!("u64");
+            idl["accounts"][0]["type"]["fields"][1]["type"] = json!("i64");
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: account Vault: field `amount` changed type: `u64` -> `i64`",
+                "breaking: type Limits: field `max` changed type: `u32` -> `u64` (4 -> 8 bytes)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fields_added_and_removed() {
+        let changes = changes(|idl| {
+            fields(&mut idl["accounts"][0]).push(json!({ "name": "paused", "type": "bool" }));
+            idl["instructions"][1]["args"].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: account Vault: field `paused` of type `bool` added at position 4",
+                "breaking: instruction withdraw: argument `all` removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_flags() {
+        let changes = changes(|idl| {
+            idl["instructions"][0]["accounts"][0]["isSigner"] = json!(true);
+            idl["instructions"][0]["accounts"][1]["isMut"] = json!(true);
+            idl["instructions"][1]["accounts"][0]["isMut"] = json!(false);
+            idl["instructions"][1]["accounts"][1]["isSigner"] = json!(false);
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: instruction deposit: account `vault` is now a signer",
+                "breaking: instruction deposit: account `owner` is now writable",
+                "compatible: instruction withdraw: account `vault` is no longer writable",
+                "compatible: instruction withdraw: account `owner` is no longer a signer",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instruction_accounts_reordered() {
+        let changes = changes(|idl| {
+            idl["instructions"][0]["accounts"]
+                .as_array_mut()
+                .unwrap()
+                .swap(0, 1)
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: instruction deposit: account `vault` moved from position 0 to 1",
+                "breaking: instruction deposit: account `owner` moved from position 1 to 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enum_variants_removed() {
+        let changes = changes(|idl| {
+            idl["types"][0]["type"]["variants"]
+                .as_array_mut()
+                .unwrap()
+                .remove(0);
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: type Side: variant `Bid` removed",
+                "breaking: type Side: variant `Ask` moved from index 1 to 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enum_variants_reordered() {
+        let changes = changes(|idl| {
+            idl["types"][0]["type"]["variants"]
+                .as_array_mut()
+                .unwrap()
+                .swap(0, 1)
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: type Side: variant `Bid` moved from index 0 to 1",
+                "breaking: type Side: variant `Ask` moved from index 1 to 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_changes() {
+        let changed = changes(|idl| idl["events"][0]["fields"][1]["type"] = json!("u32"));
+        assert_eq!(
+            changed,
+            ["breaking: event Deposited: field `amount` changed type: `u64` -> `u32` (8 -> 4 bytes)"]
+        );
+
+        let renamed = changes(|idl| idl["events"][0]["name"] = json!("VaultDeposited"));
+        assert_eq!(renamed.len(), 1);
+        assert!(renamed[0].starts_with(
+            "breaking: event Deposited: renamed to `VaultDeposited`, changing the discriminator from"
+        ));
+    }
+
+    #[test]
+    fn test_error_changes() {
+        let changes = changes(|idl| {
+            idl["errors"][0]["name"] = json!("NotEnoughFunds");
+            idl["errors"][0]["msg"] = json!("Not enough funds");
+            idl["errors"].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: error 6000: renamed from `InsufficientFunds` to `NotEnoughFunds`",
+                "compatible: error 6000: message changed from \"Insufficient funds\" to \"Not enough funds\"",
+                "breaking: error 6001: `Locked` removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_renames() {
+        let changes = changes(|idl| {
+            idl["types"][1]["name"] = json!("Caps");
+            idl["accounts"][0]["type"]["fields"][3]["type"] = json!({ "defined": "Caps" });
+            idl["accounts"][0]["type"]["fields"][2]["name"] = json!("seed");
+            idl["instructions"][0]["name"] = json!("add");
+        });
+        assert_eq!(
+            changes,
+            [
+                "compatible: account Vault: field `bump` renamed to `seed`",
+                "compatible: type Limits: renamed to `Caps` without changing its layout",
+                "breaking: instruction deposit: renamed to `add`, changing the discriminator from \
+                 f223c68952e1f2b6 to 29f9f992c56f38b5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_rename() {
+        let changes = changes(|idl| idl["accounts"][0]["name"] = json!("Safe"));
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].starts_with(
+            "breaking: account Vault: renamed to `Safe`, changing the discriminator from"
+        ));
+    }
+
+    #[test]
+    fn test_compatible_changes_are_not_breaking() {
+        let old: Idl = serde_json::from_value(base()).unwrap();
+        let mut new = base();
+        new["instructions"].as_array_mut().unwrap().push(json!({
+            "name": "close",
+            "accounts": [{ "name": "vault", "isMut": true, "isSigner": false }],
+            "args": []
+        }));
+        new["instructions"][1]["returns"] = json!("u64");
+        new["types"].as_array_mut().unwrap().push(json!({
+            "name": "Fees",
+            "type": { "kind": "struct", "fields": [{ "name": "bps", "type": "u16" }] }
+        }));
+        new["types"][0]["type"]["variants"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "name": "Cross" }));
+        new["errors"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "code": 6002, "name": "Closed" }));
+        let diff = diff_idls(&old, &serde_json::from_value(new).unwrap());
+        assert!(!diff.is_breaking(), "{:?}", diff);
+        assert_eq!(diff.changes.len(), 5);
+    }
+
+    #[test]
+    fn test_anchor_instruction_order() {
+        assert!(changes(|idl| idl["instructions"].as_array_mut().unwrap().swap(0, 1)).is_empty());
+    }
+
+    #[test]
+    fn test_shank_instruction_order() {
+        let mut shank = base();
+        shank["metadata"] = json!({ "origin": "shank" });
+        let changes = changes_from(shank, |idl| {
+            idl["instructions"].as_array_mut().unwrap().swap(0, 1)
+        });
+        assert_eq!(
+            changes,
+            [
+                "breaking: instruction deposit: moved from position 0 to 1, changing the \
+                 discriminator from 00 to 01",
+                "breaking: instruction withdraw: moved from position 1 to 0, changing the \
+                 discriminator from 01 to 00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shank_renames() {
+        let mut shank = base();
+        shank["metadata"] = json!({ "origin": "shank" });
+        let changes = changes_from(shank, |idl| {
+            idl["accounts"][0]["name"] = json!("Safe");
+            idl["instructions"][0]["name"] = json!("add");
+        });
+        assert_eq!(
+            changes,
+            [
+                "compatible: account Vault: renamed to `Safe` without changing its layout",
+                "compatible: instruction deposit: renamed to `add` without changing its \
+                 discriminator",
+            ]
+        );
+    }
+}
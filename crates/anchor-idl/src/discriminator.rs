@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::Idl;
+use darling::FromMeta;
+use heck::ToSnakeCase;
+
+/// Computes the 8-byte discriminator Anchor derives from a namespace and a name.
+pub fn anchor_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let preimage = format!("{}:{}", namespace, name);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&anchor_syn::hash::hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// How a program tells its instructions and accounts apart in their serialized data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiscriminatorStrategy {
+    /// Instructions and accounts are prefixed with the first 8 bytes of the hash of their name,
+    /// as in Anchor programs.
+    Anchor,
+    /// Instructions are prefixed with a `u8`, and accounts have no prefix, as in native programs
+    /// whose IDL is generated by Shank.
+    Shank {
+        /// Discriminants of the instructions which are not numbered by their position, by name.
+        discriminants: BTreeMap<String, u8>,
+    },
+}
+
+impl FromMeta for DiscriminatorStrategy {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "anchor" => Ok(DiscriminatorStrategy::Anchor),
+            "shank" => Ok(DiscriminatorStrategy::Shank {
+                discriminants: BTreeMap::new(),
+            }),
+            _ => Err(darling::Error::unknown_value(value)),
+        }
+    }
+}
+
+impl DiscriminatorStrategy {
+    /// Maximum number of instructions a program can have.
+    pub fn max_instructions(&self) -> Option<usize> {
+        match self {
+            DiscriminatorStrategy::Anchor => None,
+            DiscriminatorStrategy::Shank { .. } => Some(u8::MAX as usize + 1),
+        }
+    }
+
+    /// Strategy of the program an IDL was generated from: [DiscriminatorStrategy::Shank] if its
+    /// `metadata.origin` is `"shank"`, or else [DiscriminatorStrategy::Anchor].
+    ///
+    /// ```
+    /// let idl: anchor_idl::Idl = serde_json::from_str(r#"{
+    ///     "version": "0.1.0",
+    ///     "name": "counter",
+    ///     "instructions": [],
+    ///     "metadata": { "origin": "shank" }
+    /// }"#).unwrap();
+    /// assert_eq!(
+    ///     anchor_idl::DiscriminatorStrategy::detect(&idl),
+    ///     anchor_idl::DiscriminatorStrategy::shank(&idl)
+    /// );
+    /// ```
+    pub fn detect(idl: &Idl) -> Self {
+        let origin = idl
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("origin"))
+            .and_then(|origin| origin.as_str());
+        match origin {
+            Some("shank") => DiscriminatorStrategy::shank(idl),
+            _ => DiscriminatorStrategy::Anchor,
+        }
+    }
+
+    /// [DiscriminatorStrategy::Shank] with the discriminants recorded in `metadata.discriminants`
+    /// by [parse_idl_json](crate::parse_idl_json), for the instructions which are not numbered
+    /// by their position.
+    pub fn shank(idl: &Idl) -> Self {
+        let discriminants = idl
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("discriminants"))
+            .and_then(|discriminants| discriminants.as_object())
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, value)| {
+                let value = u8::try_from(value.as_u64()?).ok()?;
+                Some((name.clone(), value))
+            })
+            .collect();
+        DiscriminatorStrategy::Shank { discriminants }
+    }
+
+    /// Discriminator of the instruction `name`, at position `index` in the IDL.
+    ///
+    /// Shank numbers instructions by the position of their variant in the instruction enum,
+    /// which is their position in the IDL unless the variant has an explicit discriminant.
+    pub fn instruction_discriminator(&self, index: usize, name: &str) -> Vec<u8> {
+        match self {
+            DiscriminatorStrategy::Anchor => {
+                anchor_discriminator("global", &name.to_snake_case()).to_vec()
+            }
+            DiscriminatorStrategy::Shank { discriminants } => {
+                vec![discriminants.get(name).copied().unwrap_or(index as u8)]
+            }
+        }
+    }
+
+    /// Discriminator prefixing the data of the account `name`.
+    pub fn account_discriminator(&self, name: &str) -> Vec<u8> {
+        match self {
+            DiscriminatorStrategy::Anchor => anchor_discriminator("account", name).to_vec(),
+            DiscriminatorStrategy::Shank { .. } => vec![],
+        }
+    }
+
+    /// Length in bytes of the discriminator prefixing the data of accounts.
+    pub fn account_discriminator_len(&self) -> usize {
+        match self {
+            DiscriminatorStrategy::Anchor => 8,
+            DiscriminatorStrategy::Shank { .. } => 0,
+        }
+    }
+}
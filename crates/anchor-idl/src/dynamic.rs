@@ -0,0 +1,1040 @@
+//! Encodes and decodes the accounts, instructions, events and types of any program at runtime,
+//! from its parsed [Idl], for tools which cannot generate code for every program they handle.
+//!
+//! Values are represented by the self-describing [Value] tree, which converts to and from JSON.
+//! The layout is the Borsh serialization of the generated types, with the discriminators of the
+//! [DiscriminatorStrategy] of the IDL, so that both agree on every value. Zero copy accounts
+//! are decoded the same way, which matches their in-memory layout if they are packed.
+//!
+//! ```
+//! use anchor_idl::dynamic::IdlCodec;
+//!
+//! let idl: anchor_idl::Idl = serde_json::from_str(include_str!(
+//!     "../../../examples/govern-cpi/idl.json"
+//! )).unwrap();
+//! let codec = IdlCodec::new(&idl);
+//!
+//! let args = codec.instruction_args_from_json("createGovernor", &serde_json::json!({
+//!     "bump": 254,
+//!     "electorate": "Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw",
+//!     "params": {
+//!         "votingDelay": 0,
+//!         "votingPeriod": 86400,
+//!         "quorumVotes": "1000000",
+//!         "timelockDelaySeconds": 3600,
+//!     },
+//! })).unwrap();
+//! let data = codec.encode_instruction("createGovernor", &args).unwrap();
+//! assert_eq!(data[..8], anchor_idl::anchor_discriminator("global", "create_governor"));
+//!
+//! let decoded = codec.decode_instruction(&data).unwrap();
+//! assert_eq!(decoded.name, "createGovernor");
+//! assert_eq!(decoded.value, args);
+//! assert_eq!(decoded.value.to_json()["params"]["votingPeriod"], 86400);
+//! ```
+
+use std::fmt;
+
+use anchor_syn::idl::{
+    EnumFields, Idl, IdlEnumVariant, IdlInstruction, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
+};
+use heck::ToSnakeCase;
+use serde_json::{json, Map};
+
+use crate::{anchor_discriminator, decode_base58, DiscriminatorStrategy, TypeTable};
+
+/// A value of a type of an IDL.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    PublicKey([u8; 32]),
+    Option(Option<Box<Value>>),
+    /// Elements of a vector or an array.
+    List(Vec<Value>),
+    /// Fields of a struct, or of an enum variant with named fields, in the order of the IDL.
+    Struct(Vec<(String, Value)>),
+    /// Fields of an enum variant with unnamed fields.
+    Tuple(Vec<Value>),
+    /// A variant of an enum, with its payload if it has fields.
+    Enum {
+        variant: String,
+        payload: Option<Box<Value>>,
+    },
+}
+
+impl Value {
+    /// Field `name` of a struct.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Struct(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Converts the value to JSON.
+    ///
+    /// Integers of up to 64 bits are numbers, and larger ones are strings, since JSON parsers
+    /// commonly lose their precision. Bytes are arrays of numbers, public keys are base58 strings,
+    /// `None` is `null` and enums are objects with their variant as the only key, e.g.
+    /// `{ "Buy": {} }` or `{ "Limit": { "price": 10 } }`.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Bool(value) => json!(value),
+            Value::U8(value) => json!(value),
+            Value::I8(value) => json!(value),
+            Value::U16(value) => json!(value),
+            Value::I16(value) => json!(value),
+            Value::U32(value) => json!(value),
+            Value::I32(value) => json!(value),
+            Value::U64(value) => json!(value),
+            Value::I64(value) => json!(value),
+            Value::U128(value) => json!(value.to_string()),
+            Value::I128(value) => json!(value.to_string()),
+            Value::F32(value) => float_to_json(*value as f64),
+            Value::F64(value) => float_to_json(*value),
+            Value::String(value) => json!(value),
+            Value::Bytes(value) => json!(value),
+            Value::PublicKey(value) => json!(encode_base58(value)),
+            Value::Option(value) => value
+                .as_ref()
+                .map_or(serde_json::Value::Null, |value| value.to_json()),
+            Value::List(values) | Value::Tuple(values) => {
+                serde_json::Value::Array(values.iter().map(Value::to_json).collect())
+            }
+            Value::Struct(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect(),
+            ),
+            Value::Enum { variant, payload } => {
+                let payload = payload
+                    .as_ref()
+                    .map_or_else(|| json!({}), |payload| payload.to_json());
+                json!({ variant: payload })
+            }
+        }
+    }
+
+    /// Name of the kind of value, for error messages.
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "a bool",
+            Value::U8(_) => "a u8",
+            Value::I8(_) => "an i8",
+            Value::U16(_) => "a u16",
+            Value::I16(_) => "an i16",
+            Value::U32(_) => "a u32",
+            Value::I32(_) => "an i32",
+            Value::U64(_) => "a u64",
+            Value::I64(_) => "an i64",
+            Value::U128(_) => "a u128",
+            Value::I128(_) => "an i128",
+            Value::F32(_) => "an f32",
+            Value::F64(_) => "an f64",
+            Value::String(_) => "a string",
+            Value::Bytes(_) => "bytes",
+            Value::PublicKey(_) => "a public key",
+            Value::Option(_) => "an option",
+            Value::List(_) => "a list",
+            Value::Struct(_) => "a struct",
+            Value::Tuple(_) => "a tuple",
+            Value::Enum { .. } => "an enum",
+        }
+    }
+}
+
+/// Infinite floats, which JSON numbers cannot hold, are written as strings.
+fn float_to_json(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value)
+        .map_or_else(|| json!(value.to_string()), serde_json::Value::Number)
+}
+
+/// An account, instruction or event, identified by its discriminator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoded {
+    /// Name of the account, instruction or event in the IDL.
+    pub name: String,
+    /// Fields of the account or event, or arguments of the instruction, as a [Value::Struct].
+    pub value: Value,
+}
+
+/// An error encoding or decoding a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodecError {
+    /// Path of the offending value, e.g. `swap.amount` or `Whirlpool.rewardInfos[1].mint`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+fn error(path: &str, message: impl Into<String>) -> CodecError {
+    CodecError {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Fields of a struct, an instruction or an event, by name.
+type Fields<'a> = Vec<(&'a str, &'a IdlType)>;
+
+/// Encodes and decodes the values of the types of an [Idl].
+pub struct IdlCodec<'a> {
+    idl: &'a Idl,
+    types: TypeTable<'a>,
+    discriminators: DiscriminatorStrategy,
+}
+
+impl<'a> IdlCodec<'a> {
+    /// Codec of an IDL, with the discriminators of the program it was generated from, see
+    /// [DiscriminatorStrategy::detect].
+    pub fn new(idl: &'a Idl) -> Self {
+        Self::with_discriminators(idl, DiscriminatorStrategy::detect(idl))
+    }
+
+    /// Codec of an IDL, with the given discriminators.
+    pub fn with_discriminators(idl: &'a Idl, discriminators: DiscriminatorStrategy) -> Self {
+        Self {
+            idl,
+            types: TypeTable::new(&idl.types),
+            discriminators,
+        }
+    }
+
+    /// Decodes a value of type `ty` from the start of `data`, which is advanced past it.
+    pub fn decode(&self, ty: &IdlType, data: &mut &[u8]) -> Result<Value, CodecError> {
+        self.decode_ty(ty, data, "")
+    }
+
+    /// Encodes a value of type `ty`.
+    pub fn encode(&self, ty: &IdlType, value: &Value) -> Result<Vec<u8>, CodecError> {
+        let mut out = vec![];
+        self.encode_ty(ty, value, &mut out, "")?;
+        Ok(out)
+    }
+
+    /// Converts JSON to a value of type `ty`, see [Value::to_json].
+    ///
+    /// Integers may also be given as strings, and unit enum variants as their name.
+    pub fn from_json(&self, ty: &IdlType, json: &serde_json::Value) -> Result<Value, CodecError> {
+        self.ty_from_json(ty, json, "")
+    }
+
+    /// Decodes an account, identified by its discriminator. Bytes following the account are
+    /// ignored, as accounts may be allocated more space than they use.
+    ///
+    /// Accounts of programs using Shank discriminators cannot be identified, see
+    /// [Self::decode_account_as].
+    pub fn decode_account(&self, data: &[u8]) -> Result<Decoded, CodecError> {
+        if let DiscriminatorStrategy::Shank { .. } = self.discriminators {
+            return Err(error(
+                "",
+                "accounts of programs using Shank discriminators have no discriminator, so the \
+                 account must be named",
+            ));
+        }
+        let account = self
+            .idl
+            .accounts
+            .iter()
+            .find(|account| {
+                data.starts_with(&self.discriminators.account_discriminator(&account.name))
+            })
+            .ok_or_else(|| {
+                error(
+                    "",
+                    "the data does not start with the discriminator of an account",
+                )
+            })?;
+        Ok(Decoded {
+            name: account.name.clone(),
+            value: self.decode_account_as(&account.name, data)?,
+        })
+    }
+
+    /// Decodes the account `name`, checking its discriminator.
+    pub fn decode_account_as(&self, name: &str, data: &[u8]) -> Result<Value, CodecError> {
+        let fields = self.account_fields(name)?;
+        let discriminator = self.discriminators.account_discriminator(name);
+        let mut data = data.strip_prefix(&discriminator[..]).ok_or_else(|| {
+            error(
+                name,
+                "the data does not start with the discriminator of the account",
+            )
+        })?;
+        self.decode_fields(&fields, &mut data, name)
+    }
+
+    /// Encodes the account `name`, including its discriminator.
+    pub fn encode_account(&self, name: &str, value: &Value) -> Result<Vec<u8>, CodecError> {
+        let fields = self.account_fields(name)?;
+        let mut out = self.discriminators.account_discriminator(name);
+        self.encode_fields(&fields, value, &mut out, name)?;
+        Ok(out)
+    }
+
+    /// Converts JSON to the fields of the account `name`.
+    pub fn account_from_json(
+        &self,
+        name: &str,
+        json: &serde_json::Value,
+    ) -> Result<Value, CodecError> {
+        self.fields_from_json(&self.account_fields(name)?, json, name)
+    }
+
+    /// Decodes the arguments of an instruction, identified by its discriminator. Bytes following
+    /// the arguments are ignored, as they are by Anchor.
+    pub fn decode_instruction(&self, data: &[u8]) -> Result<Decoded, CodecError> {
+        let (index, ix) = self
+            .idl
+            .instructions
+            .iter()
+            .enumerate()
+            .find(|(index, ix)| {
+                data.starts_with(
+                    &self
+                        .discriminators
+                        .instruction_discriminator(*index, &ix.name),
+                )
+            })
+            .ok_or_else(|| {
+                error(
+                    "",
+                    "the data does not start with the discriminator of an instruction",
+                )
+            })?;
+        let discriminator = self
+            .discriminators
+            .instruction_discriminator(index, &ix.name);
+        let fields = ix
+            .args
+            .iter()
+            .map(|arg| (arg.name.as_str(), &arg.ty))
+            .collect::<Vec<_>>();
+        let mut data = &data[discriminator.len()..];
+        Ok(Decoded {
+            name: ix.name.clone(),
+            value: self.decode_fields(&fields, &mut data, &ix.name)?,
+        })
+    }
+
+    /// Encodes the arguments of the instruction `name`, including its discriminator.
+    ///
+    /// Instructions are named as in the IDL, or in snake case.
+    pub fn encode_instruction(&self, name: &str, args: &Value) -> Result<Vec<u8>, CodecError> {
+        let (index, fields) = self.instruction_fields(name)?;
+        let ix_name = &self.idl.instructions[index].name;
+        let mut out = self
+            .discriminators
+            .instruction_discriminator(index, ix_name);
+        self.encode_fields(&fields, args, &mut out, ix_name)?;
+        Ok(out)
+    }
+
+    /// Converts JSON to the arguments of the instruction `name`.
+    pub fn instruction_args_from_json(
+        &self,
+        name: &str,
+        json: &serde_json::Value,
+    ) -> Result<Value, CodecError> {
+        let (index, fields) = self.instruction_fields(name)?;
+        self.fields_from_json(&fields, json, &self.idl.instructions[index].name)
+    }
+
+    /// Decodes an event from its discriminator followed by its fields, as logged by `emit!`.
+    pub fn decode_event(&self, data: &[u8]) -> Result<Decoded, CodecError> {
+        let event = self
+            .idl
+            .events
+            .iter()
+            .flatten()
+            .find(|event| data.starts_with(&anchor_discriminator("event", &event.name)))
+            .ok_or_else(|| {
+                error(
+                    "",
+                    "the data does not start with the discriminator of an event",
+                )
+            })?;
+        let fields = self.event_fields(&event.name)?;
+        let mut data = &data[8..];
+        Ok(Decoded {
+            name: event.name.clone(),
+            value: self.decode_fields(&fields, &mut data, &event.name)?,
+        })
+    }
+
+    /// Encodes the event `name`, including its discriminator.
+    pub fn encode_event(&self, name: &str, value: &Value) -> Result<Vec<u8>, CodecError> {
+        let fields = self.event_fields(name)?;
+        let mut out = anchor_discriminator("event", name).to_vec();
+        self.encode_fields(&fields, value, &mut out, name)?;
+        Ok(out)
+    }
+
+    /// Converts JSON to the fields of the event `name`.
+    pub fn event_from_json(
+        &self,
+        name: &str,
+        json: &serde_json::Value,
+    ) -> Result<Value, CodecError> {
+        self.fields_from_json(&self.event_fields(name)?, json, name)
+    }
+
+    /// The instruction `name`, named as in the IDL or in snake case.
+    pub fn instruction(&self, name: &str) -> Result<&'a IdlInstruction, CodecError> {
+        self.find_instruction(name).map(|(_, ix)| ix)
+    }
+
+    fn account_fields(&self, name: &str) -> Result<Fields<'a>, CodecError> {
+        let account = self
+            .idl
+            .accounts
+            .iter()
+            .find(|account| account.name == name)
+            .ok_or_else(|| error(name, "no such account"))?;
+        match &account.ty {
+            IdlTypeDefinitionTy::Struct { fields } => Ok(fields
+                .iter()
+                .map(|field| (field.name.as_str(), &field.ty))
+                .collect()),
+            IdlTypeDefinitionTy::Enum { .. } => Err(error(name, "accounts must be structs")),
+        }
+    }
+
+    fn find_instruction(&self, name: &str) -> Result<(usize, &'a IdlInstruction), CodecError> {
+        let snake_name = name.to_snake_case();
+        self.idl
+            .instructions
+            .iter()
+            .enumerate()
+            .find(|(_, ix)| ix.name == name || ix.name.to_snake_case() == snake_name)
+            .ok_or_else(|| error(name, "no such instruction"))
+    }
+
+    fn instruction_fields(&self, name: &str) -> Result<(usize, Fields<'a>), CodecError> {
+        let (index, ix) = self.find_instruction(name)?;
+        let fields = ix
+            .args
+            .iter()
+            .map(|arg| (arg.name.as_str(), &arg.ty))
+            .collect();
+        Ok((index, fields))
+    }
+
+    fn event_fields(&self, name: &str) -> Result<Fields<'a>, CodecError> {
+        let event = self
+            .idl
+            .events
+            .iter()
+            .flatten()
+            .find(|event| event.name == name)
+            .ok_or_else(|| error(name, "no such event"))?;
+        Ok(event
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), &field.ty))
+            .collect())
+    }
+
+    fn decode_ty(&self, ty: &IdlType, data: &mut &[u8], path: &str) -> Result<Value, CodecError> {
+        Ok(match ty {
+            IdlType::Bool => match read_array::<1>(data, path)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                [other] => return Err(error(path, format!("invalid bool {}", other))),
+            },
+            IdlType::U8 => Value::U8(u8::from_le_bytes(read_array(data, path)?)),
+            IdlType::I8 => Value::I8(i8::from_le_bytes(read_array(data, path)?)),
+            IdlType::U16 => Value::U16(u16::from_le_bytes(read_array(data, path)?)),
+            IdlType::I16 => Value::I16(i16::from_le_bytes(read_array(data, path)?)),
+            IdlType::U32 => Value::U32(u32::from_le_bytes(read_array(data, path)?)),
+            IdlType::I32 => Value::I32(i32::from_le_bytes(read_array(data, path)?)),
+            IdlType::U64 => Value::U64(u64::from_le_bytes(read_array(data, path)?)),
+            IdlType::I64 => Value::I64(i64::from_le_bytes(read_array(data, path)?)),
+            IdlType::U128 => Value::U128(u128::from_le_bytes(read_array(data, path)?)),
+            IdlType::I128 => Value::I128(i128::from_le_bytes(read_array(data, path)?)),
+            // Borsh refuses NaNs, since they have several representations
+            IdlType::F32 => match f32::from_le_bytes(read_array(data, path)?) {
+                value if value.is_nan() => return Err(error(path, "NaN is not allowed")),
+                value => Value::F32(value),
+            },
+            IdlType::F64 => match f64::from_le_bytes(read_array(data, path)?) {
+                value if value.is_nan() => return Err(error(path, "NaN is not allowed")),
+                value => Value::F64(value),
+            },
+            IdlType::U256 | IdlType::I256 => return Err(unsupported(ty, path)),
+            IdlType::PublicKey => Value::PublicKey(read_array(data, path)?),
+            IdlType::Bytes => {
+                let len = read_len(data, path)?;
+                Value::Bytes(read_bytes(data, len, path)?.to_vec())
+            }
+            IdlType::String => {
+                let len = read_len(data, path)?;
+                let bytes = read_bytes(data, len, path)?;
+                Value::String(
+                    String::from_utf8(bytes.to_vec()).map_err(|_| error(path, "invalid UTF-8"))?,
+                )
+            }
+            IdlType::Option(inner) => match read_array::<1>(data, path)? {
+                [0] => Value::Option(None),
+                [1] => Value::Option(Some(Box::new(self.decode_ty(inner, data, path)?))),
+                [other] => return Err(error(path, format!("invalid option tag {}", other))),
+            },
+            IdlType::Vec(inner) => {
+                let len = read_len(data, path)?;
+                // as in Borsh, which would otherwise loop over up to `u32::MAX` empty elements
+                if len > 0 && self.types.fixed_size(inner) == Some(0) {
+                    return Err(error(path, "vectors of empty values are not allowed"));
+                }
+                // the length is not trusted until the elements have been read
+                let mut elements = Vec::with_capacity(len.min(data.len()));
+                for index in 0..len {
+                    elements.push(self.decode_ty(inner, data, &format!("{}[{}]", path, index))?);
+                }
+                Value::List(elements)
+            }
+            IdlType::Array(inner, len) => Value::List(
+                (0..*len)
+                    .map(|index| self.decode_ty(inner, data, &format!("{}[{}]", path, index)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            IdlType::Defined(name) => match &self.defined(name, path)?.ty {
+                IdlTypeDefinitionTy::Struct { fields } => {
+                    let fields = fields
+                        .iter()
+                        .map(|field| (field.name.as_str(), &field.ty))
+                        .collect::<Vec<_>>();
+                    self.decode_fields(&fields, data, path)?
+                }
+                IdlTypeDefinitionTy::Enum { variants } => {
+                    let [index] = read_array::<1>(data, path)?;
+                    let variant = variants.get(index as usize).ok_or_else(|| {
+                        error(
+                            path,
+                            format!("invalid variant index {} of `{}`", index, name),
+                        )
+                    })?;
+                    let path = join(path, &variant.name);
+                    let payload = match &variant.fields {
+                        Some(EnumFields::Named(fields)) => {
+                            let fields = fields
+                                .iter()
+                                .map(|field| (field.name.as_str(), &field.ty))
+                                .collect::<Vec<_>>();
+                            Some(self.decode_fields(&fields, data, &path)?)
+                        }
+                        Some(EnumFields::Tuple(tuple)) => Some(Value::Tuple(
+                            tuple
+                                .iter()
+                                .enumerate()
+                                .map(|(index, ty)| {
+                                    self.decode_ty(ty, data, &format!("{}.{}", path, index))
+                                })
+                                .collect::<Result<_, _>>()?,
+                        )),
+                        None => None,
+                    };
+                    Value::Enum {
+                        variant: variant.name.clone(),
+                        payload: payload.map(Box::new),
+                    }
+                }
+            },
+        })
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[(&str, &IdlType)],
+        data: &mut &[u8],
+        path: &str,
+    ) -> Result<Value, CodecError> {
+        Ok(Value::Struct(
+            fields
+                .iter()
+                .map(|(name, ty)| {
+                    let value = self.decode_ty(ty, data, &join(path, name))?;
+                    Ok((name.to_string(), value))
+                })
+                .collect::<Result<_, CodecError>>()?,
+        ))
+    }
+
+    fn encode_ty(
+        &self,
+        ty: &IdlType,
+        value: &Value,
+        out: &mut Vec<u8>,
+        path: &str,
+    ) -> Result<(), CodecError> {
+        match (ty, value) {
+            (IdlType::Bool, Value::Bool(value)) => out.push(*value as u8),
+            (IdlType::U8, Value::U8(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::I8, Value::I8(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::U16, Value::U16(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::I16, Value::I16(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::U32, Value::U32(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::I32, Value::I32(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::U64, Value::U64(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::I64, Value::I64(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::U128, Value::U128(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::I128, Value::I128(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::F32, Value::F32(value)) if value.is_nan() => {
+                return Err(error(path, "NaN is not allowed"))
+            }
+            (IdlType::F32, Value::F32(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::F64, Value::F64(value)) if value.is_nan() => {
+                return Err(error(path, "NaN is not allowed"))
+            }
+            (IdlType::F64, Value::F64(value)) => out.extend(value.to_le_bytes()),
+            (IdlType::U256 | IdlType::I256, _) => return Err(unsupported(ty, path)),
+            (IdlType::PublicKey, Value::PublicKey(value)) => out.extend(value),
+            (IdlType::Bytes, Value::Bytes(value)) => {
+                write_len(value.len(), out, path)?;
+                out.extend(value);
+            }
+            (IdlType::String, Value::String(value)) => {
+                write_len(value.len(), out, path)?;
+                out.extend(value.as_bytes());
+            }
+            (IdlType::Option(_), Value::Option(None)) => out.push(0),
+            (IdlType::Option(inner), Value::Option(Some(value))) => {
+                out.push(1);
+                self.encode_ty(inner, value, out, path)?;
+            }
+            (IdlType::Vec(inner), Value::List(elements)) => {
+                write_len(elements.len(), out, path)?;
+                for (index, element) in elements.iter().enumerate() {
+                    self.encode_ty(inner, element, out, &format!("{}[{}]", path, index))?;
+                }
+            }
+            (IdlType::Array(inner, len), Value::List(elements)) => {
+                if elements.len() != *len {
+                    return Err(error(
+                        path,
+                        format!("expected {} elements, found {}", len, elements.len()),
+                    ));
+                }
+                for (index, element) in elements.iter().enumerate() {
+                    self.encode_ty(inner, element, out, &format!("{}[{}]", path, index))?;
+                }
+            }
+            (IdlType::Defined(name), value) => match (&self.defined(name, path)?.ty, value) {
+                (IdlTypeDefinitionTy::Struct { fields }, value) => {
+                    let fields = fields
+                        .iter()
+                        .map(|field| (field.name.as_str(), &field.ty))
+                        .collect::<Vec<_>>();
+                    self.encode_fields(&fields, value, out, path)?;
+                }
+                (IdlTypeDefinitionTy::Enum { variants }, Value::Enum { variant, payload }) => {
+                    let (index, def) = find_variant(variants, variant, name, path)?;
+                    out.push(index as u8);
+                    let path = join(path, variant);
+                    match (&def.fields, payload.as_deref()) {
+                        (Some(EnumFields::Named(fields)), Some(payload)) => {
+                            let fields = fields
+                                .iter()
+                                .map(|field| (field.name.as_str(), &field.ty))
+                                .collect::<Vec<_>>();
+                            self.encode_fields(&fields, payload, out, &path)?;
+                        }
+                        (Some(EnumFields::Tuple(tuple)), Some(Value::Tuple(values))) => {
+                            if values.len() != tuple.len() {
+                                return Err(error(
+                                    &path,
+                                    format!(
+                                        "expected {} fields, found {}",
+                                        tuple.len(),
+                                        values.len()
+                                    ),
+                                ));
+                            }
+                            for (index, (ty, value)) in tuple.iter().zip(values).enumerate() {
+                                self.encode_ty(ty, value, out, &format!("{}.{}", path, index))?;
+                            }
+                        }
+                        (None, None) => {}
+                        (None, Some(_)) => return Err(error(&path, "the variant has no fields")),
+                        (Some(_), _) => return Err(error(&path, "missing or invalid fields")),
+                    }
+                }
+                (IdlTypeDefinitionTy::Enum { .. }, value) => {
+                    return Err(mismatch(&format!("`{}`", name), value, path))
+                }
+            },
+            (ty, value) => {
+                return Err(mismatch(
+                    &format!("`{}`", crate::ty_to_rust_type(ty)),
+                    value,
+                    path,
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_fields(
+        &self,
+        fields: &[(&str, &IdlType)],
+        value: &Value,
+        out: &mut Vec<u8>,
+        path: &str,
+    ) -> Result<(), CodecError> {
+        let values = match value {
+            Value::Struct(values) => values,
+            value => return Err(mismatch("a struct", value, path)),
+        };
+        if let Some((unknown, _)) = values
+            .iter()
+            .find(|(name, _)| !fields.iter().any(|(field, _)| field == name))
+        {
+            return Err(error(&join(path, unknown), "unknown field"));
+        }
+        for (name, ty) in fields {
+            let path = join(path, name);
+            let value = value
+                .field(name)
+                .ok_or_else(|| error(&path, "missing field"))?;
+            self.encode_ty(ty, value, out, &path)?;
+        }
+        Ok(())
+    }
+
+    fn ty_from_json(
+        &self,
+        ty: &IdlType,
+        json: &serde_json::Value,
+        path: &str,
+    ) -> Result<Value, CodecError> {
+        Ok(match ty {
+            IdlType::Bool => Value::Bool(
+                json.as_bool()
+                    .ok_or_else(|| error(path, "expected a bool"))?,
+            ),
+            IdlType::U8 => Value::U8(int_from_json(json, path)?),
+            IdlType::I8 => Value::I8(int_from_json(json, path)?),
+            IdlType::U16 => Value::U16(int_from_json(json, path)?),
+            IdlType::I16 => Value::I16(int_from_json(json, path)?),
+            IdlType::U32 => Value::U32(int_from_json(json, path)?),
+            IdlType::I32 => Value::I32(int_from_json(json, path)?),
+            IdlType::U64 => Value::U64(int_from_json(json, path)?),
+            IdlType::I64 => Value::I64(int_from_json(json, path)?),
+            IdlType::U128 => Value::U128(int_from_json(json, path)?),
+            IdlType::I128 => Value::I128(int_from_json(json, path)?),
+            IdlType::F32 => Value::F32(float_from_json(json, path)? as f32),
+            IdlType::F64 => Value::F64(float_from_json(json, path)?),
+            IdlType::U256 | IdlType::I256 => return Err(unsupported(ty, path)),
+            IdlType::PublicKey => {
+                let key = json
+                    .as_str()
+                    .and_then(decode_base58)
+                    .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                    .ok_or_else(|| error(path, "expected a base58 public key"))?;
+                Value::PublicKey(key)
+            }
+            IdlType::Bytes => Value::Bytes(
+                json.as_array()
+                    .ok_or_else(|| error(path, "expected an array of bytes"))?
+                    .iter()
+                    .enumerate()
+                    .map(|(index, byte)| int_from_json(byte, &format!("{}[{}]", path, index)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            IdlType::String => Value::String(
+                json.as_str()
+                    .ok_or_else(|| error(path, "expected a string"))?
+                    .to_string(),
+            ),
+            IdlType::Option(inner) => Value::Option(match json {
+                serde_json::Value::Null => None,
+                json => Some(Box::new(self.ty_from_json(inner, json, path)?)),
+            }),
+            IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+                let elements = json
+                    .as_array()
+                    .ok_or_else(|| error(path, "expected an array"))?;
+                if let IdlType::Array(_, len) = ty {
+                    if elements.len() != *len {
+                        return Err(error(
+                            path,
+                            format!("expected {} elements, found {}", len, elements.len()),
+                        ));
+                    }
+                }
+                Value::List(
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(index, element)| {
+                            self.ty_from_json(inner, element, &format!("{}[{}]", path, index))
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            IdlType::Defined(name) => match &self.defined(name, path)?.ty {
+                IdlTypeDefinitionTy::Struct { fields } => {
+                    let fields = fields
+                        .iter()
+                        .map(|field| (field.name.as_str(), &field.ty))
+                        .collect::<Vec<_>>();
+                    self.fields_from_json(&fields, json, path)?
+                }
+                IdlTypeDefinitionTy::Enum { variants } => {
+                    let (variant, payload) = match json {
+                        serde_json::Value::String(variant) => (variant, None),
+                        serde_json::Value::Object(object) if object.len() == 1 => {
+                            let (variant, payload) = object.iter().next().unwrap();
+                            (variant, Some(payload))
+                        }
+                        _ => {
+                            return Err(error(
+                                path,
+                                "expected an object with the variant as its only key",
+                            ))
+                        }
+                    };
+                    let (_, def) = find_variant(variants, variant, name, path)?;
+                    let path = join(path, variant);
+                    let payload = match (&def.fields, payload) {
+                        (Some(EnumFields::Named(fields)), Some(payload)) => {
+                            let fields = fields
+                                .iter()
+                                .map(|field| (field.name.as_str(), &field.ty))
+                                .collect::<Vec<_>>();
+                            Some(self.fields_from_json(&fields, payload, &path)?)
+                        }
+                        (
+                            Some(EnumFields::Tuple(tuple)),
+                            Some(serde_json::Value::Array(values)),
+                        ) if values.len() == tuple.len() => Some(Value::Tuple(
+                            tuple
+                                .iter()
+                                .zip(values)
+                                .enumerate()
+                                .map(|(index, (ty, value))| {
+                                    self.ty_from_json(ty, value, &format!("{}.{}", path, index))
+                                })
+                                .collect::<Result<_, _>>()?,
+                        )),
+                        (Some(EnumFields::Tuple(tuple)), _) => {
+                            return Err(error(
+                                &path,
+                                format!("expected an array of {} fields", tuple.len()),
+                            ))
+                        }
+                        (Some(EnumFields::Named(_)), None) => {
+                            return Err(error(&path, "missing fields"))
+                        }
+                        (None, None) => None,
+                        (None, Some(serde_json::Value::Object(object))) if object.is_empty() => {
+                            None
+                        }
+                        (None, Some(serde_json::Value::Null)) => None,
+                        (None, Some(_)) => return Err(error(&path, "the variant has no fields")),
+                    };
+                    Value::Enum {
+                        variant: variant.clone(),
+                        payload: payload.map(Box::new),
+                    }
+                }
+            },
+        })
+    }
+
+    fn fields_from_json(
+        &self,
+        fields: &[(&str, &IdlType)],
+        json: &serde_json::Value,
+        path: &str,
+    ) -> Result<Value, CodecError> {
+        let empty = Map::new();
+        let object = match json {
+            serde_json::Value::Object(object) => object,
+            // instructions without arguments
+            serde_json::Value::Null if fields.is_empty() => &empty,
+            _ => return Err(error(path, "expected an object")),
+        };
+        if let Some(unknown) = object
+            .keys()
+            .find(|key| !fields.iter().any(|(field, _)| field == key))
+        {
+            return Err(error(&join(path, unknown), "unknown field"));
+        }
+        Ok(Value::Struct(
+            fields
+                .iter()
+                .map(|(name, ty)| {
+                    let path = join(path, name);
+                    let json = object
+                        .get(*name)
+                        .ok_or_else(|| error(&path, "missing field"))?;
+                    Ok((name.to_string(), self.ty_from_json(ty, json, &path)?))
+                })
+                .collect::<Result<_, CodecError>>()?,
+        ))
+    }
+
+    fn defined(&self, name: &str, path: &str) -> Result<&'a IdlTypeDefinition, CodecError> {
+        self.types
+            .get(name)
+            .ok_or_else(|| error(path, format!("`{}` is not defined by the IDL", name)))
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn find_variant<'v>(
+    variants: &'v [IdlEnumVariant],
+    variant: &str,
+    enum_name: &str,
+    path: &str,
+) -> Result<(usize, &'v IdlEnumVariant), CodecError> {
+    variants
+        .iter()
+        .enumerate()
+        .find(|(_, def)| def.name == variant)
+        .ok_or_else(|| {
+            error(
+                path,
+                format!("`{}` has no variant `{}`", enum_name, variant),
+            )
+        })
+}
+
+fn mismatch(expected: &str, found: &Value, path: &str) -> CodecError {
+    error(
+        path,
+        format!("expected {}, found {}", expected, found.kind()),
+    )
+}
+
+fn unsupported(ty: &IdlType, path: &str) -> CodecError {
+    error(
+        path,
+        format!("`{}` is not supported", crate::ty_to_rust_type(ty)),
+    )
+}
+
+fn read_bytes<'d>(data: &mut &'d [u8], len: usize, path: &str) -> Result<&'d [u8], CodecError> {
+    if data.len() < len {
+        return Err(error(path, "unexpected end of data"));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+fn read_array<const N: usize>(data: &mut &[u8], path: &str) -> Result<[u8; N], CodecError> {
+    Ok(read_bytes(data, N, path)?.try_into().unwrap())
+}
+
+/// Reads the `u32` length prefixing strings, bytes and vectors.
+fn read_len(data: &mut &[u8], path: &str) -> Result<usize, CodecError> {
+    Ok(u32::from_le_bytes(read_array(data, path)?) as usize)
+}
+
+fn write_len(len: usize, out: &mut Vec<u8>, path: &str) -> Result<(), CodecError> {
+    let len = u32::try_from(len).map_err(|_| error(path, "too many elements"))?;
+    out.extend(len.to_le_bytes());
+    Ok(())
+}
+
+/// Reads an integer from a JSON number or string.
+fn int_from_json<T>(json: &serde_json::Value, path: &str) -> Result<T, CodecError>
+where
+    T: TryFrom<i128> + std::str::FromStr,
+{
+    let value = match json {
+        serde_json::Value::Number(number) => number
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| number.as_u64().map(i128::from))
+            .and_then(|value| T::try_from(value).ok()),
+        serde_json::Value::String(value) => value.parse().ok(),
+        _ => None,
+    };
+    value.ok_or_else(|| {
+        error(
+            path,
+            format!(
+                "expected an integer in the range of the type, found {}",
+                json
+            ),
+        )
+    })
+}
+
+fn float_from_json(json: &serde_json::Value, path: &str) -> Result<f64, CodecError> {
+    let value = match json {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(value) => value.parse().ok(),
+        _ => None,
+    };
+    value
+        .filter(|value: &f64| !value.is_nan())
+        .ok_or_else(|| error(path, format!("expected a number, found {}", json)))
+}
+
+fn encode_base58(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // little endian digits in base 58
+    let mut digits: Vec<u8> = vec![];
+    for &byte in data {
+        let mut carry = byte as u32;
+        for digit in &mut digits {
+            carry += *digit as u32 * 256;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    // each leading zero is encoded as a `1`
+    let zeros = data.iter().take_while(|&&byte| byte == 0).count();
+    let mut encoded = "1".repeat(zeros);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from(ALPHABET[digit as usize])),
+    );
+    encoded
+}
@@ -0,0 +1,296 @@
+use anchor_syn::idl::IdlErrorCode;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Variants of `anchor_lang::error::ErrorCode`, the errors of the Anchor framework, as of
+/// anchor-lang 0.28.
+///
+/// The generated code names every variant, so it only compiles against anchor-lang 0.28, which
+/// the crates and examples pin.
+pub const ANCHOR_ERROR_CODES: &[&str] = &[
+    "InstructionMissing",
+    "InstructionFallbackNotFound",
+    "InstructionDidNotDeserialize",
+    "InstructionDidNotSerialize",
+    "IdlInstructionStub",
+    "IdlInstructionInvalidProgram",
+    "IdlAccountNotEmpty",
+    "EventInstructionStub",
+    "ConstraintMut",
+    "ConstraintHasOne",
+    "ConstraintSigner",
+    "ConstraintRaw",
+    "ConstraintOwner",
+    "ConstraintRentExempt",
+    "ConstraintSeeds",
+    "ConstraintExecutable",
+    "ConstraintState",
+    "ConstraintAssociated",
+    "ConstraintAssociatedInit",
+    "ConstraintClose",
+    "ConstraintAddress",
+    "ConstraintZero",
+    "ConstraintTokenMint",
+    "ConstraintTokenOwner",
+    "ConstraintMintMintAuthority",
+    "ConstraintMintFreezeAuthority",
+    "ConstraintMintDecimals",
+    "ConstraintSpace",
+    "ConstraintAccountIsNone",
+    "ConstraintTokenTokenProgram",
+    "ConstraintMintTokenProgram",
+    "ConstraintAssociatedTokenTokenProgram",
+    "RequireViolated",
+    "RequireEqViolated",
+    "RequireKeysEqViolated",
+    "RequireNeqViolated",
+    "RequireKeysNeqViolated",
+    "RequireGtViolated",
+    "RequireGteViolated",
+    "AccountDiscriminatorAlreadySet",
+    "AccountDiscriminatorNotFound",
+    "AccountDiscriminatorMismatch",
+    "AccountDidNotDeserialize",
+    "AccountDidNotSerialize",
+    "AccountNotEnoughKeys",
+    "AccountNotMutable",
+    "AccountOwnedByWrongProgram",
+    "InvalidProgramId",
+    "InvalidProgramExecutable",
+    "AccountNotSigner",
+    "AccountNotSystemOwned",
+    "AccountNotInitialized",
+    "AccountNotProgramData",
+    "AccountNotAssociatedTokenAccount",
+    "AccountSysvarMismatch",
+    "AccountReallocExceedsLimit",
+    "AccountDuplicateReallocs",
+    "DeclaredProgramIdMismatch",
+    "TryingToInitPayerAsProgramAccount",
+    "Deprecated",
+];
+
+/// Generates the `ErrorCode` enum of the program's custom errors.
+pub fn generate_error_code(errors: &[IdlErrorCode]) -> TokenStream {
+    let variants = errors.iter().map(|error| {
+        let name = format_ident!("{}", error.name);
+        let code = proc_macro2::Literal::u32_unsuffixed(error.code);
+        let msg = match &error.msg {
+            Some(msg) => quote! { #[msg(#msg)] },
+            None => quote! {},
+        };
+        let doc = format!(
+            " {} - {}",
+            error.code,
+            error.msg.as_deref().unwrap_or(&error.name)
+        );
+        quote! {
+            #[doc = #doc]
+            #msg
+            #name = #code
+        }
+    });
+    let from_code_arms = errors.iter().map(|error| {
+        let name = format_ident!("{}", error.name);
+        let code = error.code;
+        quote! {
+            #code => Some(ErrorCode::#name)
+        }
+    });
+    quote! {
+        /// Errors returned by the program.
+        #[error_code(offset = 0)]
+        pub enum ErrorCode {
+            #(#variants),*
+        }
+
+        impl ErrorCode {
+            /// Gets the [ErrorCode] with the given error number.
+            pub fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    #(#from_code_arms,)*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Generates the `errors` module, containing the program's `ErrorCode` and functions for
+/// decoding errors from `ProgramError`s, Anchor errors and transaction logs.
+pub fn generate_errors(errors: &[IdlErrorCode]) -> TokenStream {
+    let error_code = generate_error_code(errors);
+    let anchor_error_arms = ANCHOR_ERROR_CODES.iter().map(|name| {
+        let name = format_ident!("{}", name);
+        quote! {
+            code if code == u32::from(anchor_lang::error::ErrorCode::#name) => {
+                Some(anchor_lang::error::ErrorCode::#name)
+            }
+        }
+    });
+
+    quote! {
+        pub mod errors {
+            //! Errors returned by the program.
+            use super::*;
+
+            #error_code
+
+            /// Gets the Anchor framework error with the given error number.
+            pub fn anchor_error_code(code: u32) -> Option<anchor_lang::error::ErrorCode> {
+                match code {
+                    #(#anchor_error_arms)*
+                    _ => None,
+                }
+            }
+
+            /// An error returned by the program, either defined by the program itself or by
+            /// the Anchor framework.
+            #[derive(Clone, Copy, Debug)]
+            pub enum Error {
+                /// An error defined by the program.
+                Program(ErrorCode),
+                /// An error defined by the Anchor framework.
+                Anchor(anchor_lang::error::ErrorCode),
+            }
+
+            impl Error {
+                /// Gets the [Error] with the given error number.
+                pub fn from_code(code: u32) -> Option<Self> {
+                    ErrorCode::from_code(code)
+                        .map(Error::Program)
+                        .or_else(|| anchor_error_code(code).map(Error::Anchor))
+                }
+
+                /// Decodes the [Error] of a `ProgramError::Custom`.
+                pub fn from_program_error(error: &ProgramError) -> Option<Self> {
+                    match error {
+                        ProgramError::Custom(code) => Self::from_code(*code),
+                        _ => None,
+                    }
+                }
+
+                /// Decodes the [Error] of an Anchor error, such as the one returned by a failed CPI.
+                pub fn from_anchor_error(error: &anchor_lang::error::Error) -> Option<Self> {
+                    match error {
+                        anchor_lang::error::Error::AnchorError(error) => {
+                            Self::from_code(error.error_code_number)
+                        }
+                        anchor_lang::error::Error::ProgramError(error) => {
+                            Self::from_program_error(&error.program_error)
+                        }
+                    }
+                }
+
+                /// The error number.
+                pub fn code(&self) -> u32 {
+                    match self {
+                        Error::Program(error) => u32::from(*error),
+                        Error::Anchor(error) => u32::from(*error),
+                    }
+                }
+
+                /// The name of the error.
+                pub fn name(&self) -> String {
+                    match self {
+                        Error::Program(error) => error.name(),
+                        Error::Anchor(error) => error.name(),
+                    }
+                }
+            }
+
+            impl std::fmt::Display for Error {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        Error::Program(error) => std::fmt::Display::fmt(error, f),
+                        Error::Anchor(error) => std::fmt::Display::fmt(error, f),
+                    }
+                }
+            }
+
+            impl std::error::Error for Error {}
+
+            /// Where an error was thrown, as logged by Anchor.
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            pub enum LoggedErrorOrigin {
+                /// The source file and line which threw the error.
+                Source { file: String, line: u32 },
+                /// The account which caused the error.
+                Account(String),
+            }
+
+            /// An error parsed from the log messages of a failed transaction.
+            #[derive(Clone, Debug)]
+            pub struct LoggedError {
+                /// The error number.
+                pub code: u32,
+                /// The decoded error, if its number is known.
+                pub error: Option<Error>,
+                /// The name of the error, if it was logged by Anchor.
+                pub name: Option<String>,
+                /// The message of the error, if it was logged by Anchor.
+                pub message: Option<String>,
+                /// Where the error was thrown, if it was logged by Anchor.
+                pub origin: Option<LoggedErrorOrigin>,
+            }
+
+            impl LoggedError {
+                /// Parses a `Program log: AnchorError ...` log message.
+                pub fn from_anchor_log(log: &str) -> Option<Self> {
+                    let log = log.strip_prefix("Program log: AnchorError ")?;
+                    let (origin, rest) = log.split_once(". Error Code: ")?;
+                    let (name, rest) = rest.split_once(". Error Number: ")?;
+                    let (code, message) = rest.split_once(". Error Message: ")?;
+                    let code: u32 = code.parse().ok()?;
+                    let origin = if let Some(source) = origin.strip_prefix("thrown in ") {
+                        let (file, line) = source.rsplit_once(':')?;
+                        Some(LoggedErrorOrigin::Source {
+                            file: file.to_string(),
+                            line: line.parse().ok()?,
+                        })
+                    } else {
+                        origin
+                            .strip_prefix("caused by account: ")
+                            .map(|account| LoggedErrorOrigin::Account(account.to_string()))
+                    };
+                    Some(LoggedError {
+                        code,
+                        error: Error::from_code(code),
+                        name: Some(name.to_string()),
+                        message: Some(message.strip_suffix('.').unwrap_or(message).to_string()),
+                        origin,
+                    })
+                }
+
+                /// Parses a `Program <id> failed: custom program error: 0x...` log message.
+                pub fn from_failure_log(log: &str) -> Option<Self> {
+                    let (_, code) = log.split_once(" failed: custom program error: 0x")?;
+                    let code = u32::from_str_radix(code, 16).ok()?;
+                    Some(LoggedError {
+                        code,
+                        error: Error::from_code(code),
+                        name: None,
+                        message: None,
+                        origin: None,
+                    })
+                }
+            }
+
+            /// Parses the error returned by `program_id` from the log messages of a failed transaction.
+            ///
+            /// Anchor's `AnchorError` log messages are preferred, falling back to the custom
+            /// error number of the `Program <id> failed` log message.
+            pub fn parse_logs<S: AsRef<str>>(program_id: &Pubkey, logs: &[S]) -> Option<LoggedError> {
+                let program_logs = logs::program_logs(program_id, logs);
+                program_logs
+                    .iter()
+                    .find_map(|log| LoggedError::from_anchor_log(log))
+                    .or_else(|| {
+                        program_logs
+                            .iter()
+                            .find_map(|log| LoggedError::from_failure_log(log))
+                    })
+            }
+        }
+    }
+}
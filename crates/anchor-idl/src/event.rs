@@ -0,0 +1,121 @@
+use anchor_syn::idl::{IdlEvent, IdlField};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{generate_fields, NamingConvention, TypeTable};
+
+/// Generates a single event struct.
+pub fn generate_event(
+    types: &TypeTable,
+    event: &IdlEvent,
+    naming: NamingConvention,
+) -> TokenStream {
+    let event_name = format_ident!("{}", event.name);
+    let fields = event
+        .fields
+        .iter()
+        .map(|field| IdlField {
+            name: field.name.clone(),
+            docs: None,
+            ty: field.ty.clone(),
+        })
+        .collect::<Vec<_>>();
+    let fields_rendered = generate_fields(types, &event.name, &fields, naming);
+    let doc = format!(" Event: {}", event.name);
+    quote! {
+        #[event]
+        #[doc = #doc]
+        #[derive(Clone, Debug)]
+        pub struct #event_name {
+            #fields_rendered
+        }
+    }
+}
+
+/// Generates the `events` module, containing the event structs, an `Event` enum
+/// and functions for decoding events from transaction logs and `emit_cpi!` instructions.
+///
+/// Logs are decoded with the `base64` crate re-exported by `anchor_gen`.
+pub fn generate_events(
+    types: &TypeTable,
+    events: &[IdlEvent],
+    naming: NamingConvention,
+) -> TokenStream {
+    if events.is_empty() {
+        return quote! {};
+    }
+    let event_structs = events
+        .iter()
+        .map(|event| generate_event(types, event, naming));
+    let event_idents = events
+        .iter()
+        .map(|event| format_ident!("{}", event.name))
+        .collect::<Vec<_>>();
+
+    quote! {
+        pub mod events {
+            //! Events emitted by the program.
+            use super::*;
+
+            #(#event_structs)*
+
+            /// An event emitted by the program.
+            #[derive(Clone, Debug)]
+            pub enum Event {
+                #(#event_idents(#event_idents)),*
+            }
+
+            impl Event {
+                /// Decodes an event from its discriminator followed by its Borsh serialization.
+                pub fn decode(data: &[u8]) -> Option<Self> {
+                    if data.len() < 8 {
+                        return None;
+                    }
+                    let (discriminator, mut data) = data.split_at(8);
+                    #(
+                        if discriminator == <#event_idents as anchor_lang::Discriminator>::DISCRIMINATOR {
+                            return AnchorDeserialize::deserialize(&mut data)
+                                .ok()
+                                .map(Event::#event_idents);
+                        }
+                    )*
+                    None
+                }
+
+                /// Decodes an event from the data of an `emit_cpi!` self-invocation.
+                pub fn decode_cpi(data: &[u8]) -> Option<Self> {
+                    let data = data.strip_prefix(&anchor_lang::event::EVENT_IX_TAG_LE[..])?;
+                    Self::decode(data)
+                }
+            }
+
+            /// Parses the events emitted by `program_id` from the log messages of a transaction.
+            ///
+            /// Only `Program data:` lines logged by `program_id` itself are decoded, and not those
+            /// of the programs it invoked.
+            pub fn parse_logs<S: AsRef<str>>(program_id: &Pubkey, logs: &[S]) -> Vec<Event> {
+                logs::program_logs(program_id, logs)
+                    .into_iter()
+                    .filter_map(|log| log.strip_prefix("Program data: "))
+                    .filter_map(|data| {
+                        let data = ::anchor_gen::base64::decode(data).ok()?;
+                        Event::decode(&data)
+                    })
+                    .collect()
+            }
+
+            /// Parses the events emitted by `program_id` through `emit_cpi!`, given the program id
+            /// and data of every inner instruction of a transaction.
+            pub fn parse_cpi_events<'a>(
+                program_id: &Pubkey,
+                instructions: impl IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+            ) -> Vec<Event> {
+                instructions
+                    .into_iter()
+                    .filter(|(instruction_program_id, _)| *instruction_program_id == program_id)
+                    .filter_map(|(_, data)| Event::decode_cpi(data))
+                    .collect()
+            }
+        }
+    }
+}
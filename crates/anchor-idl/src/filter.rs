@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::{IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{DiscriminatorStrategy, StructOpts, TypeTable};
+
+/// Size in bytes of every serialized value of an [IdlType], or `None` if it varies.
+/// See [TypeTable::fixed_size].
+pub fn get_fixed_size(defs: &[IdlTypeDefinition], ty: &IdlType) -> Option<usize> {
+    TypeTable::new(defs).fixed_size(ty)
+}
+
+/// Computes the offset of every field of an account which starts at a static offset,
+/// i.e. every field up to and including the first variable-length field.
+///
+/// Fields start after the discriminator of the account, `discriminator_len` bytes long.
+pub fn get_static_field_offsets<'a>(
+    types: &TypeTable,
+    fields: &'a [IdlField],
+    discriminator_len: usize,
+) -> Vec<(&'a IdlField, usize)> {
+    let mut offset = Some(discriminator_len);
+    fields
+        .iter()
+        .map_while(|field| {
+            let field_offset = offset?;
+            offset = types.fixed_size(&field.ty).map(|size| field_offset + size);
+            Some((field, field_offset))
+        })
+        .collect()
+}
+
+/// Whether the Borsh serialization of a field can be used to filter a zero copy account.
+fn is_zero_copy_filterable(ty: &IdlType) -> bool {
+    match ty {
+        IdlType::Defined(_) => false,
+        IdlType::Array(inner, _) => is_zero_copy_filterable(inner),
+        _ => true,
+    }
+}
+
+/// Generates `getProgramAccounts` filter constructors for an account.
+pub fn generate_account_filters(
+    types: &TypeTable,
+    account: &IdlTypeDefinition,
+    struct_opts: &BTreeMap<String, StructOpts>,
+    discriminators: &DiscriminatorStrategy,
+) -> TokenStream {
+    let struct_name = format_ident!("{}", account.name);
+    let fields = match &account.ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields,
+        IdlTypeDefinitionTy::Enum { .. } => return quote! {},
+    };
+    let opts = struct_opts.get(&account.name).copied().unwrap_or_default();
+
+    let field_filters =
+        get_static_field_offsets(types, fields, discriminators.account_discriminator_len())
+            .into_iter()
+            .filter(|(field, _)| opts.zero_copy.is_none() || is_zero_copy_filterable(&field.ty))
+            .map(|(field, offset)| {
+                let field_name = field.name.to_snake_case();
+                let filter_name = format_ident!("filter_by_{}", field_name);
+                let ty: TokenStream = crate::ty_to_rust_type(&field.ty).parse().unwrap();
+                let doc = format!(
+                    " Filter matching [{}] accounts whose `{}` is `value`.",
+                    account.name, field_name
+                );
+                quote! {
+                    #[doc = #doc]
+                    pub fn #filter_name(value: &#ty) -> Memcmp {
+                        Memcmp::new(#offset, value.try_to_vec().unwrap())
+                    }
+                }
+            });
+
+    let discriminator_filter = match discriminators {
+        DiscriminatorStrategy::Anchor => {
+            let discriminator_doc = format!(
+                " Filter matching [{}] accounts by their discriminator.",
+                account.name
+            );
+            quote! {
+                #[doc = #discriminator_doc]
+                pub fn discriminator_filter() -> Memcmp {
+                    Memcmp::new(0, Self::DISCRIMINATOR.to_vec())
+                }
+            }
+        }
+        DiscriminatorStrategy::Shank { .. } => quote! {},
+    };
+    quote! {
+        impl #struct_name {
+            #discriminator_filter
+
+            #(#field_filters)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZeroCopy;
+
+    fn defs() -> Vec<IdlTypeDefinition> {
+        serde_json::from_str(
+            r#"[{
+                "name": "Fees",
+                "type": {
+                    "kind": "struct",
+                    "fields": [{ "name": "rate", "type": "u16" }, { "name": "cap", "type": "u64" }]
+                }
+            }]"#,
+        )
+        .unwrap()
+    }
+
+    fn pool(fields: &str) -> IdlTypeDefinition {
+        serde_json::from_str(&format!(
+            r#"{{ "name": "Pool", "type": {{ "kind": "struct", "fields": {} }} }}"#,
+            fields
+        ))
+        .unwrap()
+    }
+
+    fn fields(account: &IdlTypeDefinition) -> &[IdlField] {
+        match &account.ty {
+            IdlTypeDefinitionTy::Struct { fields } => fields,
+            IdlTypeDefinitionTy::Enum { .. } => unreachable!(),
+        }
+    }
+
+    fn offsets(
+        types: &TypeTable,
+        fields: &[IdlField],
+        discriminator_len: usize,
+    ) -> Vec<(String, usize)> {
+        get_static_field_offsets(types, fields, discriminator_len)
+            .into_iter()
+            .map(|(field, offset)| (field.name.clone(), offset))
+            .collect()
+    }
+
+    fn filters(
+        account: &IdlTypeDefinition,
+        opts: StructOpts,
+        discriminators: &DiscriminatorStrategy,
+    ) -> String {
+        let defs = defs();
+        let struct_opts = BTreeMap::from([(account.name.clone(), opts)]);
+        generate_account_filters(
+            &TypeTable::new(&defs),
+            account,
+            &struct_opts,
+            discriminators,
+        )
+        .to_string()
+    }
+
+    #[test]
+    fn test_offsets_after_discriminator() {
+        let defs = defs();
+        let types = TypeTable::new(&defs);
+        let account = pool(
+            r#"[
+                { "name": "bump", "type": "u8" },
+                { "name": "fees", "type": { "defined": "Fees" } },
+                { "name": "owner", "type": "publicKey" },
+                { "name": "history", "type": { "array": ["u16", 4] } }
+            ]"#,
+        );
+        let discriminator_len = DiscriminatorStrategy::Anchor.account_discriminator_len();
+        assert_eq!(
+            offsets(&types, fields(&account), discriminator_len),
+            [
+                ("bump".to_string(), 8),
+                ("fees".to_string(), 9),
+                ("owner".to_string(), 19),
+                ("history".to_string(), 51)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cutoff_at_variable_length_field() {
+        let defs = defs();
+        let types = TypeTable::new(&defs);
+        let account = pool(
+            r#"[
+                { "name": "amount", "type": "u64" },
+                { "name": "label", "type": "string" },
+                { "name": "bump", "type": "u8" }
+            ]"#,
+        );
+        // the variable-length field itself starts at a static offset
+        assert_eq!(
+            offsets(&types, fields(&account), 8),
+            [("amount".to_string(), 8), ("label".to_string(), 16)]
+        );
+
+        let optional = pool(
+            r#"[
+                { "name": "delegate", "type": { "option": "publicKey" } },
+                { "name": "bump", "type": "u8" }
+            ]"#,
+        );
+        assert_eq!(
+            offsets(&types, fields(&optional), 8),
+            [("delegate".to_string(), 8)]
+        );
+    }
+
+    #[test]
+    fn test_zero_copy_filters() {
+        let account = pool(
+            r#"[
+                { "name": "bump", "type": "u8" },
+                { "name": "fees", "type": { "defined": "Fees" } },
+                { "name": "owner", "type": "publicKey" }
+            ]"#,
+        );
+        let borsh = filters(
+            &account,
+            StructOpts::default(),
+            &DiscriminatorStrategy::Anchor,
+        );
+        assert!(borsh.contains("filter_by_fees"));
+
+        // the fields of defined types are not laid out as they are serialized by Borsh
+        let zero_copy = filters(
+            &account,
+            StructOpts {
+                zero_copy: Some(ZeroCopy::Unsafe),
+                ..Default::default()
+            },
+            &DiscriminatorStrategy::Anchor,
+        );
+        assert!(zero_copy.contains("filter_by_bump"));
+        assert!(!zero_copy.contains("filter_by_fees"));
+        assert!(zero_copy.contains("filter_by_owner"));
+        assert!(zero_copy.contains("discriminator_filter"));
+    }
+
+    #[test]
+    fn test_shank_accounts() {
+        let defs = defs();
+        let types = TypeTable::new(&defs);
+        let account =
+            pool(r#"[{ "name": "key", "type": "u8" }, { "name": "count", "type": "u64" }]"#);
+        let shank = DiscriminatorStrategy::Shank {
+            discriminants: BTreeMap::new(),
+        };
+        assert_eq!(
+            offsets(&types, fields(&account), shank.account_discriminator_len()),
+            [("key".to_string(), 0), ("count".to_string(), 1)]
+        );
+
+        let filters = filters(&account, StructOpts::default(), &shank);
+        assert!(filters.contains("filter_by_count"));
+        assert!(!filters.contains("discriminator_filter"));
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::{EnumFields, Idl, IdlEnumVariant, IdlField, IdlTypeDefinitionTy};
+use heck::{ToPascalCase, ToSnakeCase};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::{DiscriminatorStrategy, NamingConvention, StructOpts, TypeTable};
+
+/// Generates the `fuzz` module, which implements `arbitrary::Arbitrary` and
+/// `proptest::arbitrary::Arbitrary` for the generated types, accounts and instruction arguments,
+/// and tests that every value of those with a Borsh serialization round-trips.
+///
+/// The implementations come from `anchor_gen::arbitrary`, which requires the `arbitrary` feature
+/// of `anchor-gen`. Types re-exported from other crates must implement
+/// `anchor_gen::arbitrary::ArbitraryField`, e.g. by being generated with this option as well.
+/// `handler` is whether the `#[program]` module delegates to the `handler` module, which
+/// determines the field names of the `instruction` structs Anchor generates.
+pub fn generate_fuzz(
+    types: &TypeTable,
+    idl: &Idl,
+    struct_opts: &BTreeMap<String, StructOpts>,
+    extern_types: &BTreeMap<String, syn::Path>,
+    naming: NamingConvention,
+    discriminators: &DiscriminatorStrategy,
+    handler: bool,
+) -> TokenStream {
+    let mut impls = vec![];
+    let mut tests = vec![];
+
+    for def in types.defs() {
+        if extern_types.contains_key(&def.name) {
+            continue;
+        }
+        let name = format_ident!("{}", def.name);
+        let opts = struct_opts.get(&def.name).copied().unwrap_or_default();
+        let constructor = match &def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => struct_constructor(fields, naming),
+            IdlTypeDefinitionTy::Enum { variants } => enum_constructor(variants, naming),
+        };
+        impls.push(generate_arbitrary_impls(&quote! { #name }, &constructor));
+        if opts.zero_copy.is_none() {
+            tests.push(generate_round_trip_test(
+                &format_ident!("borsh_round_trip_{}", def.name.to_snake_case()),
+                &quote! { #name },
+            ));
+        }
+    }
+
+    for def in &idl.accounts {
+        let fields = match &def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => fields,
+            IdlTypeDefinitionTy::Enum { .. } => panic!("unexpected enum account"),
+        };
+        let name = format_ident!("{}", def.name);
+        let opts = struct_opts.get(&def.name).copied().unwrap_or_default();
+        let field_names = fields
+            .iter()
+            .map(|field| naming.field_ident(&field.name))
+            .collect::<Vec<_>>();
+        impls.push(generate_arbitrary_impls(
+            &quote! { #name },
+            &struct_constructor(fields, naming),
+        ));
+        // accounts do not derive `Debug`, which proptest requires
+        impls.push(generate_debug_impl(
+            &quote! { #name },
+            &def.name,
+            &field_names,
+            opts.zero_copy.is_some(),
+        ));
+        if opts.zero_copy.is_none() {
+            tests.push(generate_round_trip_test(
+                &format_ident!("borsh_round_trip_{}", def.name.to_snake_case()),
+                &quote! { #name },
+            ));
+        }
+    }
+
+    // Anchor names the fields of instruction structs after the arguments of the handlers
+    let prefix = match discriminators {
+        DiscriminatorStrategy::Anchor if !handler => "_",
+        _ => "",
+    };
+    for ix in &idl.instructions {
+        let name = format_ident!("{}", ix.name.to_pascal_case());
+        let path = quote! { crate::instruction::#name };
+        let field_names = ix
+            .args
+            .iter()
+            .map(|arg| format_ident!("{}{}", prefix, arg.name.to_snake_case()))
+            .collect::<Vec<_>>();
+        impls.push(generate_arbitrary_impls(
+            &path,
+            &quote! {
+                Self {
+                    #(#field_names: ArbitraryField::arbitrary_field(u)?),*
+                }
+            },
+        ));
+        impls.push(generate_debug_impl(
+            &path,
+            &name.to_string(),
+            &field_names,
+            false,
+        ));
+        tests.push(generate_round_trip_test(
+            &format_ident!("borsh_round_trip_instruction_{}", ix.name.to_snake_case()),
+            &path,
+        ));
+    }
+
+    quote! {
+        pub mod fuzz {
+            //! Arbitrary values of the generated types, for fuzzing and property-based tests.
+            use super::*;
+            use ::anchor_gen::arbitrary::{
+                arbitrary::{self, Arbitrary, Unstructured},
+                proptest, ArbitraryField,
+            };
+
+            #(#impls)*
+
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                proptest::proptest! {
+                    #(#tests)*
+                }
+            }
+        }
+    }
+}
+
+/// Expression building a struct from arbitrary values of its fields.
+fn struct_constructor(fields: &[IdlField], naming: NamingConvention) -> TokenStream {
+    let field_names = fields.iter().map(|field| naming.field_ident(&field.name));
+    quote! {
+        Self {
+            #(#field_names: ArbitraryField::arbitrary_field(u)?),*
+        }
+    }
+}
+
+/// Expression building an arbitrary variant of an enum, with arbitrary values of its payload.
+fn enum_constructor(variants: &[IdlEnumVariant], naming: NamingConvention) -> TokenStream {
+    let count = variants.len();
+    let arms = variants.iter().enumerate().map(|(index, variant)| {
+        let name = format_ident!("{}", variant.name);
+        let value = match &variant.fields {
+            Some(EnumFields::Named(fields)) => {
+                let field_names = fields.iter().map(|field| naming.field_ident(&field.name));
+                quote! {
+                    Self::#name { #(#field_names: ArbitraryField::arbitrary_field(u)?),* }
+                }
+            }
+            Some(EnumFields::Tuple(tuple)) => {
+                let values = tuple
+                    .iter()
+                    .map(|_| quote! { ArbitraryField::arbitrary_field(u)? });
+                quote! {
+                    Self::#name(#(#values),*)
+                }
+            }
+            None => quote! { Self::#name },
+        };
+        quote! {
+            #index => #value,
+        }
+    });
+    quote! {
+        match u.choose_index(#count)? {
+            #(#arms)*
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Implements the `Arbitrary` traits of `arbitrary` and proptest, and `ArbitraryField`, for the
+/// type at `path`, whose arbitrary values are built by `constructor`.
+fn generate_arbitrary_impls(path: &TokenStream, constructor: &TokenStream) -> TokenStream {
+    quote! {
+        impl<'a> Arbitrary<'a> for #path {
+            fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+                Ok(#constructor)
+            }
+        }
+
+        impl<'a> ArbitraryField<'a> for #path {
+            fn arbitrary_field(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+                Self::arbitrary(u)
+            }
+        }
+
+        impl proptest::arbitrary::Arbitrary for #path {
+            type Parameters = ();
+            type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                ::anchor_gen::arbitrary::arbitrary_strategy()
+            }
+        }
+    }
+}
+
+/// Implements `Debug` for the struct at `path`, which does not derive it.
+///
+/// The fields of zero copy structs are copied before being formatted, since they may be packed.
+fn generate_debug_impl(
+    path: &TokenStream,
+    struct_name: &str,
+    field_names: &[Ident],
+    zero_copy: bool,
+) -> TokenStream {
+    let fields = field_names.iter().map(|field| {
+        let field_name = field.to_string();
+        if zero_copy {
+            quote! { .field(#field_name, &{ self.#field }) }
+        } else {
+            quote! { .field(#field_name, &self.#field) }
+        }
+    });
+    quote! {
+        impl std::fmt::Debug for #path {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#struct_name)
+                    #(#fields)*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// Tests that arbitrary values of the type at `path` are serialized back to the bytes they are
+/// deserialized from.
+fn generate_round_trip_test(test_name: &Ident, path: &TokenStream) -> TokenStream {
+    quote! {
+        #[test]
+        fn #test_name(value: #path) {
+            let bytes = AnchorSerialize::try_to_vec(&value).unwrap();
+            let decoded = <#path as AnchorDeserialize>::try_from_slice(&bytes).unwrap();
+            proptest::prop_assert_eq!(AnchorSerialize::try_to_vec(&decoded).unwrap(), bytes);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use anchor_syn::idl::IdlInstruction;
+use heck::{ToPascalCase, ToSnakeCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ModuleLayout;
+
+/// Generates the `handler` module, whose `Handler` trait has a method for every instruction.
+///
+/// The handlers of the `#[program]` module delegate to the implementation registered with
+/// `set_handler`, so that the program can stand in for the real one in tests, e.g. with
+/// `solana-program-test`. The argument types of the instructions must be qualified, see
+/// [ModuleLayout::qualify_types].
+pub fn generate_handler(ixs: &[IdlInstruction], layout: &ModuleLayout) -> TokenStream {
+    let ix_accounts_path: TokenStream = format!("{}::{}", layout.scope_path(), layout.ix_accounts)
+        .parse()
+        .unwrap();
+
+    let methods = ixs.iter().map(|ix| {
+        let ix_name = format_ident!("{}", ix.name.to_snake_case());
+        let accounts_name = format_ident!("{}", ix.name.to_pascal_case());
+        let args = ix.args.iter().map(|arg| {
+            let name = format_ident!("_{}", arg.name.to_snake_case());
+            let ty: TokenStream = crate::ty_to_rust_type(&arg.ty).parse().unwrap();
+            quote! {
+                #name: #ty
+            }
+        });
+        let (returns, not_implemented) = handler_result(ix);
+        let doc = format!(" Handles the `{}` instruction.", ix.name);
+        quote! {
+            #[doc = #doc]
+            fn #ix_name(
+                &self,
+                _ctx: Context<#ix_accounts_path::#accounts_name>,
+                #(#args),*
+            ) -> #returns {
+                #not_implemented
+            }
+        }
+    });
+
+    quote! {
+        pub mod handler {
+            //! Implementation of the instructions, for running the program in tests.
+            use super::*;
+
+            /// Implementation of the instructions of the program, which the handlers of the
+            /// `#[program]` module delegate to once it is registered with [set_handler].
+            ///
+            /// Instructions which are not implemented fail with
+            /// `ErrorCode::InstructionFallbackNotFound`.
+            pub trait Handler: Send + Sync {
+                #(#methods)*
+            }
+
+            /// The registered implementation, shared by every thread of the process, since
+            /// `solana-program-test` runs programs on threads of its own.
+            static HANDLER: std::sync::RwLock<Option<std::sync::Arc<dyn Handler>>> =
+                std::sync::RwLock::new(None);
+
+            /// Registers the implementation of the instructions, replacing the one registered
+            /// before, if any.
+            ///
+            /// A single implementation is registered for the whole process, so tests which
+            /// register different ones must not run in parallel, e.g. by holding a shared lock.
+            pub fn set_handler(handler: impl Handler + 'static) {
+                *HANDLER.write().unwrap_or_else(|e| e.into_inner()) =
+                    Some(std::sync::Arc::new(handler));
+            }
+
+            /// Returns the registered implementation of the instructions, or
+            /// `ErrorCode::InstructionFallbackNotFound` if none has been registered with
+            /// [set_handler].
+            pub fn handler() -> Result<std::sync::Arc<dyn Handler>> {
+                HANDLER
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone()
+                    .ok_or_else(|| error!(anchor_lang::error::ErrorCode::InstructionFallbackNotFound))
+            }
+        }
+    }
+}
+
+/// Return type of the handler of an instruction, and the body of handlers of instructions which
+/// are not implemented.
+pub(crate) fn handler_result(ix: &IdlInstruction) -> (TokenStream, TokenStream) {
+    let not_implemented = quote! {
+        error!(anchor_lang::error::ErrorCode::InstructionFallbackNotFound)
+    };
+    if let Some(returns) = &ix.returns {
+        let returns: TokenStream = crate::ty_to_rust_type(returns).parse().unwrap();
+        // `ProgramResult` cannot hold a value
+        (
+            quote! { Result<#returns> },
+            quote! { Err(#not_implemented) },
+        )
+    } else if cfg!(feature = "compat-program-result") {
+        (
+            quote! { ProgramResult },
+            quote! { Err(#not_implemented.into()) },
+        )
+    } else {
+        (quote! { Result<()> }, quote! { Err(#not_implemented) })
+    }
+}
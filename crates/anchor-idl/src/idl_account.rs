@@ -0,0 +1,220 @@
+use std::{fmt, io::Read};
+
+use anchor_syn::idl::Idl;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use flate2::read::ZlibDecoder;
+
+use crate::{anchor_discriminator, parse_idl_json, IdlJsonError};
+
+/// Seed of the on-chain IDL account, derived from the program's signer address.
+pub const IDL_ACCOUNT_SEED: &str = "anchor:idl";
+
+/// Size of the IDL account header: the discriminator, the authority and the length of the data.
+pub const IDL_ACCOUNT_HEADER_LEN: usize = 8 + 32 + 4;
+
+/// An error reading an on-chain IDL account.
+#[derive(Debug)]
+pub enum IdlAccountError {
+    /// The account dump could not be parsed.
+    InvalidDump(String),
+    /// The account does not start with the `IdlAccount` discriminator.
+    InvalidDiscriminator,
+    /// The account is shorter than its header or its data length.
+    Truncated { expected: usize, actual: usize },
+    /// The IDL could not be decompressed.
+    Decompress(std::io::Error),
+    /// The IDL is not valid JSON, or not a valid Anchor IDL.
+    Json(IdlJsonError),
+}
+
+impl fmt::Display for IdlAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdlAccountError::InvalidDump(msg) => write!(f, "invalid account dump: {}", msg),
+            IdlAccountError::InvalidDiscriminator => {
+                write!(f, "account is not an Anchor IDL account")
+            }
+            IdlAccountError::Truncated { expected, actual } => write!(
+                f,
+                "IDL account is truncated: expected {} bytes, found {}",
+                expected, actual
+            ),
+            IdlAccountError::Decompress(error) => {
+                write!(f, "could not decompress the IDL: {}", error)
+            }
+            IdlAccountError::Json(error) => write!(f, "could not parse the IDL: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for IdlAccountError {}
+
+/// Derives the address of the on-chain IDL account of a program.
+///
+/// The IDL account is created with the seed [IDL_ACCOUNT_SEED] from the program's signer,
+/// the program derived address with no seeds.
+///
+/// ```
+/// use anchor_lang::prelude::Pubkey;
+///
+/// let program_id: Pubkey = "GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps".parse().unwrap();
+/// assert_eq!(
+///     Pubkey::new_from_array(anchor_idl::idl_address(&program_id.to_bytes())),
+///     anchor_lang::idl::IdlAccount::address(&program_id),
+/// );
+/// ```
+pub fn idl_address(program_id: &[u8; 32]) -> [u8; 32] {
+    let (signer, _) = find_program_address(&[], program_id);
+    anchor_syn::hash::hashv(&[&signer, IDL_ACCOUNT_SEED.as_bytes(), program_id]).to_bytes()
+}
+
+/// Finds a program derived address and its bump seed, like `Pubkey::find_program_address`.
+pub fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> ([u8; 32], u8) {
+    (0..=u8::MAX)
+        .rev()
+        .find_map(|bump| {
+            let bump_seed = [bump];
+            let mut preimage = seeds.to_vec();
+            preimage.extend([&bump_seed[..], program_id, b"ProgramDerivedAddress"]);
+            let address = anchor_syn::hash::hashv(&preimage).to_bytes();
+            // program derived addresses must not be valid ed25519 public keys
+            let on_curve = CompressedEdwardsY::from_slice(&address)
+                .decompress()
+                .is_some();
+            (!on_curve).then_some((address, bump))
+        })
+        .expect("no viable bump seed found")
+}
+
+/// Parses the data of an on-chain IDL account: its header followed by the zlib compressed IDL,
+/// in any of the formats accepted by [parse_idl_json].
+pub fn parse_idl_account(data: &[u8]) -> Result<Idl, IdlAccountError> {
+    if data.len() < IDL_ACCOUNT_HEADER_LEN {
+        return Err(IdlAccountError::Truncated {
+            expected: IDL_ACCOUNT_HEADER_LEN,
+            actual: data.len(),
+        });
+    }
+    if data[..8] != anchor_discriminator("account", "IdlAccount") {
+        return Err(IdlAccountError::InvalidDiscriminator);
+    }
+    let data_len = u32::from_le_bytes(data[40..44].try_into().unwrap()) as usize;
+    let compressed = data
+        .get(IDL_ACCOUNT_HEADER_LEN..IDL_ACCOUNT_HEADER_LEN + data_len)
+        .ok_or(IdlAccountError::Truncated {
+            expected: IDL_ACCOUNT_HEADER_LEN + data_len,
+            actual: data.len(),
+        })?;
+    let mut json = vec![];
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut json)
+        .map_err(IdlAccountError::Decompress)?;
+    parse_idl_json(&json).map_err(IdlAccountError::Json)
+}
+
+/// Parses a dump of an on-chain IDL account, either the raw account data or the output of
+/// `solana account --output json`.
+///
+/// ```
+/// use std::io::Write;
+///
+/// let idl = include_bytes!("../../../examples/govern-cpi/idl.json");
+/// let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+/// encoder.write_all(idl).unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// let mut data = anchor_idl::anchor_discriminator("account", "IdlAccount").to_vec();
+/// data.extend([0; 32]);
+/// data.extend((compressed.len() as u32).to_le_bytes());
+/// data.extend(compressed);
+///
+/// let idl = anchor_idl::parse_idl_account_dump(&data).unwrap();
+/// assert_eq!(idl.name, "govern");
+/// ```
+pub fn parse_idl_account_dump(dump: &[u8]) -> Result<Idl, IdlAccountError> {
+    parse_idl_account(&account_dump_data(dump)?)
+}
+
+/// Data of an account from a dump, either the raw account data or the output of
+/// `solana account --output json`.
+pub fn account_dump_data(dump: &[u8]) -> Result<Vec<u8>, IdlAccountError> {
+    if dump.iter().find(|byte| !byte.is_ascii_whitespace()) != Some(&b'{') {
+        return Ok(dump.to_vec());
+    }
+    let dump: serde_json::Value = serde_json::from_slice(dump)
+        .map_err(|error| IdlAccountError::InvalidDump(error.to_string()))?;
+    let data = match &dump["account"]["data"] {
+        serde_json::Value::Array(data) => data,
+        _ => {
+            return Err(IdlAccountError::InvalidDump(
+                "missing `account.data`".to_string(),
+            ))
+        }
+    };
+    match (
+        data.first().and_then(|d| d.as_str()),
+        data.get(1).and_then(|e| e.as_str()),
+    ) {
+        (Some(data), Some("base64")) => {
+            base64::decode(data).map_err(|error| IdlAccountError::InvalidDump(error.to_string()))
+        }
+        (_, Some(encoding)) => Err(IdlAccountError::InvalidDump(format!(
+            "unsupported encoding `{}`, expected `base64`",
+            encoding
+        ))),
+        _ => Err(IdlAccountError::InvalidDump(
+            "`account.data` must be a `[data, encoding]` pair".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::legacy_idl_to_new;
+
+    /// Data of an IDL account containing `json`.
+    fn idl_account(json: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(json).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut data = anchor_discriminator("account", "IdlAccount").to_vec();
+        data.extend([0; 32]);
+        data.extend((compressed.len() as u32).to_le_bytes());
+        data.extend(compressed);
+        data
+    }
+
+    #[test]
+    fn test_new_idl() {
+        let idl: Idl =
+            serde_json::from_str(include_str!("../../../examples/govern-cpi/idl.json")).unwrap();
+        let new_idl =
+            legacy_idl_to_new(&idl, Some("Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw")).unwrap();
+        let parsed = parse_idl_account(&idl_account(new_idl.to_string().as_bytes())).unwrap();
+        assert_eq!(parsed.name, idl.name);
+        assert_eq!(parsed.instructions, idl.instructions);
+        assert_eq!(parsed.accounts, idl.accounts);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let error = parse_idl_account(&idl_account(b"{ \"name\": ")).unwrap_err();
+        assert!(matches!(
+            error,
+            IdlAccountError::Json(IdlJsonError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut data = idl_account(b"{}");
+        data.pop();
+        assert!(matches!(
+            parse_idl_account(&data),
+            Err(IdlAccountError::Truncated { .. })
+        ));
+    }
+}
@@ -4,15 +4,24 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
 /// Generates a single instruction handler.
-pub fn generate_ix_handler(ix: &IdlInstruction) -> TokenStream {
+///
+/// The handler delegates to the registered implementation of the `Handler` trait if `handler`
+/// is the path of the module generated by [crate::generate_handler], or else is unimplemented.
+pub fn generate_ix_handler(ix: &IdlInstruction, handler: Option<&TokenStream>) -> TokenStream {
     let ix_name = format_ident!("{}", ix.name.to_snake_case());
     let accounts_name = format_ident!("{}", ix.name.to_pascal_case());
 
+    let prefix = if handler.is_some() { "" } else { "_" };
+    let arg_names = ix
+        .args
+        .iter()
+        .map(|arg| format_ident!("{}{}", prefix, arg.name.to_snake_case()))
+        .collect::<Vec<_>>();
     let args = ix
         .args
         .iter()
-        .map(|arg| {
-            let name = format_ident!("_{}", arg.name.to_snake_case());
+        .zip(&arg_names)
+        .map(|(arg, name)| {
             let type_name = crate::ty_to_rust_type(&arg.ty);
             let stream: proc_macro2::TokenStream = type_name.parse().unwrap();
             quote! {
@@ -21,24 +30,24 @@ pub fn generate_ix_handler(ix: &IdlInstruction) -> TokenStream {
         })
         .collect::<Vec<_>>();
 
-    if cfg!(feature = "compat-program-result") {
-        quote! {
+    let (returns, _) = crate::handler_result(ix);
+    match handler {
+        Some(handler) => quote! {
             pub fn #ix_name(
-                _ctx: Context<#accounts_name>,
+                ctx: Context<#accounts_name>,
                 #(#args),*
-            ) -> ProgramResult {
-                unimplemented!("This program is a wrapper for CPI.")
+            ) -> #returns {
+                #handler::handler()?.#ix_name(ctx, #(#arg_names),*)
             }
-        }
-    } else {
-        quote! {
+        },
+        None => quote! {
             pub fn #ix_name(
                 _ctx: Context<#accounts_name>,
                 #(#args),*
-            ) -> Result<()> {
+            ) -> #returns {
                 unimplemented!("This program is a wrapper for CPI.")
             }
-        }
+        },
     }
 }
 
@@ -64,9 +73,9 @@ pub fn generate_ix_structs(ixs: &[IdlInstruction]) -> TokenStream {
     }
 }
 
-/// Generates all instruction handlers.
-pub fn generate_ix_handlers(ixs: &[IdlInstruction]) -> TokenStream {
-    let streams = ixs.iter().map(generate_ix_handler);
+/// Generates all instruction handlers, see [generate_ix_handler].
+pub fn generate_ix_handlers(ixs: &[IdlInstruction], handler: Option<&TokenStream>) -> TokenStream {
+    let streams = ixs.iter().map(|ix| generate_ix_handler(ix, handler));
     quote! {
         #(#streams)*
     }
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::{
+    EnumFields, Idl, IdlField, IdlInstruction, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+use darling::FromMeta;
+use heck::{ToPascalCase, ToSnakeCase};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+/// Naming convention of generated types or fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromMeta)]
+pub enum NamingConvention {
+    /// Names are used exactly as written in the IDL.
+    #[darling(rename = "preserve")]
+    Preserve,
+    /// Names are converted to Rust conventions, i.e. `PascalCase` types and `snake_case` fields.
+    #[darling(rename = "rust")]
+    Rust,
+}
+
+impl NamingConvention {
+    /// Name of a type.
+    pub fn type_name(&self, name: &str) -> String {
+        match self {
+            NamingConvention::Preserve => name.to_string(),
+            NamingConvention::Rust => name.to_pascal_case(),
+        }
+    }
+
+    /// Identifier of a field.
+    pub fn field_ident(&self, name: &str) -> Ident {
+        match self {
+            NamingConvention::Preserve => format_ident!("{}", name),
+            NamingConvention::Rust => format_ident!("{}", name.to_snake_case()),
+        }
+    }
+
+    /// Attributes of items whose names may not follow Rust conventions.
+    pub fn allow_attrs(&self) -> TokenStream {
+        match self {
+            NamingConvention::Preserve => quote! { #[allow(non_snake_case)] },
+            NamingConvention::Rust => quote! {},
+        }
+    }
+}
+
+/// Renames the types of an IDL, and every reference to them, following `naming`.
+///
+/// Accounts and events keep their names, since their discriminators are derived from them.
+/// Returns the renamed IDL and the new name of every type, by original name.
+pub fn rename_types(idl: &Idl, naming: NamingConvention) -> (Idl, BTreeMap<String, String>) {
+    let renames = idl
+        .types
+        .iter()
+        .map(|def| (def.name.clone(), naming.type_name(&def.name)))
+        .filter(|(name, renamed)| name != renamed)
+        .collect::<BTreeMap<_, _>>();
+    let mut idl = idl.clone();
+    if renames.is_empty() {
+        return (idl, renames);
+    }
+    let rename_fields = |fields: &mut [IdlField]| {
+        fields
+            .iter_mut()
+            .for_each(|field| rename_ty(&mut field.ty, &renames))
+    };
+    for def in idl.types.iter_mut().chain(idl.accounts.iter_mut()) {
+        if let Some(renamed) = renames.get(&def.name) {
+            def.name = renamed.clone();
+        }
+        match &mut def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => rename_fields(fields),
+            IdlTypeDefinitionTy::Enum { variants } => {
+                for variant in variants {
+                    match &mut variant.fields {
+                        Some(EnumFields::Named(fields)) => rename_fields(fields),
+                        Some(EnumFields::Tuple(types)) => {
+                            types.iter_mut().for_each(|ty| rename_ty(ty, &renames))
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+    for ix in &mut idl.instructions {
+        rename_fields(&mut ix.args);
+        if let Some(returns) = &mut ix.returns {
+            rename_ty(returns, &renames);
+        }
+    }
+    for event in idl.events.iter_mut().flatten() {
+        event
+            .fields
+            .iter_mut()
+            .for_each(|field| rename_ty(&mut field.ty, &renames));
+    }
+    (idl, renames)
+}
+
+fn rename_ty(ty: &mut IdlType, renames: &BTreeMap<String, String>) {
+    match ty {
+        IdlType::Defined(name) => {
+            if let Some(renamed) = renames.get(name) {
+                *name = renamed.clone();
+            }
+        }
+        IdlType::Option(inner) | IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+            rename_ty(inner, renames)
+        }
+        _ => {}
+    }
+}
+
+/// Names and nesting of the generated modules.
+#[derive(Clone, Debug)]
+pub struct ModuleLayout {
+    /// Module wrapping the generated items.
+    ///
+    /// The `#[program]` module is always generated at the crate root, since Anchor refers to
+    /// the items it generates through `crate::`.
+    pub module: Option<Ident>,
+    /// Name of the `#[program]` module. Defaults to the name of the program.
+    pub program: Option<Ident>,
+    /// Name of the module containing the user-defined types.
+    pub typedefs: Ident,
+    /// Name of the module containing the accounts.
+    pub state: Ident,
+    /// Name of the module containing the accounts of each instruction.
+    pub ix_accounts: Ident,
+    /// Whether the types and accounts are publicly re-exported next to their modules.
+    pub glob_reexports: bool,
+}
+
+impl Default for ModuleLayout {
+    fn default() -> Self {
+        ModuleLayout {
+            module: None,
+            program: None,
+            typedefs: format_ident!("typedefs"),
+            state: format_ident!("state"),
+            ix_accounts: format_ident!("ix_accounts"),
+            glob_reexports: true,
+        }
+    }
+}
+
+impl ModuleLayout {
+    /// Path of the module containing the generated modules.
+    pub fn scope_path(&self) -> String {
+        match &self.module {
+            Some(module) => format!("crate::{}", module),
+            None => "crate".to_string(),
+        }
+    }
+
+    /// Rewrites the argument and return types of instructions as absolute paths.
+    ///
+    /// Anchor copies the signatures of instruction handlers into modules it generates at the
+    /// crate root, where the generated types may not be in scope.
+    pub fn qualify_types(
+        &self,
+        ixs: &[IdlInstruction],
+        accounts: &[IdlTypeDefinition],
+    ) -> Vec<IdlInstruction> {
+        ixs.iter()
+            .map(|ix| {
+                let mut ix = ix.clone();
+                for arg in &mut ix.args {
+                    self.qualify_ty(&mut arg.ty, accounts);
+                }
+                if let Some(returns) = &mut ix.returns {
+                    self.qualify_ty(returns, accounts);
+                }
+                ix
+            })
+            .collect()
+    }
+
+    fn qualify_ty(&self, ty: &mut IdlType, accounts: &[IdlTypeDefinition]) {
+        match ty {
+            IdlType::Defined(name) => {
+                let module = if accounts.iter().any(|def| &def.name == name) {
+                    &self.state
+                } else {
+                    &self.typedefs
+                };
+                *name = format!("{}::{}::{}", self.scope_path(), module, name);
+            }
+            IdlType::Option(inner) | IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+                self.qualify_ty(inner, accounts)
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GeneratorOptions;
+
+    const IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "pools",
+        "instructions": [{
+            "name": "swap",
+            "accounts": [],
+            "args": [{ "name": "params", "type": { "option": { "defined": "swap_params" } } }],
+            "returns": { "vec": { "defined": "swap_params" } }
+        }],
+        "accounts": [{
+            "name": "pool",
+            "type": {
+                "kind": "struct",
+                "fields": [{ "name": "history", "type": { "array": [{ "defined": "swap_params" }, 2] } }]
+            }
+        }],
+        "types": [
+            { "name": "swap_params", "type": { "kind": "struct", "fields": [] } },
+            { "name": "Side", "type": { "kind": "enum", "variants": [{ "name": "bid" }] } }
+        ],
+        "events": [{
+            "name": "swapped",
+            "fields": [{ "name": "params", "type": { "defined": "swap_params" }, "index": false }]
+        }]
+    }"#;
+
+    fn idl(json: &str) -> Idl {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn names(defs: &[IdlTypeDefinition]) -> Vec<&str> {
+        defs.iter().map(|def| def.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rename_types() {
+        let (renamed, renames) = rename_types(&idl(IDL), NamingConvention::Rust);
+        assert_eq!(
+            renames,
+            BTreeMap::from([("swap_params".to_string(), "SwapParams".to_string())])
+        );
+        assert_eq!(names(&renamed.types), ["SwapParams", "Side"]);
+        // accounts and events keep their names, but not the types they refer to
+        assert_eq!(names(&renamed.accounts), ["pool"]);
+        assert_eq!(renamed.events.as_ref().unwrap()[0].name, "swapped");
+        let params = || Box::new(IdlType::Defined("SwapParams".to_string()));
+        assert_eq!(
+            renamed.instructions[0].args[0].ty,
+            IdlType::Option(params())
+        );
+        assert_eq!(
+            renamed.instructions[0].returns,
+            Some(IdlType::Vec(params()))
+        );
+        assert_eq!(
+            renamed.accounts[0].ty,
+            IdlTypeDefinitionTy::Struct {
+                fields: vec![IdlField {
+                    name: "history".to_string(),
+                    docs: None,
+                    ty: IdlType::Array(params(), 2),
+                }]
+            }
+        );
+        assert_eq!(
+            renamed.events.unwrap()[0].fields[0].ty,
+            IdlType::Defined("SwapParams".to_string())
+        );
+
+        let (preserved, renames) = rename_types(&idl(IDL), NamingConvention::Preserve);
+        assert!(renames.is_empty());
+        assert_eq!(preserved, idl(IDL));
+    }
+
+    #[test]
+    fn test_rename_types_collisions() {
+        let mut colliding = idl(IDL);
+        colliding.types.push(IdlTypeDefinition {
+            name: "SwapParams".to_string(),
+            docs: None,
+            ty: IdlTypeDefinitionTy::Struct { fields: vec![] },
+        });
+        colliding.types.push(IdlTypeDefinition {
+            name: "flag".to_string(),
+            docs: None,
+            ty: IdlTypeDefinitionTy::Struct { fields: vec![] },
+        });
+        colliding.accounts.push(IdlTypeDefinition {
+            name: "Flag".to_string(),
+            docs: None,
+            ty: IdlTypeDefinitionTy::Struct { fields: vec![] },
+        });
+        let (renamed, _) = rename_types(&colliding, NamingConvention::Rust);
+        assert_eq!(
+            names(&renamed.types),
+            ["SwapParams", "Side", "SwapParams", "Flag"]
+        );
+        assert_eq!(names(&renamed.accounts), ["pool", "Flag"]);
+
+        // the generator reports types which end up with the same name
+        let options = |idl: &Idl, type_naming| GeneratorOptions {
+            idl_json: Some(serde_json::to_string(idl).unwrap()),
+            type_naming: Some(type_naming),
+            ..Default::default()
+        };
+        let error = options(&colliding, NamingConvention::Rust)
+            .to_generator()
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("more than one type or account is named `SwapParams`"));
+        assert!(error.contains("more than one type or account is named `Flag`"));
+        assert!(options(&colliding, NamingConvention::Preserve)
+            .to_generator()
+            .is_ok());
+    }
+}
@@ -2,17 +2,63 @@
 
 pub use anchor_syn::idl::*;
 
+pub mod dynamic;
+
 mod account;
+mod client;
+mod codama;
+mod compat;
+mod discriminator;
+mod error;
+mod event;
+mod filter;
+mod fuzz;
+mod handler;
+mod idl_account;
 mod instruction;
+mod layout;
+mod logs;
+mod native;
 mod program;
+mod recursion;
+mod resolve;
+mod returns;
+mod shared;
+mod source;
+mod spec;
 mod state;
+mod subset;
+mod type_table;
 mod typedef;
+mod workspace;
 
 pub use account::*;
+pub use client::*;
+pub use codama::*;
+pub use compat::*;
+pub use discriminator::*;
+pub use error::*;
+pub use event::*;
+pub use filter::*;
+pub use fuzz::*;
+pub use handler::*;
+pub use idl_account::*;
 pub use instruction::*;
+pub use layout::*;
+pub use logs::*;
+pub use native::*;
 pub use program::*;
+pub use recursion::*;
+pub use resolve::*;
+pub use returns::*;
+pub use shared::*;
+pub use source::*;
+pub use spec::*;
 pub use state::*;
+pub use subset::*;
+pub use type_table::*;
 pub use typedef::*;
+pub use workspace::*;
 
 /// Version of anchor-idl.
 pub const GEN_VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
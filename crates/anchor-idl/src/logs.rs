@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Generates the `logs` module, which attributes transaction log messages to programs.
+pub fn generate_logs() -> TokenStream {
+    quote! {
+        pub mod logs {
+            //! Attribution of transaction log messages to programs.
+            use super::*;
+
+            /// Returns the log messages of a transaction which were logged while `program_id`
+            /// was the innermost invocation, including its own `invoke`, `success` and `failed` lines.
+            ///
+            /// Messages logged by programs which `program_id` invoked are skipped.
+            pub fn program_logs<'a, S: AsRef<str>>(program_id: &Pubkey, logs: &'a [S]) -> Vec<&'a str> {
+                let program_id = program_id.to_string();
+                let mut invocations: Vec<&str> = vec![];
+                let mut program_logs = vec![];
+                for log in logs {
+                    let log = log.as_ref();
+                    let mut returned = false;
+                    if let Some(rest) = log.strip_prefix("Program ") {
+                        let is_message = rest.starts_with("log: ")
+                            || rest.starts_with("data: ")
+                            || rest.starts_with("return: ");
+                        if let (false, Some((program, status))) = (is_message, rest.split_once(' ')) {
+                            if status.starts_with("invoke [") {
+                                invocations.push(program);
+                            } else if status == "success" || status.starts_with("failed") {
+                                returned = true;
+                            }
+                        }
+                    }
+                    if invocations.last() == Some(&program_id.as_str()) {
+                        program_logs.push(log);
+                    }
+                    if returned {
+                        invocations.pop();
+                    }
+                }
+                program_logs
+            }
+        }
+    }
+}
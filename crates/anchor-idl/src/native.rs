@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use anchor_syn::idl::IdlInstruction;
+use heck::{ToPascalCase, ToSnakeCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ModuleLayout;
+
+/// Generates the `instruction`, `accounts` and `cpi` modules of a program which is not built
+/// with Anchor, in place of those the `#[program]` module would generate.
+///
+/// `discriminators` holds the discriminator of every instruction by name, and the argument types
+/// of the instructions must be qualified, see [ModuleLayout::qualify_types].
+pub fn generate_native_ix_modules(
+    ixs: &[IdlInstruction],
+    discriminators: &BTreeMap<String, Vec<u8>>,
+    layout: &ModuleLayout,
+) -> TokenStream {
+    let ix_accounts_path: TokenStream = format!("{}::{}", layout.scope_path(), layout.ix_accounts)
+        .parse()
+        .unwrap();
+
+    let data_structs = ixs.iter().map(|ix| {
+        let struct_name = format_ident!("{}", ix.name.to_pascal_case());
+        let discriminator = &discriminators[&ix.name];
+        let discriminator_len = discriminator.len();
+        let fields = ix.args.iter().map(|arg| {
+            let name = format_ident!("{}", arg.name.to_snake_case());
+            let ty: TokenStream = crate::ty_to_rust_type(&arg.ty).parse().unwrap();
+            quote! {
+                pub #name: #ty
+            }
+        });
+        let doc = format!(" Data of the `{}` instruction.", ix.name);
+        quote! {
+            #[doc = #doc]
+            #[derive(AnchorSerialize, AnchorDeserialize)]
+            pub struct #struct_name {
+                #(#fields),*
+            }
+
+            impl #struct_name {
+                /// Bytes prefixing the data of the instruction.
+                pub const DISCRIMINATOR: [u8; #discriminator_len] = [#(#discriminator),*];
+
+                /// Serializes the instruction data, including its discriminator.
+                pub fn data(&self) -> Vec<u8> {
+                    let mut data = Self::DISCRIMINATOR.to_vec();
+                    data.append(&mut self.try_to_vec().unwrap());
+                    data
+                }
+            }
+        }
+    });
+
+    let client_accounts = ixs.iter().map(|ix| {
+        let module = format_ident!("__client_accounts_{}", ix.name.to_snake_case());
+        quote! {
+            pub use #ix_accounts_path::#module::*;
+        }
+    });
+    let cpi_accounts = ixs.iter().map(|ix| {
+        let module = format_ident!("__cpi_client_accounts_{}", ix.name.to_snake_case());
+        quote! {
+            pub use #ix_accounts_path::#module::*;
+        }
+    });
+
+    let cpi_fns = ixs.iter().map(|ix| {
+        let ix_name = format_ident!("{}", ix.name.to_snake_case());
+        let struct_name = format_ident!("{}", ix.name.to_pascal_case());
+        let arg_names = ix
+            .args
+            .iter()
+            .map(|arg| format_ident!("{}", arg.name.to_snake_case()))
+            .collect::<Vec<_>>();
+        let arg_types = ix.args.iter().map(|arg| {
+            crate::ty_to_rust_type(&arg.ty)
+                .parse::<TokenStream>()
+                .unwrap()
+        });
+        let doc = format!(" Invokes the `{}` instruction.", ix.name);
+        quote! {
+            #[doc = #doc]
+            pub fn #ix_name<'a, 'b, 'c, 'info>(
+                ctx: CpiContext<'a, 'b, 'c, 'info, accounts::#struct_name<'info>>,
+                #(#arg_names: #arg_types),*
+            ) -> Result<()> {
+                let ix = anchor_lang::solana_program::instruction::Instruction {
+                    program_id: crate::ID,
+                    accounts: ctx.to_account_metas(None),
+                    data: crate::instruction::#struct_name { #(#arg_names),* }.data(),
+                };
+                anchor_lang::solana_program::program::invoke_signed(
+                    &ix,
+                    &ctx.to_account_infos(),
+                    ctx.signer_seeds,
+                )
+                .map_err(Into::into)
+            }
+        }
+    });
+
+    quote! {
+        pub mod instruction {
+            //! Data of each instruction, for building instructions off-chain.
+            use super::*;
+
+            #(#data_structs)*
+        }
+
+        pub mod accounts {
+            //! Accounts of each instruction, for building instructions off-chain.
+            #(#client_accounts)*
+        }
+
+        pub mod cpi {
+            //! Cross-program invocations of each instruction.
+            use super::*;
+
+            pub mod accounts {
+                //! Accounts of each instruction, for cross-program invocations.
+                #(#cpi_accounts)*
+            }
+
+            #(#cpi_fns)*
+        }
+    }
+}
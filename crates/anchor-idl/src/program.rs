@@ -1,31 +1,160 @@
 use std::{
-    collections::{BTreeMap, HashSet},
-    env, fs,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env,
 };
 
-use darling::{util::PathList, FromMeta};
+use anchor_syn::idl::Idl;
+use darling::{
+    util::{Flag, PathList},
+    FromMeta,
+};
+use heck::ToSnakeCase;
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
 use crate::{
-    generate_accounts, generate_ix_handlers, generate_ix_structs, generate_typedefs, GEN_VERSION,
+    find_infinite_types, find_unresolved_types, generate_accounts, generate_client,
+    generate_errors, generate_events, generate_fuzz, generate_handler, generate_ix_handlers,
+    generate_ix_structs, generate_logs, generate_native_ix_modules, generate_returns,
+    generate_typedefs, parse_idl_json, read_idl_file, rename_types, DiscriminatorStrategy,
+    IdlDependencies, IdlFormat, IdlSubset, ModuleLayout, NamingConvention, PlaceholderType,
+    TypeTable, GEN_VERSION,
 };
 
+/// Names of the type namespace which `anchor_lang::prelude` exports.
+const PRELUDE_TYPE_NAMES: &[&str] = &[
+    "Account",
+    "AccountDeserialize",
+    "AccountInfo",
+    "AccountLoader",
+    "AccountMeta",
+    "AccountSerialize",
+    "Accounts",
+    "AccountsClose",
+    "AccountsExit",
+    "AnchorDeserialize",
+    "AnchorError",
+    "AnchorSerialize",
+    "Clock",
+    "ComparedValues",
+    "Context",
+    "CpiContext",
+    "EpochSchedule",
+    "Error",
+    "ErrorCode",
+    "ErrorOrigin",
+    "Id",
+    "InitSpace",
+    "Instructions",
+    "Interface",
+    "InterfaceAccount",
+    "Key",
+    "Owner",
+    "Program",
+    "ProgramData",
+    "ProgramError",
+    "ProgramErrorWithOrigin",
+    "Pubkey",
+    "Rent",
+    "Result",
+    "Rewards",
+    "Signer",
+    "SlotHashes",
+    "SlotHistory",
+    "SolanaSysvar",
+    "Source",
+    "Space",
+    "StakeHistory",
+    "System",
+    "SystemAccount",
+    "Sysvar",
+    "ToAccountInfo",
+    "ToAccountInfos",
+    "ToAccountMetas",
+    "UncheckedAccount",
+    "UpgradeableLoaderState",
+];
+
 #[derive(Default, FromMeta)]
 pub struct GeneratorOptions {
     /// Path to the IDL.
-    pub idl_path: String,
+    pub idl_path: Option<String>,
+    /// Path to a dump of the on-chain IDL account, either the raw account data or the output of
+    /// `solana account --output json`.
+    pub idl_account_path: Option<String>,
+    /// The JSON IDL itself.
+    pub idl_json: Option<String>,
+    /// Environment variable containing the path to the IDL.
+    pub idl_env: Option<String>,
     /// List of zero copy structs.
     pub zero_copy: Option<PathList>,
     /// List of anchor legacy zero copy structs.
     pub zero_copy_unsafe: Option<PathList>,
-    /// List of `repr(C)` structs.
+    /// List of `repr(C)` structs and enums.
     pub c_representation: Option<PathList>,
     /// List of `repr(transparent)` structs.
     pub transparent_representation: Option<PathList>,
-    /// List of `repr(packed)` structs.
+    /// List of `repr(C, packed)` structs, which must be zero copy.
     pub packed_representation: Option<PathList>,
+    /// Alignment of `repr(align(N))` structs and enums, e.g. `align_representation(Foo = 8)`.
+    pub align_representation: Option<HashMap<syn::Ident, u32>>,
+    /// Integer representation of enums, e.g. `int_representation(Side = "u8")`.
+    pub int_representation: Option<HashMap<syn::Ident, IntRepresentation>>,
+    /// Types defined in another crate, e.g. `extern_types(Name = "other_crate::typedefs::Name")`.
+    ///
+    /// These are re-exported from the given path instead of being generated.
+    pub extern_types: Option<HashMap<syn::Ident, syn::Path>>,
+    /// Types which are not defined by the IDL, generated as opaque bytes of the given size,
+    /// e.g. `placeholder_types(Metadata = "[u8; 32]")`.
+    pub placeholder_types: Option<HashMap<syn::Ident, PlaceholderType>>,
+    /// Instructions to generate, by snake case name. Defaults to every instruction.
+    pub include_instructions: Option<PathList>,
+    /// Instructions not to generate, by snake case name.
+    pub exclude_instructions: Option<PathList>,
+    /// Accounts to generate. Defaults to every account.
+    pub include_accounts: Option<PathList>,
+    /// Types to generate even if no generated instruction, account or event references them.
+    ///
+    /// When any of the `include_*` or `exclude_*` options is specified, only the types
+    /// referenced by the generated items are generated.
+    pub include_types: Option<PathList>,
+    /// Module wrapping the generated items, e.g. `module = "governance"`.
+    ///
+    /// The `#[program]` module is still generated at the crate root, which Anchor requires, along
+    /// with a glob import of the Anchor prelude, which items of the crate shadow.
+    pub module: Option<syn::Ident>,
+    /// Name of the `#[program]` module. Defaults to the name of the program.
+    pub program_module: Option<syn::Ident>,
+    /// Name of the module containing the user-defined types. Defaults to `typedefs`.
+    pub typedefs_module: Option<syn::Ident>,
+    /// Name of the module containing the accounts. Defaults to `state`.
+    pub state_module: Option<syn::Ident>,
+    /// Name of the module containing the accounts of each instruction. Defaults to `ix_accounts`.
+    pub ix_accounts_module: Option<syn::Ident>,
+    /// Whether the types and accounts are publicly re-exported next to their modules.
+    /// Defaults to `true`.
+    pub glob_reexports: Option<bool>,
+    /// Naming convention of types, `"preserve"` (the default) or `"rust"`.
+    pub type_naming: Option<NamingConvention>,
+    /// Naming convention of fields, `"rust"` (the default) or `"preserve"`.
+    pub field_naming: Option<NamingConvention>,
+    /// How instructions and accounts are discriminated, `"anchor"` or `"shank"`.
+    ///
+    /// Defaults to `"shank"` for IDLs whose `metadata.origin` is `"shank"`, and to `"anchor"`
+    /// otherwise. Programs which are not built with Anchor get no `#[program]` module.
+    pub discriminators: Option<DiscriminatorStrategy>,
+    /// Generates a `client` module for fetching accounts over RPC.
+    ///
+    /// Requires the `client` feature of `anchor-gen`.
+    pub client: Flag,
+    /// Generates a `handler` module whose `Handler` trait implements the instructions, which the
+    /// `#[program]` module delegates to, so that the program can be executed in tests.
+    pub handler: Flag,
+    /// Implements `arbitrary` and proptest's `Arbitrary` for the generated types, accounts and
+    /// instruction arguments in a `fuzz` module, along with Borsh round-trip tests.
+    ///
+    /// Requires the `arbitrary` feature of `anchor-gen`.
+    pub arbitrary: Flag,
 }
 
 fn path_list_to_string(list: Option<&PathList>) -> HashSet<String> {
@@ -38,11 +167,224 @@ fn path_list_to_string(list: Option<&PathList>) -> HashSet<String> {
 }
 
 impl GeneratorOptions {
-    pub fn to_generator(&self) -> Generator {
-        let cargo_manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
-        let path = PathBuf::from(cargo_manifest_dir).join(&self.idl_path);
-        let idl_contents = fs::read_to_string(path).unwrap();
-        let idl: anchor_syn::idl::Idl = serde_json::from_str(&idl_contents).unwrap();
+    /// Paths of every type referenced in the options, used to attach spans to errors.
+    fn type_paths(&self) -> BTreeMap<String, syn::Path> {
+        let mut paths = BTreeMap::new();
+        [
+            &self.zero_copy,
+            &self.zero_copy_unsafe,
+            &self.c_representation,
+            &self.transparent_representation,
+            &self.packed_representation,
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|list| list.iter())
+        .for_each(|path| {
+            if let Some(ident) = path.get_ident() {
+                paths
+                    .entry(ident.to_string())
+                    .or_insert_with(|| path.clone());
+            }
+        });
+        let align_idents = self.align_representation.iter().flat_map(|m| m.keys());
+        let int_idents = self.int_representation.iter().flat_map(|m| m.keys());
+        align_idents.chain(int_idents).for_each(|ident| {
+            paths
+                .entry(ident.to_string())
+                .or_insert_with(|| ident.clone().into());
+        });
+        paths
+    }
+
+    /// Builds the [IdlSubset] selected by the `include_*` and `exclude_*` options, checking
+    /// that every name they reference is defined in the IDL.
+    fn subset(&self, idl: &Idl, errors: &mut darling::error::Accumulator) -> IdlSubset {
+        let instructions = idl
+            .instructions
+            .iter()
+            .map(|ix| ix.name.to_snake_case())
+            .collect::<HashSet<_>>();
+        let accounts = idl
+            .accounts
+            .iter()
+            .map(|def| def.name.clone())
+            .collect::<HashSet<_>>();
+        let types = idl
+            .types
+            .iter()
+            .map(|def| def.name.clone())
+            .collect::<HashSet<_>>();
+        let mut names = |list: &Option<PathList>, defined: &HashSet<String>, kind: &str| {
+            list.as_ref().map(|list| {
+                list.iter()
+                    .filter_map(|path| {
+                        let name = match path.get_ident() {
+                            Some(ident) if kind == "instruction" => {
+                                ident.to_string().to_snake_case()
+                            }
+                            Some(ident) => ident.to_string(),
+                            None => {
+                                errors.push(
+                                    darling::Error::custom(format!("expected {} name", kind))
+                                        .with_span(path),
+                                );
+                                return None;
+                            }
+                        };
+                        if !defined.contains(&name) {
+                            errors.push(
+                                darling::Error::custom(format!(
+                                    "no {} named `{}` in the IDL",
+                                    kind, name
+                                ))
+                                .with_span(path),
+                            );
+                        }
+                        Some(name)
+                    })
+                    .collect::<BTreeSet<_>>()
+            })
+        };
+        IdlSubset {
+            include_instructions: names(&self.include_instructions, &instructions, "instruction"),
+            exclude_instructions: names(&self.exclude_instructions, &instructions, "instruction")
+                .unwrap_or_default(),
+            include_accounts: names(&self.include_accounts, &accounts, "account"),
+            include_types: names(&self.include_types, &types, "type"),
+        }
+    }
+
+    /// Builds the [ModuleLayout] selected by the module options, checking that no two
+    /// modules have the same name.
+    fn layout(&self, idl: &Idl, errors: &mut darling::error::Accumulator) -> ModuleLayout {
+        let defaults = ModuleLayout::default();
+        let layout = ModuleLayout {
+            module: self.module.clone(),
+            program: self.program_module.clone(),
+            typedefs: self.typedefs_module.clone().unwrap_or(defaults.typedefs),
+            state: self.state_module.clone().unwrap_or(defaults.state),
+            ix_accounts: self
+                .ix_accounts_module
+                .clone()
+                .unwrap_or(defaults.ix_accounts),
+            glob_reexports: self.glob_reexports.unwrap_or(defaults.glob_reexports),
+        };
+
+        // modules generated next to the configurable ones, by anchor-gen or by Anchor
+        let mut taken: HashMap<String, String> = [
+            "logs",
+            "events",
+            "errors",
+            "client",
+            "fuzz",
+            "returns",
+            "instruction",
+            "accounts",
+            "cpi",
+            "program",
+        ]
+        .into_iter()
+        .map(|name| (name.to_string(), "a generated module".to_string()))
+        .collect();
+        let program = layout
+            .program
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}", idl.name));
+        [
+            (&layout.typedefs, "typedefs_module"),
+            (&layout.state, "state_module"),
+            (&layout.ix_accounts, "ix_accounts_module"),
+            (&program, "program_module"),
+        ]
+        .into_iter()
+        .chain(layout.module.iter().map(|module| (module, "module")))
+        .for_each(|(ident, option)| {
+            if let Some(other) = taken.insert(ident.to_string(), format!("`{}`", option)) {
+                errors.push(
+                    darling::Error::custom(format!(
+                        "`{} = \"{}\"` has the same name as {}",
+                        option, ident, other
+                    ))
+                    .with_span(ident),
+                );
+            }
+        });
+        layout
+    }
+
+    /// Reads the IDL from whichever of `idl_path`, `idl_account_path`, `idl_json` or `idl_env`
+    /// is specified.
+    fn load_idl(&self) -> darling::Result<(anchor_syn::idl::Idl, IdlDependencies)> {
+        let specified = [
+            ("idl_path", self.idl_path.is_some()),
+            ("idl_account_path", self.idl_account_path.is_some()),
+            ("idl_json", self.idl_json.is_some()),
+            ("idl_env", self.idl_env.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, is_some)| *is_some)
+        .map(|(name, _)| format!("`{}`", name))
+        .collect::<Vec<_>>();
+        if specified.len() != 1 {
+            let found = if specified.is_empty() {
+                "none".to_string()
+            } else {
+                specified.join(", ")
+            };
+            return Err(darling::Error::custom(format!(
+                "exactly one of `idl_path`, `idl_account_path`, `idl_json` or `idl_env` must be specified, found {}",
+                found
+            )));
+        }
+
+        let mut deps = IdlDependencies::default();
+        let idl = if let Some(idl_path) = &self.idl_path {
+            read_idl_file("idl_path", idl_path, IdlFormat::Json, &mut deps)?
+        } else if let Some(idl_account_path) = &self.idl_account_path {
+            read_idl_file(
+                "idl_account_path",
+                idl_account_path,
+                IdlFormat::Account,
+                &mut deps,
+            )?
+        } else if let Some(idl_json) = &self.idl_json {
+            parse_idl_json(idl_json.as_bytes())
+                .map_err(|e| darling::Error::custom(format!("could not parse `idl_json`: {}", e)))?
+        } else {
+            let var = self.idl_env.as_ref().unwrap();
+            let idl_path = env::var(var).map_err(|_| {
+                darling::Error::custom(format!("environment variable `{}` is not set", var))
+            })?;
+            deps.env_vars.push(var.clone());
+            read_idl_file("idl_env", &idl_path, IdlFormat::Json, &mut deps)?
+        };
+        Ok((idl, deps))
+    }
+
+    pub fn to_generator(&self) -> darling::Result<Generator> {
+        let (mut idl, dependencies) = self.load_idl()?;
+        let mut errors = darling::Error::accumulator();
+
+        self.placeholder_types
+            .iter()
+            .flatten()
+            .for_each(|(ident, placeholder)| {
+                let name = ident.to_string();
+                if idl
+                    .types
+                    .iter()
+                    .chain(idl.accounts.iter())
+                    .any(|def| def.name == name)
+                {
+                    errors.push(
+                        darling::Error::custom(format!("`{}` is already defined in the IDL", name))
+                            .with_span(ident),
+                    );
+                } else {
+                    idl.types.push(placeholder.to_type_definition(&name));
+                }
+            });
 
         let zero_copy_safe = path_list_to_string(self.zero_copy.as_ref());
 
@@ -54,60 +396,297 @@ impl GeneratorOptions {
 
         let packed_repr = path_list_to_string(self.packed_representation.as_ref());
 
-        let repr = c_repr
-            .union(&transparent_repr)
-            .cloned()
-            .collect::<HashSet<_>>()
-            .union(&packed_repr)
-            .cloned()
-            .collect::<HashSet<_>>();
+        let align_repr: HashMap<String, u32> = self
+            .align_representation
+            .iter()
+            .flatten()
+            .map(|(ident, align)| (ident.to_string(), *align))
+            .collect();
 
-        let zero_copy = zero_copy_safe
-            .union(&zero_copy_unsafe)
-            .cloned()
-            .collect::<HashSet<_>>();
+        let int_repr: HashMap<String, IntRepresentation> = self
+            .int_representation
+            .iter()
+            .flatten()
+            .map(|(ident, repr)| (ident.to_string(), *repr))
+            .collect();
+
+        // whether each type in the IDL is an enum
+        let is_enum: HashMap<&str, bool> = idl
+            .types
+            .iter()
+            .chain(idl.accounts.iter())
+            .map(|def| {
+                let is_enum = matches!(def.ty, anchor_syn::idl::IdlTypeDefinitionTy::Enum { .. });
+                (def.name.as_str(), is_enum)
+            })
+            .collect();
+        // whether each struct in the IDL is plain old data, as safe zero copy structs must be
+        let types = TypeTable::new(&idl.types);
+        let is_pod: HashMap<&str, bool> = idl
+            .types
+            .iter()
+            .chain(idl.accounts.iter())
+            .map(|def| {
+                let is_pod = match &def.ty {
+                    anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => {
+                        types.field_list_info(fields).is_pod
+                    }
+                    anchor_syn::idl::IdlTypeDefinitionTy::Enum { .. } => false,
+                };
+                (def.name.as_str(), is_pod)
+            })
+            .collect();
 
+        let paths = self.type_paths();
         let mut struct_opts: BTreeMap<String, StructOpts> = BTreeMap::new();
-        let all_structs: HashSet<&String> = zero_copy.union(&repr).collect::<HashSet<_>>();
-        all_structs.into_iter().for_each(|name| {
-            let is_c_repr = c_repr.contains(name);
-            let is_transparent_repr = transparent_repr.contains(name);
-            let is_packed_repr = packed_repr.contains(name);
-
-            let representation = match (is_c_repr, is_transparent_repr, is_packed_repr) {
-                (true, false, false) => Some(Representation::C),
-                (false, true, false) => Some(Representation::Transparent),
-                (false, false, true) => Some(Representation::Packed),
-                (false, false, false) => None,
-                _ => panic!("a type cannot have many representation"),
+        paths.iter().for_each(|(name, path)| {
+            let mut push_error =
+                |msg: String| errors.push(darling::Error::custom(msg).with_span(path));
+
+            let is_enum = match is_enum.get(name.as_str()) {
+                Some(is_enum) => *is_enum,
+                None => {
+                    push_error(format!("`{}` is not defined in the IDL", name));
+                    return;
+                }
             };
 
+            let mut reprs = vec![];
+            if c_repr.contains(name) {
+                reprs.push(Representation::C);
+            }
+            if transparent_repr.contains(name) {
+                reprs.push(Representation::Transparent);
+            }
+            if packed_repr.contains(name) {
+                reprs.push(Representation::Packed);
+            }
+            if let Some(int) = int_repr.get(name) {
+                reprs.push(Representation::Int(*int));
+            }
+            if reprs.len() > 1 {
+                push_error(format!(
+                    "`{}` cannot have more than one representation, found {}",
+                    name,
+                    reprs
+                        .iter()
+                        .map(|repr| format!("`repr({})`", repr.name()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            let representation = reprs.first().copied();
+
+            match representation {
+                Some(Representation::Transparent | Representation::Packed) if is_enum => {
+                    push_error(format!(
+                        "enum `{}` cannot have representation `repr({})`",
+                        name,
+                        representation.unwrap().name()
+                    ));
+                }
+                Some(Representation::Int(_)) if !is_enum => {
+                    push_error(format!(
+                        "integer representation is only valid on enums, but `{}` is a struct",
+                        name
+                    ));
+                }
+                _ => {}
+            }
+
+            let align = align_repr.get(name).copied();
+            if let Some(align) = align {
+                if !align.is_power_of_two() || align > 1 << 29 {
+                    push_error(format!(
+                        "alignment of `{}` must be a power of two no greater than 2^29, got {}",
+                        name, align
+                    ));
+                }
+                if let Some(repr @ (Representation::Transparent | Representation::Packed)) =
+                    representation
+                {
+                    push_error(format!(
+                        "`{}` cannot be both `repr({})` and `repr(align)`",
+                        name,
+                        repr.name()
+                    ));
+                }
+            }
+
             let is_zero_copy_safe = zero_copy_safe.contains(name);
             let is_zero_copy_unsafe = zero_copy_unsafe.contains(name);
 
             let zero_copy = match (is_zero_copy_safe, is_zero_copy_unsafe) {
-                (true, true) => panic!("cant be safe and unsafe zero copy at the same time"),
+                (true, true) => {
+                    push_error(format!(
+                        "`{}` cannot be safe and unsafe zero copy at the same time",
+                        name
+                    ));
+                    None
+                }
                 (true, false) => Some(ZeroCopy::Safe),
                 (false, true) => Some(ZeroCopy::Unsafe),
                 (false, false) => None,
             };
+            if zero_copy.is_some() && is_enum {
+                push_error(format!("enum `{}` cannot be zero copy", name));
+            } else if is_zero_copy_safe && !is_pod[name.as_str()] {
+                push_error(format!(
+                    "`{}` cannot be safe zero copy, since some of its fields are not plain old \
+                     data, i.e. numbers, public keys, or arrays or structs of them",
+                    name
+                ));
+            }
+            // the derived Borsh and `Debug` implementations take references to the fields
+            if matches!(representation, Some(Representation::Packed)) && zero_copy.is_none() {
+                push_error(format!(
+                    "`{}` cannot be `repr(packed)` unless it is zero copy, since references to \
+                     its fields may be unaligned",
+                    name
+                ));
+            }
 
             struct_opts.insert(
                 name.to_string(),
                 StructOpts {
                     representation,
+                    align,
                     zero_copy,
                 },
             );
         });
 
-        Generator { idl, struct_opts }
+        let mut extern_types = BTreeMap::new();
+        self.extern_types
+            .iter()
+            .flatten()
+            .for_each(|(ident, path)| {
+                let name = ident.to_string();
+                if !idl.types.iter().any(|def| def.name == name) {
+                    errors.push(
+                        darling::Error::custom(format!(
+                            "`{}` is not a type defined in the IDL",
+                            name
+                        ))
+                        .with_span(ident),
+                    );
+                } else if struct_opts.contains_key(&name) {
+                    errors.push(
+                        darling::Error::custom(format!(
+                            "extern type `{}` cannot have representation or zero copy options",
+                            name
+                        ))
+                        .with_span(ident),
+                    );
+                }
+                extern_types.insert(name, path.clone());
+            });
+
+        // the discriminants of Shank instructions come from the IDL
+        let discriminators = match &self.discriminators {
+            Some(DiscriminatorStrategy::Anchor) => DiscriminatorStrategy::Anchor,
+            Some(DiscriminatorStrategy::Shank { .. }) => DiscriminatorStrategy::shank(&idl),
+            None => DiscriminatorStrategy::detect(&idl),
+        };
+        if let Some(max) = discriminators.max_instructions() {
+            if idl.instructions.len() > max {
+                errors.push(darling::Error::custom(format!(
+                    "the IDL has {} instructions, but programs using {:?} discriminators have at most {}",
+                    idl.instructions.len(),
+                    discriminators,
+                    max
+                )));
+            }
+        }
+        if let DiscriminatorStrategy::Shank { .. } = discriminators {
+            if self.handler.is_present() {
+                errors.push(
+                    darling::Error::custom(
+                        "`handler` requires a `#[program]` module, which programs using Shank \
+                         discriminators do not get",
+                    )
+                    .with_span(&self.handler),
+                );
+            }
+            for def in &idl.accounts {
+                if let Some(StructOpts {
+                    zero_copy: Some(_), ..
+                }) = struct_opts.get(&def.name)
+                {
+                    errors.push(
+                        darling::Error::custom(format!(
+                            "account `{}` cannot be zero copy, since accounts without a \
+                                 discriminator are deserialized with Borsh",
+                            def.name
+                        ))
+                        .with_span(&paths[&def.name]),
+                    );
+                }
+            }
+        }
+        // computed before instructions are filtered out, since they may depend on positions
+        let instruction_discriminators = idl
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, ix)| {
+                (
+                    ix.name.clone(),
+                    discriminators.instruction_discriminator(index, &ix.name),
+                )
+            })
+            .collect();
+
+        let layout = self.layout(&idl, &mut errors);
+        let idl = self.subset(&idl, &mut errors).apply(&idl);
+        for unresolved in find_unresolved_types(&idl) {
+            errors.push(darling::Error::custom(unresolved.to_string()));
+        }
+        for infinite in find_infinite_types(&idl) {
+            errors.push(darling::Error::custom(infinite.to_string()));
+        }
+
+        let type_naming = self.type_naming.unwrap_or(NamingConvention::Preserve);
+        let (idl, renames) = rename_types(&idl, type_naming);
+        let renamed = |name: String| renames.get(&name).cloned().unwrap_or(name);
+        let struct_opts = struct_opts
+            .into_iter()
+            .map(|(name, opts)| (renamed(name), opts))
+            .collect();
+        let extern_types = extern_types
+            .into_iter()
+            .map(|(name, path)| (renamed(name), path))
+            .collect();
+        let mut names = HashSet::new();
+        for def in idl.types.iter().chain(idl.accounts.iter()) {
+            if !names.insert(&def.name) {
+                errors.push(darling::Error::custom(format!(
+                    "more than one type or account is named `{}`",
+                    def.name
+                )));
+            }
+        }
+
+        errors.finish_with(Generator {
+            idl,
+            struct_opts,
+            extern_types,
+            client: self.client.is_present(),
+            handler: self.handler.is_present(),
+            arbitrary: self.arbitrary.is_present(),
+            dependencies,
+            layout,
+            field_naming: self.field_naming.unwrap_or(NamingConvention::Rust),
+            discriminators,
+            instruction_discriminators,
+        })
     }
 }
 
 #[derive(Clone, Copy, Default)]
 pub struct StructOpts {
     pub representation: Option<Representation>,
+    pub align: Option<u32>,
     pub zero_copy: Option<ZeroCopy>,
 }
 
@@ -122,21 +701,148 @@ pub enum Representation {
     C,
     Transparent,
     Packed,
+    Int(IntRepresentation),
+}
+
+impl Representation {
+    /// Name of the representation as written inside `repr(...)`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Representation::C => "C",
+            Representation::Transparent => "transparent",
+            Representation::Packed => "packed",
+            Representation::Int(int) => int.name(),
+        }
+    }
+}
+
+/// Primitive integer representation of an enum.
+#[derive(Clone, Copy, FromMeta)]
+pub enum IntRepresentation {
+    #[darling(rename = "u8")]
+    U8,
+    #[darling(rename = "i8")]
+    I8,
+    #[darling(rename = "u16")]
+    U16,
+    #[darling(rename = "i16")]
+    I16,
+    #[darling(rename = "u32")]
+    U32,
+    #[darling(rename = "i32")]
+    I32,
+    #[darling(rename = "u64")]
+    U64,
+    #[darling(rename = "i64")]
+    I64,
+}
+
+impl IntRepresentation {
+    /// Name of the integer type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntRepresentation::U8 => "u8",
+            IntRepresentation::I8 => "i8",
+            IntRepresentation::U16 => "u16",
+            IntRepresentation::I16 => "i16",
+            IntRepresentation::U32 => "u32",
+            IntRepresentation::I32 => "i32",
+            IntRepresentation::U64 => "u64",
+            IntRepresentation::I64 => "i64",
+        }
+    }
 }
+
 pub struct Generator {
     pub idl: anchor_syn::idl::Idl,
     pub struct_opts: BTreeMap<String, StructOpts>,
+    /// Types re-exported from other crates instead of being generated.
+    pub extern_types: BTreeMap<String, syn::Path>,
+    /// Whether to generate the `client` module.
+    pub client: bool,
+    /// Whether to generate the `handler` module, which the `#[program]` module delegates to.
+    pub handler: bool,
+    /// Whether to generate the `fuzz` module.
+    pub arbitrary: bool,
+    /// Files and environment variables the IDL was loaded from.
+    pub dependencies: IdlDependencies,
+    /// Names and nesting of the generated modules.
+    pub layout: ModuleLayout,
+    /// Naming convention of fields.
+    pub field_naming: NamingConvention,
+    /// How instructions and accounts are discriminated.
+    pub discriminators: DiscriminatorStrategy,
+    /// Discriminator of every instruction, by name.
+    pub instruction_discriminators: BTreeMap<String, Vec<u8>>,
 }
 
 impl Generator {
     pub fn generate_cpi_interface(&self) -> TokenStream {
         let idl = &self.idl;
-        let program_name: Ident = format_ident!("{}", idl.name);
+        let layout = &self.layout;
+        let program_name: Ident = layout
+            .program
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}", idl.name));
+        let typedefs_mod = &layout.typedefs;
+        let state_mod = &layout.state;
+        let ix_accounts_mod = &layout.ix_accounts;
 
-        let accounts = generate_accounts(&idl.types, &idl.accounts, &self.struct_opts);
-        let typedefs = generate_typedefs(&idl.types, &self.struct_opts);
-        let ix_handlers = generate_ix_handlers(&idl.instructions);
+        let types = TypeTable::new(&idl.types);
+        let accounts = generate_accounts(
+            &types,
+            &idl.accounts,
+            &self.struct_opts,
+            self.field_naming,
+            &self.discriminators,
+        );
+        let typedefs = generate_typedefs(
+            &types,
+            &self.struct_opts,
+            &self.extern_types,
+            self.field_naming,
+        );
+        let qualified_ixs = layout.qualify_types(&idl.instructions, &idl.accounts);
+        let returns = generate_returns(&qualified_ixs);
         let ix_structs = generate_ix_structs(&idl.instructions);
+        let events = generate_events(
+            &types,
+            idl.events.as_deref().unwrap_or_default(),
+            self.field_naming,
+        );
+        let logs = generate_logs();
+        let errors = match &idl.errors {
+            Some(errors) if !errors.is_empty() => generate_errors(errors),
+            _ => quote! {},
+        };
+        let handler = if self.handler {
+            generate_handler(&qualified_ixs, layout)
+        } else {
+            quote! {}
+        };
+        let fuzz = if self.arbitrary {
+            generate_fuzz(
+                &types,
+                idl,
+                &self.struct_opts,
+                &self.extern_types,
+                self.field_naming,
+                &self.discriminators,
+                self.handler,
+            )
+        } else {
+            quote! {}
+        };
+        let client = if self.client {
+            generate_client(
+                &types,
+                &idl.accounts,
+                &self.struct_opts,
+                &self.discriminators,
+            )
+        } else {
+            quote! {}
+        };
 
         let docs = format!(
         " Anchor CPI crate generated from {} v{} using [anchor-gen](https://crates.io/crates/anchor-gen) v{}.",
@@ -145,38 +851,196 @@ impl Generator {
         &GEN_VERSION.unwrap_or("unknown")
     );
 
-        quote! {
-            use anchor_lang::prelude::*;
+        let dependencies = self.dependencies.to_tokens();
 
-            pub mod typedefs {
+        let reexport = if layout.glob_reexports {
+            quote! { pub use }
+        } else {
+            quote! { use }
+        };
+        // names which collide with the prelude are imported explicitly, so that they take
+        // precedence over its glob, e.g. the `Key` enum of Metaplex programs over
+        // `anchor_lang::Key`, whose methods stay in scope through `prelude_traits`. Other names
+        // are only re-exported through globs, which items of the crate may shadow.
+        let shadows_prelude = |name: &String| PRELUDE_TYPE_NAMES.contains(&name.as_str());
+        let type_names = idl
+            .types
+            .iter()
+            .filter(|def| !self.extern_types.contains_key(&def.name))
+            .filter(|def| shadows_prelude(&def.name))
+            .map(|def| format_ident!("{}", def.name))
+            .collect::<Vec<_>>();
+        let account_names = idl
+            .accounts
+            .iter()
+            .filter(|def| shadows_prelude(&def.name))
+            .map(|def| format_ident!("{}", def.name))
+            .collect::<Vec<_>>();
+        let state_shadows = (!account_names.is_empty()).then(|| {
+            quote! {
+                #reexport #state_mod::{#(#account_names),*};
+            }
+        });
+        let typedefs_shadows = (!type_names.is_empty()).then(|| {
+            quote! {
+                #reexport #typedefs_mod::{#(#type_names),*};
+            }
+        });
+        let items = quote! {
+            pub mod #typedefs_mod {
                 //! User-defined types.
                 use super::*;
                 #typedefs
             }
 
-            pub mod state {
+            pub mod #state_mod {
                 //! Structs of accounts which hold state.
                 use super::*;
                 #accounts
             }
 
-            pub mod ix_accounts {
+            pub mod #ix_accounts_mod {
                 //! Accounts used in instructions.
                 use super::*;
                 #ix_structs
             }
 
-            use ix_accounts::*;
-            pub use state::*;
-            pub use typedefs::*;
+            #logs
 
-            #[program]
-            pub mod #program_name {
-                #![doc = #docs]
+            #events
 
-                use super::*;
-                #ix_handlers
+            #errors
+
+            #client
+
+            #handler
+
+            #fuzz
+
+            #returns
+
+            #reexport #state_mod::*;
+            #reexport #typedefs_mod::*;
+            #state_shadows
+            #typedefs_shadows
+        };
+        let prelude_traits = quote! {
+            use anchor_lang::prelude::{
+                AccountDeserialize as _, AccountSerialize as _, Accounts as _, AccountsExit as _,
+                Id as _, Key as _, Owner as _, ToAccountInfo as _, ToAccountInfos as _,
+                ToAccountMetas as _,
+            };
+        };
+        // Anchor refers to the items generated for the accounts of instructions through `crate::`
+        let items = match &layout.module {
+            Some(module) => quote! {
+                pub mod #module {
+                    use anchor_lang::prelude::*;
+                    #prelude_traits
+                    use crate::ID;
+
+                    #items
+                }
+
+                use #module::#ix_accounts_mod::*;
+            },
+            None => quote! {
+                #items
+
+                use #ix_accounts_mod::*;
+            },
+        };
+
+        let program = match self.discriminators {
+            DiscriminatorStrategy::Anchor => {
+                let handler_path = self.handler.then(|| {
+                    format!("{}::handler", layout.scope_path())
+                        .parse::<TokenStream>()
+                        .unwrap()
+                });
+                let ix_handlers = generate_ix_handlers(&qualified_ixs, handler_path.as_ref());
+                // items of the crate root, e.g. a `Result` alias, shadow the prelude when the
+                // generated items are wrapped in a module
+                let prelude = layout.module.as_ref().map(|_| {
+                    quote! {
+                        use anchor_lang::prelude::{Context, Result};
+                    }
+                });
+                quote! {
+                    #[program]
+                    pub mod #program_name {
+                        #![doc = #docs]
+
+                        use super::*;
+                        #prelude
+                        #ix_handlers
+                    }
+                }
+            }
+            DiscriminatorStrategy::Shank { .. } => {
+                generate_native_ix_modules(&qualified_ixs, &self.instruction_discriminators, layout)
             }
+        };
+
+        // the code `#[program]` generates at the crate root refers to the prelude, which items
+        // of the crate shadow since it is a glob import
+        let prelude = match &layout.module {
+            Some(_) => quote! {
+                use anchor_lang::prelude::*;
+            },
+            None => quote! {
+                use anchor_lang::prelude::*;
+                #prelude_traits
+            },
+        };
+
+        quote! {
+            #prelude
+
+            #dependencies
+
+            #items
+
+            #program
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "vaults",
+        "instructions": [],
+        "accounts": [{
+            "name": "Vault",
+            "type": {
+                "kind": "struct",
+                "fields": [{ "name": "bump", "type": "u8" }, { "name": "amount", "type": "u64" }]
+            }
+        }]
+    }"#;
+
+    fn vault() -> PathList {
+        PathList::new(vec![syn::parse_str::<syn::Path>("Vault").unwrap()])
+    }
+
+    #[test]
+    fn test_packed_requires_zero_copy() {
+        let options = GeneratorOptions {
+            idl_json: Some(IDL.to_string()),
+            packed_representation: Some(vault()),
+            ..Default::default()
+        };
+        let error = options.to_generator().err().unwrap().to_string();
+        assert!(error.contains("`Vault` cannot be `repr(packed)` unless it is zero copy"));
+
+        let options = GeneratorOptions {
+            zero_copy_unsafe: Some(vault()),
+            ..options
+        };
+        assert!(options.to_generator().is_ok());
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+};
+
+use anchor_syn::idl::{EnumFields, Idl, IdlType, IdlTypeDefinitionTy};
+
+use crate::{ty_to_rust_type, TypeTable};
+
+/// Converts the [IdlType] of a field of `parent` to a [String] of the Rust representation.
+///
+/// Unlike [ty_to_rust_type], optional values which contain `parent` are boxed, since `parent`
+/// would otherwise have an infinite size.
+///
+/// ```
+/// let defs: Vec<anchor_idl::IdlTypeDefinition> = serde_json::from_str(r#"[{
+///     "name": "Node",
+///     "type": {
+///         "kind": "struct",
+///         "fields": [
+///             { "name": "children", "type": { "vec": { "defined": "Node" } } },
+///             { "name": "parent", "type": { "option": { "defined": "Node" } } }
+///         ]
+///     }
+/// }]"#).unwrap();
+/// let types = anchor_idl::TypeTable::new(&defs);
+/// let field_ty = |ty| anchor_idl::field_ty_to_rust_type(&types, "Node", ty);
+/// let fields = match &defs[0].ty {
+///     anchor_idl::IdlTypeDefinitionTy::Struct { fields } => fields,
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(field_ty(&fields[0].ty), "Vec<Node>");
+/// assert_eq!(field_ty(&fields[1].ty), "Option<Box<Node>>");
+/// ```
+pub fn field_ty_to_rust_type(types: &TypeTable, parent: &str, ty: &IdlType) -> String {
+    if !types.is_recursive(parent) {
+        return ty_to_rust_type(ty);
+    }
+    match ty {
+        IdlType::Option(inner) if contains_inline(types, inner, parent, &mut BTreeSet::new()) => {
+            format!("Option<Box<{}>>", ty_to_rust_type(inner))
+        }
+        IdlType::Array(inner, size) => {
+            format!(
+                "[{}; {}]",
+                field_ty_to_rust_type(types, parent, inner),
+                size
+            )
+        }
+        _ => ty_to_rust_type(ty),
+    }
+}
+
+/// Whether a value of type `ty` contains a value of type `name` without indirection,
+/// i.e. other than through a `Vec`.
+fn contains_inline<'a>(
+    types: &TypeTable<'a>,
+    ty: &IdlType,
+    name: &str,
+    visited: &mut BTreeSet<&'a str>,
+) -> bool {
+    match ty {
+        IdlType::Defined(inner) if inner == name => true,
+        IdlType::Defined(inner) => {
+            let def = match types.get(inner) {
+                Some(def) if visited.insert(&def.name) => def,
+                _ => return false,
+            };
+            match &def.ty {
+                IdlTypeDefinitionTy::Struct { fields } => fields
+                    .iter()
+                    .any(|field| contains_inline(types, &field.ty, name, visited)),
+                IdlTypeDefinitionTy::Enum { variants } => {
+                    variants.iter().any(|variant| match &variant.fields {
+                        Some(EnumFields::Named(fields)) => fields
+                            .iter()
+                            .any(|field| contains_inline(types, &field.ty, name, visited)),
+                        Some(EnumFields::Tuple(tuple)) => tuple
+                            .iter()
+                            .any(|ty| contains_inline(types, ty, name, visited)),
+                        None => false,
+                    })
+                }
+            }
+        }
+        IdlType::Option(inner) | IdlType::Array(inner, _) => {
+            contains_inline(types, inner, name, visited)
+        }
+        _ => false,
+    }
+}
+
+/// A type which always contains itself, and thus cannot be represented.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InfiniteType {
+    /// Name of the type.
+    pub name: String,
+    /// Paths of the fields through which the type contains itself, e.g. `["A.b", "B.a"]`.
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for InfiniteType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|path| format!("`{}`", path))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        write!(
+            f,
+            "type `{}` contains itself through {} and would have an infinite size; \
+             one of these fields must be an `option` or a `vec`",
+            self.name, fields
+        )
+    }
+}
+
+/// Finds the structs of an IDL which contain themselves through fields which are neither
+/// optional nor vectors. Each cycle is reported once, named after the first of its types
+/// in the IDL.
+///
+/// ```
+/// let idl: anchor_idl::Idl = serde_json::from_str(r#"{
+///     "version": "0.1.0",
+///     "name": "linked_list",
+///     "instructions": [],
+///     "types": [
+///         {
+///             "name": "Node",
+///             "type": {
+///                 "kind": "struct",
+///                 "fields": [{ "name": "next", "type": { "defined": "Link" } }]
+///             }
+///         },
+///         {
+///             "name": "Link",
+///             "type": {
+///                 "kind": "struct",
+///                 "fields": [{ "name": "nodes", "type": { "array": [{ "defined": "Node" }, 2] } }]
+///             }
+///         }
+///     ]
+/// }"#).unwrap();
+/// let infinite = anchor_idl::find_infinite_types(&idl);
+/// assert_eq!(infinite.len(), 1);
+/// assert_eq!(infinite[0].name, "Node");
+/// assert_eq!(infinite[0].fields, ["Node.next", "Link.nodes[i]"]);
+/// ```
+pub fn find_infinite_types(idl: &Idl) -> Vec<InfiniteType> {
+    let types = TypeTable::new(&idl.types);
+    let mut search = CycleSearch {
+        types: &types,
+        explored: HashSet::new(),
+        visiting: HashSet::new(),
+        path: vec![],
+        infinite: vec![],
+    };
+    for def in &idl.types {
+        search.visit(&def.name);
+    }
+    search.infinite
+}
+
+/// Depth-first search of the mandatory fields of structs, which explores every type once.
+struct CycleSearch<'a, 't> {
+    types: &'t TypeTable<'a>,
+    /// Types whose fields have all been searched.
+    explored: HashSet<&'a str>,
+    /// Types whose fields are being searched.
+    visiting: HashSet<&'a str>,
+    /// Fields being searched, along with the type they belong to.
+    path: Vec<(&'a str, String)>,
+    infinite: Vec<InfiniteType>,
+}
+
+impl<'a, 't> CycleSearch<'a, 't> {
+    fn visit(&mut self, name: &'a str) {
+        if self.explored.contains(name) {
+            return;
+        }
+        if let Some(IdlTypeDefinitionTy::Struct { fields }) =
+            self.types.get(name).map(|def| &def.ty)
+        {
+            self.visiting.insert(name);
+            for field in fields {
+                let (next, suffix) = match mandatory_reference(&field.ty) {
+                    Some(reference) => reference,
+                    None => continue,
+                };
+                self.path
+                    .push((name, format!("{}.{}{}", name, field.name, suffix)));
+                if self.visiting.contains(next) {
+                    // the fields from `next` onwards lead back to it
+                    let start = self.path.iter().position(|(ty, _)| *ty == next).unwrap();
+                    self.infinite.push(InfiniteType {
+                        name: next.to_string(),
+                        fields: self.path[start..]
+                            .iter()
+                            .map(|(_, field)| field.clone())
+                            .collect(),
+                    });
+                } else {
+                    self.visit(next);
+                }
+                self.path.pop();
+            }
+            self.visiting.remove(name);
+        }
+        self.explored.insert(name);
+    }
+}
+
+/// The type which every value of `ty` contains, along with the suffix of its path.
+fn mandatory_reference(ty: &IdlType) -> Option<(&str, String)> {
+    match ty {
+        IdlType::Defined(name) => Some((name, String::new())),
+        IdlType::Array(inner, size) if *size > 0 => {
+            mandatory_reference(inner).map(|(name, suffix)| (name, format!("[i]{}", suffix)))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use anchor_syn::idl::{EnumFields, Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+use darling::FromMeta;
+
+/// A type referenced by an IDL which it does not define.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnresolvedType {
+    /// Name of the type.
+    pub name: String,
+    /// Paths of the fields referencing the type, e.g. `Account.field[i]`.
+    pub references: Vec<String>,
+    /// Name of a defined type which was likely meant instead.
+    pub suggestion: Option<String>,
+    /// Whether the name is defined as an account, which cannot be used as a type.
+    pub is_account: bool,
+}
+
+impl fmt::Display for UnresolvedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let references = self
+            .references
+            .iter()
+            .map(|path| format!("`{}`", path))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "unknown type `{}`, referenced by {}",
+            self.name, references
+        )?;
+        if self.is_account {
+            write!(f, "; `{}` is an account, not a type", self.name)?;
+        }
+        match &self.suggestion {
+            Some(suggestion) => write!(f, "; did you mean `{}`?", suggestion),
+            None => write!(
+                f,
+                "; if it is defined by another crate, declare it with `placeholder_types({} = \"[u8; N]\")`",
+                self.name
+            ),
+        }
+    }
+}
+
+/// Finds every type referenced by an IDL but missing from its `types`.
+///
+/// ```
+/// let mut idl: anchor_idl::Idl =
+///     serde_json::from_str(include_str!("../../../examples/govern-cpi/idl.json")).unwrap();
+/// let meta = idl.types.iter_mut().find(|def| def.name == "ProposalAccountMeta").unwrap();
+/// meta.name = "ProposalAccountMetas".to_string();
+/// let governor = idl.types.iter().position(|def| def.name == "GovernanceParameters").unwrap();
+/// idl.types.remove(governor);
+///
+/// let unresolved = anchor_idl::find_unresolved_types(&idl);
+/// assert_eq!(unresolved.len(), 2);
+/// assert_eq!(unresolved[0].name, "GovernanceParameters");
+/// assert!(unresolved[0].references.contains(&"Governor.params".to_string()));
+/// assert_eq!(unresolved[0].suggestion, None);
+/// assert_eq!(unresolved[1].name, "ProposalAccountMeta");
+/// assert_eq!(unresolved[1].references, ["ProposalInstruction.keys[i]"]);
+/// assert_eq!(unresolved[1].suggestion.as_deref(), Some("ProposalAccountMetas"));
+/// ```
+pub fn find_unresolved_types(idl: &Idl) -> Vec<UnresolvedType> {
+    let defined = idl
+        .types
+        .iter()
+        .map(|def| def.name.as_str())
+        .collect::<HashSet<_>>();
+    let mut references: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut visit = |path: String, ty: &IdlType| {
+        collect_defined(&path, ty, &mut |name, path| {
+            if !defined.contains(name) {
+                references
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(path.to_string());
+            }
+        })
+    };
+
+    for ix in &idl.instructions {
+        for arg in &ix.args {
+            visit(format!("{}.{}", ix.name, arg.name), &arg.ty);
+        }
+        if let Some(returns) = &ix.returns {
+            visit(format!("{}.returns", ix.name), returns);
+        }
+    }
+    for def in idl.accounts.iter().chain(idl.types.iter()) {
+        visit_def(def, &mut visit);
+    }
+    for event in idl.events.iter().flatten() {
+        for field in &event.fields {
+            visit(format!("{}.{}", event.name, field.name), &field.ty);
+        }
+    }
+
+    references
+        .into_iter()
+        .map(|(name, references)| {
+            let is_account = idl.accounts.iter().any(|def| def.name == name);
+            let suggestion = suggest(&name, idl.types.iter().map(|def| def.name.as_str()));
+            UnresolvedType {
+                name,
+                references,
+                suggestion,
+                is_account,
+            }
+        })
+        .collect()
+}
+
+fn visit_def(def: &IdlTypeDefinition, visit: &mut impl FnMut(String, &IdlType)) {
+    match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => visit_fields(&def.name, fields, visit),
+        IdlTypeDefinitionTy::Enum { variants } => {
+            for variant in variants {
+                let prefix = format!("{}::{}", def.name, variant.name);
+                match &variant.fields {
+                    Some(EnumFields::Named(fields)) => visit_fields(&prefix, fields, visit),
+                    Some(EnumFields::Tuple(types)) => {
+                        for (index, ty) in types.iter().enumerate() {
+                            visit(format!("{}.{}", prefix, index), ty);
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+fn visit_fields(prefix: &str, fields: &[IdlField], visit: &mut impl FnMut(String, &IdlType)) {
+    for field in fields {
+        visit(format!("{}.{}", prefix, field.name), &field.ty);
+    }
+}
+
+/// Calls `f` with every type name referenced by `ty`, and the path of the reference.
+fn collect_defined(path: &str, ty: &IdlType, f: &mut impl FnMut(&str, &str)) {
+    match ty {
+        IdlType::Defined(name) => f(name, path),
+        IdlType::Option(inner) => collect_defined(path, inner, f),
+        IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+            collect_defined(&format!("{}[i]", path), inner, f)
+        }
+        _ => {}
+    }
+}
+
+/// Finds the candidate closest to `name`, if any is close enough to be a likely typo.
+///
+/// Names qualified by a module, e.g. `other_crate::Name`, are also matched by their last segment.
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let unqualified = name.rsplit("::").next().unwrap_or(name);
+    let max_distance = (unqualified.len() / 3).max(1);
+    candidates
+        .map(|candidate| {
+            let distance = edit_distance(&unqualified.to_lowercase(), &candidate.to_lowercase());
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// An opaque type standing in for a type which is not defined by the IDL, e.g.
+/// `placeholder_types(Metadata = "[u8; 32]")`.
+///
+/// The size may also be given as a bare integer, e.g. `placeholder_types(Metadata = 32)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlaceholderType {
+    /// Size of the type in bytes.
+    pub len: usize,
+}
+
+impl FromMeta for PlaceholderType {
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        let len = match value {
+            syn::Lit::Int(len) => len.base10_parse()?,
+            syn::Lit::Str(ty) => {
+                let array = match ty.parse::<syn::Type>()? {
+                    syn::Type::Array(array) => array,
+                    _ => return Err(darling::Error::custom("expected `[u8; N]`").with_span(ty)),
+                };
+                let is_u8 =
+                    matches!(&*array.elem, syn::Type::Path(path) if path.path.is_ident("u8"));
+                match &array.len {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(len),
+                        ..
+                    }) if is_u8 => len.base10_parse()?,
+                    _ => return Err(darling::Error::custom("expected `[u8; N]`").with_span(ty)),
+                }
+            }
+            _ => return Err(darling::Error::unexpected_lit_type(value)),
+        };
+        Ok(PlaceholderType { len })
+    }
+}
+
+impl PlaceholderType {
+    /// Definition of the placeholder, a struct wrapping its bytes, which has the same
+    /// Borsh serialization as `[u8; N]`.
+    pub fn to_type_definition(&self, name: &str) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: name.to_string(),
+            docs: Some(vec![format!(
+                "Placeholder for `{}`, which is not defined by the IDL.",
+                name
+            )]),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![IdlField {
+                    name: "bytes".to_string(),
+                    docs: None,
+                    ty: IdlType::Array(Box::new(IdlType::U8), self.len),
+                }],
+            },
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use anchor_syn::idl::IdlInstruction;
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Generates the `returns` module, which decodes the values returned by instructions through
+/// the return data of a CPI.
+///
+/// The return types of the instructions must be qualified, see [crate::ModuleLayout::qualify_types].
+pub fn generate_returns(ixs: &[IdlInstruction]) -> TokenStream {
+    let decoders = ixs
+        .iter()
+        .filter_map(|ix| Some((ix, ix.returns.as_ref()?)))
+        .map(|(ix, returns)| {
+            let ix_name = format_ident!("{}", ix.name.to_snake_case());
+            let ty: TokenStream = crate::ty_to_rust_type(returns).parse().unwrap();
+            let doc = format!(
+                " Decodes the value returned by the `{}` instruction, once it has been invoked \
+                 through `cpi::{}`.",
+                ix.name, ix_name
+            );
+            quote! {
+                #[doc = #doc]
+                pub fn #ix_name() -> Result<#ty> {
+                    let data = program_return_data()?;
+                    AnchorDeserialize::try_from_slice(&data)
+                        .map_err(|_| error!(anchor_lang::error::ErrorCode::InstructionDidNotDeserialize))
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    if decoders.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        pub mod returns {
+            //! Values returned by instructions, decoded from the return data of a CPI.
+            use super::*;
+
+            /// Returns the return data of the last instruction invoked, checking that it was set
+            /// by this program rather than by another program invoked earlier.
+            fn program_return_data() -> Result<Vec<u8>> {
+                let (program_id, data) = anchor_lang::solana_program::program::get_return_data()
+                    .ok_or_else(|| error!(anchor_lang::error::ErrorCode::InstructionDidNotDeserialize))?;
+                require_keys_eq!(
+                    program_id,
+                    crate::ID,
+                    anchor_lang::error::ErrorCode::InvalidProgramId
+                );
+                Ok(data)
+            }
+
+            #(#decoders)*
+        }
+    }
+}
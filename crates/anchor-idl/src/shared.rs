@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anchor_syn::idl::{EnumFields, Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+
+/// A type defined by several programs with different layouts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// Name of the type.
+    pub name: String,
+    /// Programs defining the type, grouped by identical layouts.
+    pub groups: Vec<Vec<String>>,
+}
+
+/// Types which are defined identically by several programs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SharedTypes {
+    /// For every program, the types it should use from another program instead of
+    /// generating them, mapped to the program which generates them.
+    pub extern_types: BTreeMap<String, BTreeMap<String, String>>,
+    /// Types defined with different layouts by different programs.
+    pub mismatches: Vec<TypeMismatch>,
+}
+
+/// Finds the types defined by several of `idls`, keyed by program name.
+///
+/// Each type is generated by the first program, by name, which defines it. Other programs
+/// defining it with an identical layout use that program's type, including the layouts of
+/// every type it references.
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let idl: anchor_idl::Idl =
+///     serde_json::from_str(include_str!("../../../examples/govern-cpi/idl.json")).unwrap();
+/// let mut other = idl.clone();
+/// let meta = other.types.iter_mut().find(|def| def.name == "ProposalAccountMeta").unwrap();
+/// if let anchor_idl::IdlTypeDefinitionTy::Struct { fields } = &mut meta.ty {
+///     fields.pop();
+/// }
+///
+/// let idls = BTreeMap::from([("a".to_string(), idl), ("b".to_string(), other)]);
+/// let shared = anchor_idl::find_shared_types(&idls);
+/// assert_eq!(shared.extern_types["b"]["GovernanceParameters"], "a");
+/// // `ProposalInstruction` contains a `ProposalAccountMeta`, so neither can be shared
+/// assert!(!shared.extern_types["b"].contains_key("ProposalAccountMeta"));
+/// assert!(!shared.extern_types["b"].contains_key("ProposalInstruction"));
+/// assert_eq!(shared.mismatches.len(), 2);
+/// ```
+pub fn find_shared_types(idls: &BTreeMap<String, Idl>) -> SharedTypes {
+    let mut shared = SharedTypes::default();
+    let names = idls
+        .values()
+        .flat_map(|idl| idl.types.iter().map(|def| def.name.clone()))
+        .collect::<BTreeSet<_>>();
+    for name in names {
+        // programs defining the type, grouped by identical layouts
+        let mut groups: Vec<Vec<&String>> = vec![];
+        for (program, idl) in idls {
+            if !idl.types.iter().any(|def| def.name == name) {
+                continue;
+            }
+            let group = groups.iter_mut().find(|group| {
+                let owner = &idls[group[0]];
+                same_type(owner, idl, &name, &name, &mut BTreeSet::new())
+            });
+            match group {
+                Some(group) => group.push(program),
+                None => groups.push(vec![program]),
+            }
+        }
+        for group in &groups {
+            let (owner, users) = group.split_first().unwrap();
+            for user in users {
+                shared
+                    .extern_types
+                    .entry(user.to_string())
+                    .or_default()
+                    .insert(name.clone(), owner.to_string());
+            }
+        }
+        if groups.len() > 1 {
+            shared.mismatches.push(TypeMismatch {
+                name,
+                groups: groups
+                    .into_iter()
+                    .map(|group| group.into_iter().cloned().collect())
+                    .collect(),
+            });
+        }
+    }
+    shared
+}
+
+fn find_def<'a>(idl: &'a Idl, name: &str) -> Option<&'a IdlTypeDefinition> {
+    idl.types
+        .iter()
+        .chain(idl.accounts.iter())
+        .find(|def| def.name == name)
+}
+
+/// Whether type `a` of one IDL has the same layout as type `b` of another.
+///
+/// `visiting` holds the pairs of types being compared, which are assumed to be identical
+/// when they are reached again through a recursive type.
+fn same_type(
+    a_idl: &Idl,
+    b_idl: &Idl,
+    a: &str,
+    b: &str,
+    visiting: &mut BTreeSet<(String, String)>,
+) -> bool {
+    if !visiting.insert((a.to_string(), b.to_string())) {
+        return true;
+    }
+    let (a_def, b_def) = match (find_def(a_idl, a), find_def(b_idl, b)) {
+        (Some(a_def), Some(b_def)) => (a_def, b_def),
+        _ => return false,
+    };
+    match (&a_def.ty, &b_def.ty) {
+        (IdlTypeDefinitionTy::Struct { fields: a }, IdlTypeDefinitionTy::Struct { fields: b }) => {
+            same_fields(a_idl, b_idl, a, b, visiting)
+        }
+        (IdlTypeDefinitionTy::Enum { variants: a }, IdlTypeDefinitionTy::Enum { variants: b }) => {
+            a.len() == b.len()
+                && a.iter().zip(b).all(|(a, b)| {
+                    a.name == b.name
+                        && match (&a.fields, &b.fields) {
+                            (Some(EnumFields::Named(a)), Some(EnumFields::Named(b))) => {
+                                same_fields(a_idl, b_idl, a, b, visiting)
+                            }
+                            (Some(EnumFields::Tuple(a)), Some(EnumFields::Tuple(b))) => {
+                                a.len() == b.len()
+                                    && a.iter()
+                                        .zip(b)
+                                        .all(|(a, b)| same_ty(a_idl, b_idl, a, b, visiting))
+                            }
+                            (None, None) => true,
+                            _ => false,
+                        }
+                })
+        }
+        _ => false,
+    }
+}
+
+fn same_fields(
+    a_idl: &Idl,
+    b_idl: &Idl,
+    a: &[IdlField],
+    b: &[IdlField],
+    visiting: &mut BTreeSet<(String, String)>,
+) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.name == b.name && same_ty(a_idl, b_idl, &a.ty, &b.ty, visiting))
+}
+
+fn same_ty(
+    a_idl: &Idl,
+    b_idl: &Idl,
+    a: &IdlType,
+    b: &IdlType,
+    visiting: &mut BTreeSet<(String, String)>,
+) -> bool {
+    match (a, b) {
+        // references must have the same name, since they are generated by name
+        (IdlType::Defined(a), IdlType::Defined(b)) => {
+            a == b && same_type(a_idl, b_idl, a, b, visiting)
+        }
+        (IdlType::Option(a), IdlType::Option(b)) | (IdlType::Vec(a), IdlType::Vec(b)) => {
+            same_ty(a_idl, b_idl, a, b, visiting)
+        }
+        (IdlType::Array(a, a_len), IdlType::Array(b, b_len)) => {
+            a_len == b_len && same_ty(a_idl, b_idl, a, b, visiting)
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An IDL defining `Position`, which contains a `Range`, which contains `bound`s.
+    fn idl(bound: &str) -> Idl {
+        serde_json::from_str(&format!(
+            r#"{{
+                "version": "0.1.0",
+                "name": "pools",
+                "instructions": [],
+                "types": [
+                    {{
+                        "name": "Position",
+                        "type": {{
+                            "kind": "struct",
+                            "fields": [
+                                {{ "name": "ranges", "type": {{ "vec": {{ "defined": "Range" }} }} }},
+                                {{ "name": "side", "type": {{ "defined": "Side" }} }}
+                            ]
+                        }}
+                    }},
+                    {{
+                        "name": "Range",
+                        "type": {{
+                            "kind": "struct",
+                            "fields": [
+                                {{ "name": "lower", "type": "{bound}" }},
+                                {{ "name": "upper", "type": "{bound}" }}
+                            ]
+                        }}
+                    }},
+                    {{
+                        "name": "Side",
+                        "type": {{ "kind": "enum", "variants": [{{ "name": "Bid" }}, {{ "name": "Ask" }}] }}
+                    }}
+                ]
+            }}"#,
+            bound = bound
+        ))
+        .unwrap()
+    }
+
+    fn idls(programs: &[(&str, Idl)]) -> BTreeMap<String, Idl> {
+        programs
+            .iter()
+            .map(|(name, idl)| (name.to_string(), idl.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_type_differs() {
+        let shared = find_shared_types(&idls(&[("a", idl("u64")), ("b", idl("u32"))]));
+        // `Position` has the same fields, but not the same layout
+        assert_eq!(
+            shared.extern_types["b"],
+            BTreeMap::from([("Side".to_string(), "a".to_string())])
+        );
+        let mismatches = shared
+            .mismatches
+            .iter()
+            .map(|mismatch| mismatch.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(mismatches, ["Position", "Range"]);
+        assert_eq!(
+            shared.mismatches[0].groups,
+            vec![vec!["a".to_string()], vec!["b".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_owner_is_first_program_by_name() {
+        let shared = find_shared_types(&idls(&[
+            ("c", idl("u64")),
+            ("b", idl("u32")),
+            ("a", idl("u64")),
+            ("d", idl("u32")),
+        ]));
+        assert!(!shared.extern_types.contains_key("a"));
+        // `b` owns the types which it defines differently from `a`
+        assert!(!shared.extern_types["b"].contains_key("Range"));
+        assert_eq!(shared.extern_types["b"]["Side"], "a");
+        assert_eq!(shared.extern_types["c"]["Range"], "a");
+        assert_eq!(shared.extern_types["c"]["Position"], "a");
+        assert_eq!(shared.extern_types["d"]["Range"], "b");
+        assert_eq!(shared.extern_types["d"]["Position"], "b");
+        assert_eq!(shared.extern_types["d"]["Side"], "a");
+        assert_eq!(
+            shared.mismatches[1].groups,
+            vec![
+                vec!["a".to_string(), "c".to_string()],
+                vec!["b".to_string(), "d".to_string()]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identical_idls() {
+        let shared = find_shared_types(&idls(&[("a", idl("u64")), ("b", idl("u64"))]));
+        assert_eq!(shared.extern_types["b"].len(), 3);
+        assert!(shared.mismatches.is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anchor_syn::idl::Idl;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    codama_to_idl, is_codama_root, is_new_idl, new_idl_to_legacy, parse_idl_account_dump,
+    CodamaError, IdlSpecError,
+};
+
+/// Files and environment variables an IDL was loaded from.
+///
+/// These are referenced by the generated code through `include_bytes!` and `env!`,
+/// so that the crate is rebuilt whenever they change.
+#[derive(Clone, Debug, Default)]
+pub struct IdlDependencies {
+    pub files: Vec<PathBuf>,
+    pub env_vars: Vec<String>,
+}
+
+impl IdlDependencies {
+    /// Generates the items which make the compiler track the dependencies.
+    pub fn to_tokens(&self) -> TokenStream {
+        let files = self.files.iter().map(|file| {
+            let file = file.to_string_lossy();
+            quote! {
+                const _: &[u8] = include_bytes!(#file);
+            }
+        });
+        let env_vars = self.env_vars.iter().map(|var| {
+            quote! {
+                const _: &str = env!(#var);
+            }
+        });
+        quote! {
+            #(#files)*
+            #(#env_vars)*
+        }
+    }
+}
+
+/// Finds the root of the Cargo workspace containing a crate, i.e. the outermost directory
+/// with a `Cargo.toml` declaring a `[workspace]`.
+pub fn find_workspace_root(manifest_dir: &Path) -> Option<PathBuf> {
+    manifest_dir
+        .ancestors()
+        .filter(|dir| {
+            fs::read_to_string(dir.join("Cargo.toml"))
+                .map(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+                .unwrap_or(false)
+        })
+        .last()
+        .map(Path::to_path_buf)
+}
+
+/// Resolves a path relative to the crate being compiled, then to the root of its workspace.
+///
+/// Returns every location which was tried if the file does not exist in any of them.
+pub fn resolve_idl_path(path: &str) -> Result<PathBuf, Vec<PathBuf>> {
+    let path = Path::new(path);
+    let candidates = if path.is_absolute() {
+        vec![path.to_path_buf()]
+    } else {
+        let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut candidates = vec![manifest_dir.join(path)];
+        if let Some(workspace_root) = find_workspace_root(&manifest_dir) {
+            let candidate = workspace_root.join(path);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    };
+    match candidates.iter().find(|candidate| candidate.is_file()) {
+        Some(found) => Ok(found.clone()),
+        None => Err(candidates),
+    }
+}
+
+/// An error parsing a JSON IDL.
+#[derive(Debug)]
+pub enum IdlJsonError {
+    /// The IDL is not valid JSON, or not a valid Anchor IDL.
+    Json(serde_json::Error),
+    /// The IDL is a Codama root node which could not be converted.
+    Codama(CodamaError),
+    /// The IDL follows the specification introduced by Anchor 0.30, and could not be converted.
+    Spec(IdlSpecError),
+    /// The explicit `discriminant` of an instruction of a Shank IDL is not a `u8`.
+    Discriminant {
+        instruction: String,
+        discriminant: serde_json::Value,
+    },
+    /// The `metadata` of an IDL, in which explicit discriminants are recorded, is not an object.
+    Metadata(serde_json::Value),
+}
+
+impl fmt::Display for IdlJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdlJsonError::Json(error) => write!(f, "{}", error),
+            IdlJsonError::Codama(error) => write!(f, "could not convert the Codama IDL: {}", error),
+            IdlJsonError::Spec(error) => write!(f, "could not convert the IDL: {}", error),
+            IdlJsonError::Discriminant {
+                instruction,
+                discriminant,
+            } => write!(
+                f,
+                "instruction `{}` has the discriminant {}, but only `u8` discriminants are \
+                 supported",
+                instruction, discriminant
+            ),
+            IdlJsonError::Metadata(metadata) => write!(
+                f,
+                "the metadata of an IDL with explicit discriminants must be an object, found {}",
+                metadata
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IdlJsonError {}
+
+/// Parses a JSON IDL, which is either a legacy Anchor IDL, an IDL following the specification
+/// introduced by Anchor 0.30, or a Codama root node.
+///
+/// The `discriminant` Shank writes for every instruction is not part of [Idl], so those which
+/// are not the position of their instruction are recorded in `metadata.discriminants`, see
+/// [DiscriminatorStrategy::shank](crate::DiscriminatorStrategy::shank).
+pub fn parse_idl_json(contents: &[u8]) -> Result<Idl, IdlJsonError> {
+    let mut value: serde_json::Value =
+        serde_json::from_slice(contents).map_err(IdlJsonError::Json)?;
+    if is_codama_root(&value) {
+        codama_to_idl(&value).map_err(IdlJsonError::Codama)
+    } else if is_new_idl(&value) {
+        new_idl_to_legacy(&value).map_err(IdlJsonError::Spec)
+    } else {
+        record_discriminants(&mut value)?;
+        serde_json::from_value(value).map_err(IdlJsonError::Json)
+    }
+}
+
+/// Records the explicit discriminants of the instructions of a legacy IDL which are not their
+/// positions in `metadata.discriminants`.
+fn record_discriminants(idl: &mut serde_json::Value) -> Result<(), IdlJsonError> {
+    let instructions = idl
+        .get("instructions")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten();
+    let mut discriminants = serde_json::Map::new();
+    for (position, ix) in instructions.enumerate() {
+        let discriminant = match ix.get("discriminant") {
+            Some(discriminant) => discriminant,
+            None => continue,
+        };
+        let name = ix
+            .get("name")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default();
+        let value = match discriminant.get("type").and_then(serde_json::Value::as_str) {
+            Some("u8") => discriminant
+                .get("value")
+                .and_then(serde_json::Value::as_u64)
+                .filter(|value| *value <= u8::MAX as u64),
+            _ => None,
+        };
+        match value {
+            Some(value) if value == position as u64 => {}
+            Some(value) => {
+                discriminants.insert(name.to_string(), value.into());
+            }
+            None => {
+                return Err(IdlJsonError::Discriminant {
+                    instruction: name.to_string(),
+                    discriminant: discriminant.clone(),
+                })
+            }
+        }
+    }
+    if discriminants.is_empty() {
+        return Ok(());
+    }
+    let idl = match idl.as_object_mut() {
+        Some(idl) => idl,
+        None => return Ok(()),
+    };
+    // a `null` metadata is deserialized as missing
+    let metadata = idl.entry("metadata").or_insert(serde_json::Value::Null);
+    if metadata.is_null() {
+        *metadata = serde_json::Value::Object(Default::default());
+    }
+    match metadata {
+        serde_json::Value::Object(metadata) => {
+            metadata.insert("discriminants".to_string(), discriminants.into());
+            Ok(())
+        }
+        metadata => Err(IdlJsonError::Metadata(metadata.clone())),
+    }
+}
+
+/// Format of an IDL file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdlFormat {
+    /// A JSON IDL, either an Anchor IDL in the legacy format or the 0.30 one, or a Codama root node.
+    Json,
+    /// A dump of the on-chain IDL account.
+    Account,
+}
+
+/// Reads an IDL file found by [resolve_idl_path], recording it as a dependency.
+pub fn read_idl_file(
+    option: &str,
+    path: &str,
+    format: IdlFormat,
+    deps: &mut IdlDependencies,
+) -> darling::Result<Idl> {
+    let path = resolve_idl_path(path).map_err(|tried| {
+        let tried = tried
+            .iter()
+            .map(|path| format!("\n  - {}", path.display()))
+            .collect::<String>();
+        darling::Error::custom(format!(
+            "could not find the IDL `{}` = {:?}, tried:{}",
+            option, path, tried
+        ))
+    })?;
+    let contents = fs::read(&path)
+        .map_err(|e| darling::Error::custom(format!("could not read {}: {}", path.display(), e)))?;
+    let idl = match format {
+        IdlFormat::Json => parse_idl_json(&contents).map_err(|e| e.to_string()),
+        IdlFormat::Account => parse_idl_account_dump(&contents).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| darling::Error::custom(format!("could not parse {}: {}", path.display(), e)))?;
+    deps.files.push(path);
+    Ok(idl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn workspace_root() -> PathBuf {
+        manifest_dir().join("../..").canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_find_workspace_root() {
+        let root = find_workspace_root(&manifest_dir()).unwrap();
+        assert_eq!(root.canonicalize().unwrap(), workspace_root());
+    }
+
+    #[test]
+    fn test_manifest_dir_first() {
+        // both the crate and the workspace have a `Cargo.toml`
+        assert_eq!(
+            resolve_idl_path("Cargo.toml").unwrap(),
+            manifest_dir().join("Cargo.toml")
+        );
+    }
+
+    #[test]
+    fn test_workspace_root() {
+        let path = resolve_idl_path("examples/whirlpools/idl.json").unwrap();
+        assert_eq!(
+            path.canonicalize().unwrap(),
+            workspace_root().join("examples/whirlpools/idl.json")
+        );
+    }
+
+    #[test]
+    fn test_absolute_path() {
+        let path = manifest_dir().join("Cargo.toml");
+        assert_eq!(resolve_idl_path(path.to_str().unwrap()).unwrap(), path);
+    }
+
+    fn shank_idl() -> serde_json::Value {
+        serde_json::from_str(include_str!("../../../examples/shank-cpi/idl.json")).unwrap()
+    }
+
+    #[test]
+    fn test_shank_discriminants() {
+        let idl = parse_idl_json(shank_idl().to_string().as_bytes()).unwrap();
+        assert_eq!(idl.instructions.len(), 3);
+        assert!(idl.metadata.unwrap().get("discriminants").is_none());
+
+        let mut gap = shank_idl();
+        gap["instructions"][2]["discriminant"]["value"] = serde_json::json!(5);
+        let idl = parse_idl_json(gap.to_string().as_bytes()).unwrap();
+        assert_eq!(
+            idl.metadata.as_ref().unwrap()["discriminants"],
+            serde_json::json!({ "Close": 5 })
+        );
+        let discriminators = crate::DiscriminatorStrategy::detect(&idl);
+        assert_eq!(
+            discriminators.instruction_discriminator(1, "Increment"),
+            [1]
+        );
+        assert_eq!(discriminators.instruction_discriminator(2, "Close"), [5]);
+
+        gap["metadata"] = serde_json::Value::Null;
+        let idl = parse_idl_json(gap.to_string().as_bytes()).unwrap();
+        assert_eq!(
+            idl.metadata.unwrap(),
+            serde_json::json!({ "discriminants": { "Close": 5 } })
+        );
+        gap["metadata"] = serde_json::json!("counter");
+        let error = parse_idl_json(gap.to_string().as_bytes()).unwrap_err();
+        assert!(matches!(error, IdlJsonError::Metadata(_)));
+
+        let mut wide = shank_idl();
+        wide["instructions"][0]["discriminant"]["type"] = serde_json::json!("u16");
+        let error = parse_idl_json(wide.to_string().as_bytes()).unwrap_err();
+        assert!(matches!(
+            &error,
+            IdlJsonError::Discriminant { instruction, .. } if instruction == "Initialize"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "instruction `Initialize` has the discriminant {\"type\":\"u16\",\"value\":0}, but \
+             only `u8` discriminants are supported"
+        );
+    }
+
+    #[test]
+    fn test_not_found() {
+        let tried = resolve_idl_path("missing/idl.json").unwrap_err();
+        assert_eq!(tried.len(), 2);
+        assert_eq!(tried[0], manifest_dir().join("missing/idl.json"));
+        assert_eq!(
+            tried[1],
+            find_workspace_root(&manifest_dir())
+                .unwrap()
+                .join("missing/idl.json")
+        );
+
+        let error = read_idl_file(
+            "idl_path",
+            "missing/idl.json",
+            IdlFormat::Json,
+            &mut IdlDependencies::default(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("could not find the IDL `idl_path` = \"missing/idl.json\", tried:"));
+        for path in &tried {
+            assert!(error.contains(&format!("\n  - {}", path.display())));
+        }
+    }
+}
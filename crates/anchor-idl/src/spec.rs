@@ -0,0 +1,1199 @@
+use std::{collections::BTreeMap, fmt};
+
+use anchor_syn::idl::{
+    EnumFields, Idl, IdlAccount, IdlAccountItem, IdlAccounts, IdlConst, IdlEnumVariant,
+    IdlErrorCode, IdlEvent, IdlEventField, IdlField, IdlInstruction, IdlPda, IdlSeed,
+    IdlSeedAccount, IdlSeedArg, IdlSeedConst, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+use heck::{ToLowerCamelCase, ToSnakeCase};
+use serde_json::{json, Map, Value};
+
+use crate::{anchor_discriminator, decode_base58, primitive_size, DiscriminatorStrategy};
+
+/// Version of the IDL specification written by [legacy_idl_to_new].
+pub const IDL_SPEC_VERSION: &str = "0.1.0";
+
+/// An error converting an IDL between the legacy format and the specification introduced by
+/// Anchor 0.30.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlSpecError {
+    /// Path of the offending value, e.g. `instructions[0].accounts[1].pda.seeds[0]`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for IdlSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for IdlSpecError {}
+
+fn error(path: &str, message: impl Into<String>) -> IdlSpecError {
+    IdlSpecError {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Whether a JSON document follows the IDL specification introduced by Anchor 0.30, rather than
+/// the legacy format of [Idl].
+pub fn is_new_idl(value: &Value) -> bool {
+    value.get("address").is_some()
+        && value
+            .get("metadata")
+            .and_then(|metadata| metadata.get("spec"))
+            .is_some()
+}
+
+/// Converts a legacy [Idl] to the IDL specification introduced by Anchor 0.30.
+///
+/// The program address is `address` if given, or else the `metadata.address` of the IDL. The
+/// discriminators of instructions, accounts and events are computed with the
+/// [DiscriminatorStrategy] of the IDL, the fields of accounts and events are moved to `types`,
+/// and instructions, arguments, fields and instruction accounts are renamed to snake case.
+/// Whether event fields are indexed is not part of the specification, and is dropped.
+///
+/// ```
+/// let idl: anchor_idl::Idl = serde_json::from_str(include_str!(
+///     "../../../examples/govern-cpi/idl.json"
+/// )).unwrap();
+/// let new = anchor_idl::legacy_idl_to_new(
+///     &idl,
+///     Some("Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw"),
+/// ).unwrap();
+/// assert!(anchor_idl::is_new_idl(&new));
+/// assert_eq!(new["instructions"][0]["name"], "create_governor");
+/// assert_eq!(
+///     new["instructions"][0]["discriminator"],
+///     serde_json::json!(anchor_idl::anchor_discriminator("global", "create_governor")),
+/// );
+///
+/// // converting back yields the original IDL
+/// let legacy = anchor_idl::new_idl_to_legacy(&new).unwrap();
+/// assert_eq!(legacy.instructions, idl.instructions);
+/// assert_eq!(legacy.accounts, idl.accounts);
+/// assert_eq!(legacy.types, idl.types);
+/// assert_eq!(legacy.events, idl.events);
+/// assert_eq!(legacy.errors, idl.errors);
+/// ```
+pub fn legacy_idl_to_new(idl: &Idl, address: Option<&str>) -> Result<Value, IdlSpecError> {
+    let address = address
+        .or_else(|| {
+            idl.metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("address"))
+                .and_then(Value::as_str)
+        })
+        .ok_or_else(|| error("metadata.address", "missing the address of the program"))?;
+    let discriminators = DiscriminatorStrategy::detect(idl);
+
+    let instructions = idl
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, ix)| {
+            let path = format!("instructions[{}]", index);
+            let accounts = ix
+                .accounts
+                .iter()
+                .enumerate()
+                .map(|(i, item)| new_account_item(item, &format!("{}.accounts[{}]", path, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(object([
+                ("name", Some(json!(ix.name.to_snake_case()))),
+                ("docs", ix.docs.as_ref().map(|docs| json!(docs))),
+                (
+                    "discriminator",
+                    Some(json!(
+                        discriminators.instruction_discriminator(index, &ix.name)
+                    )),
+                ),
+                ("accounts", Some(Value::Array(accounts))),
+                (
+                    "args",
+                    Some(Value::Array(ix.args.iter().map(new_field).collect())),
+                ),
+                ("returns", ix.returns.as_ref().map(new_type)),
+            ]))
+        })
+        .collect::<Result<Vec<_>, IdlSpecError>>()?;
+
+    let accounts = idl.accounts.iter().map(|account| {
+        json!({
+            "name": account.name,
+            "discriminator": discriminators.account_discriminator(&account.name),
+        })
+    });
+    let events = idl.events.iter().flatten().map(|event| {
+        json!({
+            "name": event.name,
+            "discriminator": anchor_discriminator("event", &event.name),
+        })
+    });
+
+    let event_types = idl.events.iter().flatten().map(|event| IdlTypeDefinition {
+        name: event.name.clone(),
+        docs: None,
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: event
+                .fields
+                .iter()
+                .map(|field| IdlField {
+                    name: field.name.clone(),
+                    docs: None,
+                    ty: field.ty.clone(),
+                })
+                .collect(),
+        },
+    });
+    let mut types: Vec<IdlTypeDefinition> = vec![];
+    for ty in idl
+        .accounts
+        .iter()
+        .cloned()
+        .chain(event_types)
+        .chain(idl.types.iter().cloned())
+    {
+        if !types.iter().any(|existing| existing.name == ty.name) {
+            types.push(ty);
+        }
+    }
+
+    let errors = idl.errors.iter().flatten().map(|error| {
+        object([
+            ("code", Some(json!(error.code))),
+            ("name", Some(json!(error.name))),
+            ("msg", error.msg.as_ref().map(|msg| json!(msg))),
+        ])
+    });
+    let constants = idl.constants.iter().map(|constant| {
+        json!({
+            "name": constant.name,
+            "type": new_type(&constant.ty),
+            "value": constant.value,
+        })
+    });
+
+    Ok(object([
+        ("address", Some(json!(address))),
+        (
+            "metadata",
+            Some(json!({
+                "name": idl.name.to_snake_case(),
+                "version": idl.version,
+                "spec": IDL_SPEC_VERSION,
+            })),
+        ),
+        ("docs", idl.docs.as_ref().map(|docs| json!(docs))),
+        ("instructions", Some(Value::Array(instructions))),
+        ("accounts", Some(Value::Array(accounts.collect()))),
+        ("events", Some(Value::Array(events.collect()))),
+        ("errors", Some(Value::Array(errors.collect()))),
+        (
+            "types",
+            Some(Value::Array(
+                types.iter().map(new_type_definition).collect(),
+            )),
+        ),
+        ("constants", Some(Value::Array(constants.collect()))),
+    ]))
+}
+
+/// A JSON object of the entries which are present.
+fn object<const N: usize>(entries: [(&str, Option<Value>); N]) -> Value {
+    Value::Object(
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn new_type(ty: &IdlType) -> Value {
+    match ty {
+        IdlType::Bool => json!("bool"),
+        IdlType::U8 => json!("u8"),
+        IdlType::I8 => json!("i8"),
+        IdlType::U16 => json!("u16"),
+        IdlType::I16 => json!("i16"),
+        IdlType::U32 => json!("u32"),
+        IdlType::I32 => json!("i32"),
+        IdlType::F32 => json!("f32"),
+        IdlType::U64 => json!("u64"),
+        IdlType::I64 => json!("i64"),
+        IdlType::F64 => json!("f64"),
+        IdlType::U128 => json!("u128"),
+        IdlType::I128 => json!("i128"),
+        IdlType::U256 => json!("u256"),
+        IdlType::I256 => json!("i256"),
+        IdlType::Bytes => json!("bytes"),
+        IdlType::String => json!("string"),
+        IdlType::PublicKey => json!("pubkey"),
+        IdlType::Defined(name) => json!({ "defined": { "name": name } }),
+        IdlType::Option(inner) => json!({ "option": new_type(inner) }),
+        IdlType::Vec(inner) => json!({ "vec": new_type(inner) }),
+        IdlType::Array(inner, len) => json!({ "array": [new_type(inner), len] }),
+    }
+}
+
+fn new_field(field: &IdlField) -> Value {
+    object([
+        ("name", Some(json!(field.name.to_snake_case()))),
+        ("docs", field.docs.as_ref().map(|docs| json!(docs))),
+        ("type", Some(new_type(&field.ty))),
+    ])
+}
+
+fn new_type_definition(def: &IdlTypeDefinition) -> Value {
+    let ty = match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => object([
+            ("kind", Some(json!("struct"))),
+            (
+                "fields",
+                (!fields.is_empty()).then(|| Value::Array(fields.iter().map(new_field).collect())),
+            ),
+        ]),
+        IdlTypeDefinitionTy::Enum { variants } => {
+            let variants = variants.iter().map(|variant| {
+                let fields = match &variant.fields {
+                    Some(EnumFields::Named(fields)) => {
+                        Some(Value::Array(fields.iter().map(new_field).collect()))
+                    }
+                    Some(EnumFields::Tuple(types)) => {
+                        Some(Value::Array(types.iter().map(new_type).collect()))
+                    }
+                    None => None,
+                };
+                object([("name", Some(json!(variant.name))), ("fields", fields)])
+            });
+            json!({ "kind": "enum", "variants": variants.collect::<Vec<_>>() })
+        }
+    };
+    object([
+        ("name", Some(json!(def.name))),
+        ("docs", def.docs.as_ref().map(|docs| json!(docs))),
+        ("type", Some(ty)),
+    ])
+}
+
+fn new_account_item(item: &IdlAccountItem, path: &str) -> Result<Value, IdlSpecError> {
+    Ok(match item {
+        IdlAccountItem::IdlAccount(account) => {
+            let pda = account
+                .pda
+                .as_ref()
+                .map(|pda| new_pda(pda, &format!("{}.pda", path)))
+                .transpose()?;
+            let relations = account
+                .relations
+                .iter()
+                .map(|relation| relation.to_snake_case())
+                .collect::<Vec<_>>();
+            object([
+                ("name", Some(json!(account.name.to_snake_case()))),
+                ("docs", account.docs.as_ref().map(|docs| json!(docs))),
+                ("writable", account.is_mut.then_some(json!(true))),
+                ("signer", account.is_signer.then_some(json!(true))),
+                (
+                    "optional",
+                    (account.is_optional == Some(true)).then_some(json!(true)),
+                ),
+                ("pda", pda),
+                (
+                    "relations",
+                    (!relations.is_empty()).then(|| json!(relations)),
+                ),
+            ])
+        }
+        IdlAccountItem::IdlAccounts(accounts) => {
+            let items = accounts
+                .accounts
+                .iter()
+                .enumerate()
+                .map(|(i, item)| new_account_item(item, &format!("{}.accounts[{}]", path, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            json!({
+                "name": accounts.name.to_snake_case(),
+                "accounts": items,
+            })
+        }
+    })
+}
+
+fn new_pda(pda: &IdlPda, path: &str) -> Result<Value, IdlSpecError> {
+    let seeds = pda
+        .seeds
+        .iter()
+        .enumerate()
+        .map(|(i, seed)| new_seed(seed, &format!("{}.seeds[{}]", path, i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let program = pda
+        .program_id
+        .as_ref()
+        .map(|seed| new_seed(seed, &format!("{}.programId", path)))
+        .transpose()?;
+    Ok(object([
+        ("seeds", Some(Value::Array(seeds))),
+        ("program", program),
+    ]))
+}
+
+fn new_seed(seed: &IdlSeed, path: &str) -> Result<Value, IdlSpecError> {
+    let snake_path = |path: &str| {
+        path.split('.')
+            .map(|segment| segment.to_snake_case())
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    Ok(match seed {
+        IdlSeed::Const(seed) => {
+            let bytes = const_seed_bytes(&seed.ty, &seed.value).ok_or_else(|| {
+                error(
+                    path,
+                    format!("cannot serialize the constant {} as a seed", seed.value),
+                )
+            })?;
+            json!({ "kind": "const", "value": bytes })
+        }
+        IdlSeed::Arg(seed) => json!({ "kind": "arg", "path": snake_path(&seed.path) }),
+        IdlSeed::Account(seed) => object([
+            ("kind", Some(json!("account"))),
+            ("path", Some(json!(snake_path(&seed.path)))),
+            (
+                "account",
+                seed.account.as_ref().map(|account| json!(account)),
+            ),
+        ]),
+    })
+}
+
+/// Serializes the value of a constant seed, as [find_program_address](crate::find_program_address)
+/// expects it.
+fn const_seed_bytes(ty: &IdlType, value: &Value) -> Option<Vec<u8>> {
+    match ty {
+        IdlType::String => Some(value.as_str()?.as_bytes().to_vec()),
+        IdlType::PublicKey => decode_base58(value.as_str()?).filter(|key| key.len() == 32),
+        IdlType::Bytes | IdlType::Vec(_) | IdlType::Array(_, _) => {
+            if let IdlType::Vec(inner) | IdlType::Array(inner, _) = ty {
+                if **inner != IdlType::U8 {
+                    return None;
+                }
+            }
+            value
+                .as_array()?
+                .iter()
+                .map(|byte| u8::try_from(byte.as_u64()?).ok())
+                .collect()
+        }
+        IdlType::Bool => Some(vec![value.as_bool()? as u8]),
+        IdlType::U8 | IdlType::U16 | IdlType::U32 | IdlType::U64 | IdlType::U128 => {
+            let number = value
+                .as_u64()
+                .map(u128::from)
+                .or_else(|| value.as_str()?.parse().ok())?;
+            let size = primitive_size(ty);
+            (size == 16 || number >> (size * 8) == 0).then(|| number.to_le_bytes()[..size].to_vec())
+        }
+        IdlType::I8 | IdlType::I16 | IdlType::I32 | IdlType::I64 | IdlType::I128 => {
+            let number = value
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| value.as_str()?.parse().ok())?;
+            let size = primitive_size(ty);
+            let fits =
+                size == 16 || (number >> (size * 8 - 1) == 0 || number >> (size * 8 - 1) == -1);
+            fits.then(|| number.to_le_bytes()[..size].to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// Converts an IDL following the specification introduced by Anchor 0.30 to a legacy [Idl].
+///
+/// The legacy format has no discriminators, since they are computed from names: they are
+/// dropped if they follow the Anchor convention, or else if they follow the Shank convention, in
+/// which case `metadata.origin` is set to `"shank"` and the discriminants which are not the
+/// positions of their instructions are kept as `metadata.discriminants`, see
+/// [DiscriminatorStrategy::shank]. Any other discriminator is an error. The program address is kept as `metadata.address`.
+///
+/// Names are converted back to camel case, the fields of accounts and events are taken from
+/// `types`, aliases are inlined, and the fields of tuple structs are named `field0`, `field1`,
+/// and so on. Generic types have no legacy equivalent. Fixed instruction account addresses are
+/// dropped, as are PDAs whose seeds have a type which cannot be resolved.
+///
+/// ```
+/// let new: serde_json::Value = serde_json::from_str(r#"{
+///     "address": "FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ",
+///     "metadata": { "name": "counter", "version": "0.1.0", "spec": "0.1.0" },
+///     "instructions": [{
+///         "name": "increment",
+///         "discriminator": [0],
+///         "accounts": [{ "name": "counter", "writable": true }],
+///         "args": [{ "name": "by_amount", "type": "u64" }]
+///     }],
+///     "accounts": [{ "name": "Counter", "discriminator": [] }],
+///     "types": [{
+///         "name": "Counter",
+///         "type": { "kind": "struct", "fields": [{ "name": "count", "type": "u64" }] }
+///     }]
+/// }"#).unwrap();
+/// let idl = anchor_idl::new_idl_to_legacy(&new).unwrap();
+/// assert_eq!(idl.instructions[0].args[0].name, "byAmount");
+/// assert!(matches!(
+///     anchor_idl::DiscriminatorStrategy::detect(&idl),
+///     anchor_idl::DiscriminatorStrategy::Shank { .. }
+/// ));
+/// ```
+pub fn new_idl_to_legacy(idl: &Value) -> Result<Idl, IdlSpecError> {
+    let address = str_field(idl, "", "address")?;
+    let metadata = field(idl, "", "metadata")?;
+    let name = str_field(metadata, "metadata", "name")?;
+    let version = str_field(metadata, "metadata", "version")?;
+
+    let mut converter = Converter {
+        aliases: BTreeMap::new(),
+    };
+    let mut definitions = vec![];
+    for (index, def) in list(idl, "types").iter().enumerate() {
+        let path = format!("types[{}]", index);
+        if !list(def, "generics").is_empty() {
+            return Err(error(
+                &path,
+                "generic types have no equivalent in a legacy IDL",
+            ));
+        }
+        let ty = field(def, &path, "type")?;
+        if str_field(ty, &path, "kind")? == "type" {
+            converter
+                .aliases
+                .insert(str_field(def, &path, "name")?, field(ty, &path, "alias")?);
+        } else {
+            definitions.push((path, def));
+        }
+    }
+    let mut types = definitions
+        .iter()
+        .map(|(path, def)| converter.type_definition(def, path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut take_type = |name: &str, path: &str| {
+        let index = types
+            .iter()
+            .position(|ty| ty.name == name)
+            .ok_or_else(|| error(path, format!("no type named `{}`", name)))?;
+        Ok::<_, IdlSpecError>(types.remove(index))
+    };
+
+    let mut accounts = vec![];
+    let mut account_discriminators = vec![];
+    for (index, account) in list(idl, "accounts").iter().enumerate() {
+        let path = format!("accounts[{}]", index);
+        let name = str_field(account, &path, "name")?;
+        accounts.push(take_type(name, &path)?);
+        account_discriminators.push((path.clone(), bytes_field(account, &path, "discriminator")?));
+    }
+
+    let mut events = vec![];
+    for (index, event) in list(idl, "events").iter().enumerate() {
+        let path = format!("events[{}]", index);
+        let name = str_field(event, &path, "name")?;
+        if bytes_field(event, &path, "discriminator")? != anchor_discriminator("event", name) {
+            return Err(error(
+                &path,
+                "the discriminator is not derived from the name of the event, so it cannot be \
+                 left out of a legacy IDL",
+            ));
+        }
+        let fields = match take_type(name, &path)?.ty {
+            IdlTypeDefinitionTy::Struct { fields } => fields,
+            IdlTypeDefinitionTy::Enum { .. } => {
+                return Err(error(&path, format!("event `{}` is not a struct", name)))
+            }
+        };
+        events.push(IdlEvent {
+            name: name.to_string(),
+            fields: fields
+                .into_iter()
+                .map(|field| IdlEventField {
+                    name: field.name,
+                    ty: field.ty,
+                    index: false,
+                })
+                .collect(),
+        });
+    }
+
+    // types the seeds of PDAs may refer to
+    let seed_types = accounts.iter().chain(&types).collect::<Vec<_>>();
+    let mut instructions = vec![];
+    let mut ix_discriminators = vec![];
+    for (index, ix) in list(idl, "instructions").iter().enumerate() {
+        let path = format!("instructions[{}]", index);
+        let args = list(ix, "args")
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| converter.field(arg, &format!("{}.args[{}]", path, i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let accounts = list(ix, "accounts")
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                converter.account_item(
+                    item,
+                    &format!("{}.accounts[{}]", path, i),
+                    &args,
+                    &seed_types,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let returns = ix
+            .get("returns")
+            .map(|ty| converter.ty(ty, &format!("{}.returns", path)))
+            .transpose()?;
+        instructions.push(IdlInstruction {
+            name: str_field(ix, &path, "name")?.to_lower_camel_case(),
+            docs: docs(ix),
+            accounts,
+            args,
+            returns,
+        });
+        ix_discriminators.push((path.clone(), bytes_field(ix, &path, "discriminator")?));
+    }
+
+    let follows = |strategy: &DiscriminatorStrategy| {
+        let ixs = instructions
+            .iter()
+            .zip(&ix_discriminators)
+            .enumerate()
+            .find(|(index, (ix, (_, bytes)))| {
+                *bytes != strategy.instruction_discriminator(*index, &ix.name)
+            })
+            .map(|(_, (_, (path, _)))| path);
+        let accounts = accounts
+            .iter()
+            .zip(&account_discriminators)
+            .find(|(account, (_, bytes))| *bytes != strategy.account_discriminator(&account.name))
+            .map(|(_, (path, _))| path);
+        ixs.or(accounts)
+    };
+    // instructions of Shank programs are prefixed with a `u8`, usually their position
+    let shank = DiscriminatorStrategy::Shank {
+        discriminants: instructions
+            .iter()
+            .zip(&ix_discriminators)
+            .enumerate()
+            .filter_map(|(index, (ix, (_, bytes)))| match bytes.as_slice() {
+                &[discriminant] if discriminant as usize != index => {
+                    Some((ix.name.clone(), discriminant))
+                }
+                _ => None,
+            })
+            .collect(),
+    };
+    let strategy = match follows(&DiscriminatorStrategy::Anchor) {
+        None => DiscriminatorStrategy::Anchor,
+        Some(_) if follows(&shank).is_none() => shank,
+        Some(path) => {
+            return Err(error(
+                path,
+                "the discriminator follows neither the Anchor nor the Shank convention, so it \
+                 cannot be left out of a legacy IDL",
+            ))
+        }
+    };
+
+    let errors = list(idl, "errors")
+        .iter()
+        .enumerate()
+        .map(|(index, error)| {
+            let path = format!("errors[{}]", index);
+            Ok(IdlErrorCode {
+                code: usize_field(error, &path, "code")? as u32,
+                name: str_field(error, &path, "name")?.to_string(),
+                msg: error.get("msg").and_then(Value::as_str).map(str::to_string),
+            })
+        })
+        .collect::<Result<Vec<_>, IdlSpecError>>()?;
+    let constants = list(idl, "constants")
+        .iter()
+        .enumerate()
+        .map(|(index, constant)| {
+            let path = format!("constants[{}]", index);
+            Ok(IdlConst {
+                name: str_field(constant, &path, "name")?.to_string(),
+                ty: converter.ty(field(constant, &path, "type")?, &path)?,
+                value: str_field(constant, &path, "value")?.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, IdlSpecError>>()?;
+
+    let mut metadata = json!({ "address": address });
+    if let DiscriminatorStrategy::Shank { discriminants } = &strategy {
+        metadata["origin"] = json!("shank");
+        if !discriminants.is_empty() {
+            metadata["discriminants"] = json!(discriminants);
+        }
+    }
+    Ok(Idl {
+        version: version.to_string(),
+        name: name.to_string(),
+        docs: docs(idl),
+        constants,
+        instructions,
+        accounts,
+        types,
+        events: (!events.is_empty()).then_some(events),
+        errors: (!errors.is_empty()).then_some(errors),
+        metadata: Some(metadata),
+    })
+}
+
+struct Converter<'a> {
+    /// Aliases by name, which are inlined where they are used.
+    aliases: BTreeMap<&'a str, &'a Value>,
+}
+
+impl<'a> Converter<'a> {
+    fn type_definition(&self, def: &Value, path: &str) -> Result<IdlTypeDefinition, IdlSpecError> {
+        let ty_path = format!("{}.type", path);
+        let ty = field(def, path, "type")?;
+        let ty = match str_field(ty, &ty_path, "kind")? {
+            "struct" => IdlTypeDefinitionTy::Struct {
+                fields: self.fields(list(ty, "fields"), &format!("{}.fields", ty_path))?,
+            },
+            "enum" => IdlTypeDefinitionTy::Enum {
+                variants: list(ty, "variants")
+                    .iter()
+                    .enumerate()
+                    .map(|(index, variant)| {
+                        let path = format!("{}.variants[{}]", ty_path, index);
+                        let fields = list(variant, "fields");
+                        let fields = if fields.is_empty() {
+                            None
+                        } else if is_named(fields) {
+                            Some(EnumFields::Named(
+                                self.fields(fields, &format!("{}.fields", path))?,
+                            ))
+                        } else {
+                            Some(EnumFields::Tuple(
+                                fields
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, ty)| self.ty(ty, &format!("{}.fields[{}]", path, i)))
+                                    .collect::<Result<_, _>>()?,
+                            ))
+                        };
+                        Ok(IdlEnumVariant {
+                            name: str_field(variant, &path, "name")?.to_string(),
+                            fields,
+                        })
+                    })
+                    .collect::<Result<_, IdlSpecError>>()?,
+            },
+            other => return Err(error(&ty_path, format!("unknown kind of type `{}`", other))),
+        };
+        Ok(IdlTypeDefinition {
+            name: str_field(def, path, "name")?.to_string(),
+            docs: docs(def),
+            ty,
+        })
+    }
+
+    /// Converts named fields, or the fields of a tuple struct, which are named by position.
+    fn fields(&self, fields: &[Value], path: &str) -> Result<Vec<IdlField>, IdlSpecError> {
+        if is_named(fields) {
+            return fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| self.field(field, &format!("{}[{}]", path, index)))
+                .collect();
+        }
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| {
+                Ok(IdlField {
+                    name: format!("field{}", index),
+                    docs: None,
+                    ty: self.ty(ty, &format!("{}[{}]", path, index))?,
+                })
+            })
+            .collect()
+    }
+
+    fn field(&self, field: &Value, path: &str) -> Result<IdlField, IdlSpecError> {
+        Ok(IdlField {
+            name: str_field(field, path, "name")?.to_lower_camel_case(),
+            docs: docs(field),
+            ty: self.ty(self::field(field, path, "type")?, &format!("{}.type", path))?,
+        })
+    }
+
+    fn ty(&self, ty: &Value, path: &str) -> Result<IdlType, IdlSpecError> {
+        self.ty_expanding(ty, path, &mut vec![])
+    }
+
+    /// Converts a type, keeping track of the aliases being inlined to detect cycles.
+    fn ty_expanding(
+        &self,
+        ty: &Value,
+        path: &str,
+        expanding: &mut Vec<String>,
+    ) -> Result<IdlType, IdlSpecError> {
+        if let Some(name) = ty.as_str() {
+            return Ok(match name {
+                "bool" => IdlType::Bool,
+                "u8" => IdlType::U8,
+                "i8" => IdlType::I8,
+                "u16" => IdlType::U16,
+                "i16" => IdlType::I16,
+                "u32" => IdlType::U32,
+                "i32" => IdlType::I32,
+                "f32" => IdlType::F32,
+                "u64" => IdlType::U64,
+                "i64" => IdlType::I64,
+                "f64" => IdlType::F64,
+                "u128" => IdlType::U128,
+                "i128" => IdlType::I128,
+                "u256" => IdlType::U256,
+                "i256" => IdlType::I256,
+                "bytes" => IdlType::Bytes,
+                "string" => IdlType::String,
+                "pubkey" | "publicKey" => IdlType::PublicKey,
+                other => return Err(error(path, format!("unknown type `{}`", other))),
+            });
+        }
+        let (key, inner) = ty
+            .as_object()
+            .filter(|ty| ty.len() == 1)
+            .and_then(|ty| ty.iter().next())
+            .ok_or_else(|| error(path, format!("invalid type {}", ty)))?;
+        let inner_path = format!("{}.{}", path, key);
+        Ok(match key.as_str() {
+            "option" => IdlType::Option(Box::new(self.ty_expanding(
+                inner,
+                &inner_path,
+                expanding,
+            )?)),
+            "vec" => IdlType::Vec(Box::new(self.ty_expanding(
+                inner,
+                &inner_path,
+                expanding,
+            )?)),
+            "array" => {
+                let (element, len) = match inner.as_array().map(Vec::as_slice) {
+                    Some([element, len]) => (element, len),
+                    _ => return Err(error(&inner_path, "expected a type and a length")),
+                };
+                let len = len.as_u64().ok_or_else(|| {
+                    error(
+                        &inner_path,
+                        "generic array lengths have no equivalent in a legacy IDL",
+                    )
+                })?;
+                let element =
+                    self.ty_expanding(element, &format!("{}[0]", inner_path), expanding)?;
+                IdlType::Array(Box::new(element), len as usize)
+            }
+            "defined" => {
+                // the specification used to name defined types directly
+                let name = match inner.as_str() {
+                    Some(name) => name,
+                    None => {
+                        if !list(inner, "generics").is_empty() {
+                            return Err(error(
+                                &inner_path,
+                                "generic types have no equivalent in a legacy IDL",
+                            ));
+                        }
+                        str_field(inner, &inner_path, "name")?
+                    }
+                };
+                match self.aliases.get(name) {
+                    Some(alias) => {
+                        if expanding.iter().any(|expanded| expanded == name) {
+                            return Err(error(
+                                &inner_path,
+                                format!("the alias `{}` refers to itself", name),
+                            ));
+                        }
+                        expanding.push(name.to_string());
+                        let ty = self.ty_expanding(alias, path, expanding)?;
+                        expanding.pop();
+                        ty
+                    }
+                    None => IdlType::Defined(name.to_string()),
+                }
+            }
+            other => {
+                return Err(error(
+                    path,
+                    format!("`{}` has no equivalent in a legacy IDL", other),
+                ))
+            }
+        })
+    }
+
+    fn account_item(
+        &self,
+        item: &Value,
+        path: &str,
+        args: &[IdlField],
+        types: &[&IdlTypeDefinition],
+    ) -> Result<IdlAccountItem, IdlSpecError> {
+        let name = str_field(item, path, "name")?.to_lower_camel_case();
+        if let Some(items) = item.get("accounts") {
+            let accounts = items
+                .as_array()
+                .ok_or_else(|| error(path, "expected `accounts` to be a list"))?
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    self.account_item(item, &format!("{}.accounts[{}]", path, index), args, types)
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(IdlAccountItem::IdlAccounts(IdlAccounts { name, accounts }));
+        }
+        let flag = |key| item.get(key).and_then(Value::as_bool).unwrap_or(false);
+        let relations = list(item, "relations")
+            .iter()
+            .filter_map(|relation| relation.as_str().map(|r| r.to_lower_camel_case()))
+            .collect();
+        Ok(IdlAccountItem::IdlAccount(IdlAccount {
+            name,
+            is_mut: flag("writable"),
+            is_signer: flag("signer"),
+            is_optional: flag("optional").then_some(true),
+            docs: docs(item),
+            pda: item.get("pda").and_then(|pda| legacy_pda(pda, args, types)),
+            relations,
+        }))
+    }
+}
+
+/// Whether fields are named, rather than the types of a tuple.
+fn is_named(fields: &[Value]) -> bool {
+    fields.iter().all(|field| field.get("name").is_some())
+}
+
+/// Converts a PDA, or returns [None] if the type of one of its seeds cannot be resolved.
+fn legacy_pda(pda: &Value, args: &[IdlField], types: &[&IdlTypeDefinition]) -> Option<IdlPda> {
+    let seeds = list(pda, "seeds")
+        .iter()
+        .map(|seed| legacy_seed(seed, args, types))
+        .collect::<Option<_>>()?;
+    let program_id = match pda.get("program") {
+        Some(seed) => Some(legacy_seed(seed, args, types)?),
+        None => None,
+    };
+    Some(IdlPda { seeds, program_id })
+}
+
+fn legacy_seed(seed: &Value, args: &[IdlField], types: &[&IdlTypeDefinition]) -> Option<IdlSeed> {
+    let path = seed.get("path").and_then(Value::as_str);
+    match seed.get("kind")?.as_str()? {
+        "const" => {
+            let bytes = seed
+                .get("value")?
+                .as_array()?
+                .iter()
+                .map(|byte| u8::try_from(byte.as_u64()?).ok())
+                .collect::<Option<Vec<_>>>()?;
+            let (ty, value) = match String::from_utf8(bytes) {
+                Ok(string) => (IdlType::String, json!(string)),
+                Err(error) => (IdlType::Bytes, json!(error.into_bytes())),
+            };
+            Some(IdlSeed::Const(IdlSeedConst { ty, value }))
+        }
+        "arg" => {
+            let mut segments = path?.split('.');
+            let arg = segments.next()?;
+            let arg = args
+                .iter()
+                .find(|field| field.name.to_snake_case() == arg.to_snake_case())?;
+            let ty = field_path_type(&arg.ty, segments, types)?;
+            Some(IdlSeed::Arg(IdlSeedArg {
+                ty,
+                path: legacy_seed_path(path?),
+            }))
+        }
+        "account" => {
+            let account = seed.get("account").and_then(Value::as_str);
+            let mut segments = path?.split('.').skip(1).peekable();
+            let ty = if segments.peek().is_none() {
+                IdlType::PublicKey
+            } else {
+                field_path_type(&IdlType::Defined(account?.to_string()), segments, types)?
+            };
+            Some(IdlSeed::Account(IdlSeedAccount {
+                ty,
+                account: account.map(str::to_string),
+                path: legacy_seed_path(path?),
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// The path of a seed as Anchor writes it in a legacy IDL, where the account or argument is in
+/// camel case and the fields which follow it are in snake case.
+fn legacy_seed_path(path: &str) -> String {
+    let mut segments = path.split('.');
+    let mut legacy = segments.next().unwrap_or_default().to_lower_camel_case();
+    for segment in segments {
+        legacy.push('.');
+        legacy.push_str(&segment.to_snake_case());
+    }
+    legacy
+}
+
+/// Type of the field at the end of a path of field names, starting from `ty`.
+fn field_path_type<'s>(
+    ty: &IdlType,
+    segments: impl Iterator<Item = &'s str>,
+    types: &[&IdlTypeDefinition],
+) -> Option<IdlType> {
+    let mut ty = ty.clone();
+    for segment in segments {
+        let name = match &ty {
+            IdlType::Defined(name) => name,
+            _ => return None,
+        };
+        let fields = match &types.iter().find(|def| &def.name == name)?.ty {
+            IdlTypeDefinitionTy::Struct { fields } => fields,
+            IdlTypeDefinitionTy::Enum { .. } => return None,
+        };
+        ty = fields
+            .iter()
+            .find(|field| field.name.to_snake_case() == segment.to_snake_case())?
+            .ty
+            .clone();
+    }
+    Some(ty)
+}
+
+fn field<'v>(value: &'v Value, path: &str, key: &str) -> Result<&'v Value, IdlSpecError> {
+    value
+        .get(key)
+        .ok_or_else(|| error(path, format!("missing `{}`", key)))
+}
+
+fn str_field<'v>(value: &'v Value, path: &str, key: &str) -> Result<&'v str, IdlSpecError> {
+    field(value, path, key)?
+        .as_str()
+        .ok_or_else(|| error(path, format!("expected `{}` to be a string", key)))
+}
+
+fn usize_field(value: &Value, path: &str, key: &str) -> Result<usize, IdlSpecError> {
+    field(value, path, key)?
+        .as_u64()
+        .map(|value| value as usize)
+        .ok_or_else(|| error(path, format!("expected `{}` to be an integer", key)))
+}
+
+fn bytes_field(value: &Value, path: &str, key: &str) -> Result<Vec<u8>, IdlSpecError> {
+    field(value, path, key)?
+        .as_array()
+        .and_then(|bytes| {
+            bytes
+                .iter()
+                .map(|byte| u8::try_from(byte.as_u64()?).ok())
+                .collect()
+        })
+        .ok_or_else(|| error(path, format!("expected `{}` to be a list of bytes", key)))
+}
+
+/// A list of values, empty if it is missing.
+fn list<'v>(value: &'v Value, key: &str) -> &'v [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn docs(value: &Value) -> Option<Vec<String>> {
+    let docs = list(value, "docs")
+        .iter()
+        .filter_map(|doc| doc.as_str().map(str::to_string))
+        .collect::<Vec<_>>();
+    (!docs.is_empty()).then_some(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "GjphYQcbP1m3FuDyCTUJf2mUMxKPE3j6feWU1rxvC7Ps";
+
+    fn example(name: &str) -> Idl {
+        let path = format!(
+            "{}/../../examples/{}/idl.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    fn govern() -> Value {
+        legacy_idl_to_new(&example("govern-cpi"), Some(ADDRESS)).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_examples() {
+        for name in ["govern-cpi", "marinade-cpi", "shank-cpi", "whirlpools"] {
+            let idl = example(name);
+            let new = legacy_idl_to_new(&idl, Some(ADDRESS)).unwrap();
+            assert!(is_new_idl(&new), "{}", name);
+            let legacy = new_idl_to_legacy(&new).unwrap();
+            assert_eq!(legacy.name, idl.name, "{}", name);
+            assert_eq!(legacy.version, idl.version, "{}", name);
+            // instructions are renamed to snake case, and back to camel case
+            let instructions = |idl: &Idl| {
+                let mut instructions = idl.instructions.clone();
+                for ix in &mut instructions {
+                    ix.name = ix.name.to_snake_case();
+                }
+                instructions
+            };
+            assert_eq!(instructions(&legacy), instructions(&idl), "{}", name);
+            assert_eq!(legacy.accounts, idl.accounts, "{}", name);
+            assert_eq!(legacy.types, idl.types, "{}", name);
+            assert_eq!(legacy.errors, idl.errors, "{}", name);
+            assert_eq!(legacy.constants, idl.constants, "{}", name);
+            assert_eq!(
+                DiscriminatorStrategy::detect(&legacy),
+                DiscriminatorStrategy::detect(&idl),
+                "{}",
+                name
+            );
+            // whether event fields are indexed is dropped
+            let events = |idl: &Idl| {
+                idl.events.clone().map(|events| {
+                    events
+                        .into_iter()
+                        .map(|event| {
+                            let fields =
+                                event.fields.into_iter().map(|field| (field.name, field.ty));
+                            (event.name, fields.collect::<Vec<_>>())
+                        })
+                        .collect::<Vec<_>>()
+                })
+            };
+            assert_eq!(events(&legacy), events(&idl), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_legacy_idl_is_not_new() {
+        let idl = serde_json::to_value(example("govern-cpi")).unwrap();
+        assert!(!is_new_idl(&idl));
+    }
+
+    #[test]
+    fn test_missing_address() {
+        assert_eq!(
+            legacy_idl_to_new(&example("govern-cpi"), None).unwrap_err(),
+            error("metadata.address", "missing the address of the program")
+        );
+        // Shank IDLs carry their address in their metadata
+        let new = legacy_idl_to_new(&example("shank-cpi"), None).unwrap();
+        assert_eq!(
+            new["address"],
+            "FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ"
+        );
+    }
+
+    #[test]
+    fn test_shank_discriminant_gap() {
+        let gap = crate::parse_idl_json(include_bytes!(
+            "../../../examples/shank-cpi/tests/fixtures/gap.json"
+        ))
+        .unwrap();
+        let new = legacy_idl_to_new(&gap, None).unwrap();
+        assert_eq!(new["instructions"][1]["discriminator"], json!([1]));
+        assert_eq!(new["instructions"][2]["discriminator"], json!([5]));
+
+        let legacy = new_idl_to_legacy(&new).unwrap();
+        assert_eq!(
+            legacy.metadata.unwrap()["discriminants"],
+            json!({ "close": 5 })
+        );
+    }
+
+    #[test]
+    fn test_instruction_discriminator_not_derivable() {
+        let mut new = govern();
+        new["instructions"][1]["discriminator"] = json!([1, 2, 3, 4, 5, 6, 7, 8]);
+        let error = new_idl_to_legacy(&new).unwrap_err();
+        assert_eq!(error.path, "instructions[1]");
+        assert!(error
+            .message
+            .contains("follows neither the Anchor nor the Shank convention"));
+    }
+
+    #[test]
+    fn test_account_discriminator_not_derivable() {
+        let mut new = govern();
+        new["accounts"][0]["discriminator"] = json!(vec![0u8; 8]);
+        assert_eq!(new_idl_to_legacy(&new).unwrap_err().path, "accounts[0]");
+    }
+
+    #[test]
+    fn test_event_discriminator_not_derivable() {
+        let mut new = govern();
+        new["events"][2]["discriminator"] = json!(vec![0u8; 8]);
+        let error = new_idl_to_legacy(&new).unwrap_err();
+        assert_eq!(error.path, "events[2]");
+        assert!(error.message.contains("derived from the name of the event"));
+    }
+
+    #[test]
+    fn test_generic_types() {
+        let mut new = govern();
+        new["types"][0]["generics"] = json!([{ "kind": "type", "name": "T" }]);
+        assert_eq!(
+            new_idl_to_legacy(&new).unwrap_err(),
+            error(
+                "types[0]",
+                "generic types have no equivalent in a legacy IDL"
+            )
+        );
+    }
+
+    #[test]
+    fn test_aliases_are_inlined() {
+        let new = json!({
+            "address": ADDRESS,
+            "metadata": { "name": "aliases", "version": "0.1.0", "spec": IDL_SPEC_VERSION },
+            "instructions": [],
+            "types": [
+                { "name": "Amount", "type": { "kind": "type", "alias": "u64" } },
+                {
+                    "name": "Balance",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [{ "name": "amount", "type": { "defined": { "name": "Amount" } } }]
+                    }
+                }
+            ]
+        });
+        let legacy = new_idl_to_legacy(&new).unwrap();
+        assert_eq!(legacy.types.len(), 1);
+        assert_eq!(
+            legacy.types[0].ty,
+            IdlTypeDefinitionTy::Struct {
+                fields: vec![IdlField {
+                    name: "amount".to_string(),
+                    docs: None,
+                    ty: IdlType::U64,
+                }]
+            }
+        );
+    }
+}
@@ -1,19 +1,27 @@
 use std::collections::BTreeMap;
 
 use anchor_syn::idl::{IdlField, IdlTypeDefinition};
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
-use crate::{generate_fields, get_field_list_properties, StructOpts};
+use crate::{
+    generate_fields, generate_len, generate_packed_accessors, generate_repr, DiscriminatorStrategy,
+    NamingConvention, StructOpts, TypeTable,
+};
 
 /// Generates an account state struct.
+///
+/// Accounts without a discriminator cannot use `#[account]`, and instead implement the
+/// account traits over their plain Borsh serialization.
 pub fn generate_account(
-    defs: &[IdlTypeDefinition],
+    types: &TypeTable,
     account_name: &str,
     fields: &[IdlField],
     opts: StructOpts,
+    naming: NamingConvention,
+    discriminators: &DiscriminatorStrategy,
 ) -> TokenStream {
-    let props = get_field_list_properties(defs, fields);
+    let props = types.field_list_properties(fields);
 
     let derive_copy = if props.can_copy && opts.zero_copy.is_none() {
         quote! {
@@ -29,62 +37,93 @@ pub fn generate_account(
     } else {
         quote! {}
     };
-    let derive_account = if let Some(zero_copy) = opts.zero_copy {
-        let zero_copy_quote = match zero_copy {
-            crate::ZeroCopy::Unsafe => quote! {
+    let struct_name = format_ident!("{}", account_name);
+    let (derive_account, account_impls) = match (discriminators, opts.zero_copy) {
+        (DiscriminatorStrategy::Shank { .. }, _) => (
+            quote! {
+                #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            },
+            generate_undiscriminated_account_impls(&struct_name),
+        ),
+        (DiscriminatorStrategy::Anchor, Some(crate::ZeroCopy::Unsafe)) => (
+            quote! {
                 #[account(zero_copy(unsafe))]
             },
-            crate::ZeroCopy::Safe => quote! {
+            quote! {},
+        ),
+        (DiscriminatorStrategy::Anchor, Some(crate::ZeroCopy::Safe)) => (
+            quote! {
                 #[account(zero_copy)]
             },
-        };
-        if let Some(repr) = opts.representation {
-            let repr_quote = match repr {
-                crate::Representation::C => quote! {
-                    #[repr(C)]
-                },
-                crate::Representation::Transparent => quote! {
-                    #[repr(transparent)]
-                },
-                crate::Representation::Packed => quote! {
-                    #[repr(packed)]
-                },
-            };
-            quote! {
-                #zero_copy_quote
-                #repr_quote
-            }
-        } else {
-            zero_copy_quote
-        }
-    } else {
-        quote! {#[account]}
+            quote! {},
+        ),
+        (DiscriminatorStrategy::Anchor, None) => (quote! {#[account]}, quote! {}),
     };
+    let repr = generate_repr(opts);
 
     let doc = format!(" Account: {}", account_name);
-    let struct_name = format_ident!("{}", account_name);
-    let fields_rendered = generate_fields(fields);
+    let fields_rendered = generate_fields(types, account_name, fields, naming);
+    let len = generate_len(
+        &struct_name,
+        props,
+        opts,
+        discriminators.account_discriminator_len(),
+    );
+    let accessors = generate_packed_accessors(types, &struct_name, fields, opts, naming);
     quote! {
         #derive_account
+        #repr
         #[doc = #doc]
         #derive_copy
         #derive_default
         pub struct #struct_name {
             #fields_rendered
         }
+
+        #account_impls
+        #len
+        #accessors
+    }
+}
+
+/// Implements the account traits `#[account]` would for an account without a discriminator.
+fn generate_undiscriminated_account_impls(struct_name: &Ident) -> TokenStream {
+    quote! {
+        impl anchor_lang::AccountSerialize for #struct_name {
+            fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+                AnchorSerialize::serialize(self, writer)
+                    .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotSerialize))
+            }
+        }
+
+        impl anchor_lang::AccountDeserialize for #struct_name {
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                // accounts may be padded beyond their serialized size
+                AnchorDeserialize::deserialize(buf)
+                    .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotDeserialize))
+            }
+        }
+
+        impl anchor_lang::Owner for #struct_name {
+            fn owner() -> Pubkey {
+                crate::ID
+            }
+        }
     }
 }
 
 /// Generates account state structs.
 pub fn generate_accounts(
-    typedefs: &[IdlTypeDefinition],
+    types: &TypeTable,
     account_defs: &[IdlTypeDefinition],
     struct_opts: &BTreeMap<String, StructOpts>,
+    naming: NamingConvention,
+    discriminators: &DiscriminatorStrategy,
 ) -> TokenStream {
     let defined = account_defs.iter().map(|def| match &def.ty {
         anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => {
             let opts = struct_opts.get(&def.name).copied().unwrap_or_default();
-            generate_account(typedefs, &def.name, fields, opts)
+            generate_account(types, &def.name, fields, opts, naming, discriminators)
         }
         anchor_syn::idl::IdlTypeDefinitionTy::Enum { .. } => {
             panic!("unexpected enum account");
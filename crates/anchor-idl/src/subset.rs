@@ -0,0 +1,288 @@
+use std::collections::BTreeSet;
+
+use anchor_syn::idl::{EnumFields, Idl, IdlType, IdlTypeDefinitionTy};
+use heck::ToSnakeCase;
+
+/// Selection of the instructions, accounts and types of an IDL to generate.
+///
+/// Instructions are matched by their snake case name, and accounts and types by their exact name.
+#[derive(Clone, Debug, Default)]
+pub struct IdlSubset {
+    /// Instructions to generate. All instructions are generated if `None`.
+    pub include_instructions: Option<BTreeSet<String>>,
+    /// Instructions not to generate.
+    pub exclude_instructions: BTreeSet<String>,
+    /// Accounts to generate. All accounts are generated if `None`.
+    pub include_accounts: Option<BTreeSet<String>>,
+    /// Types to generate in addition to those referenced by the generated instructions,
+    /// accounts and events.
+    pub include_types: Option<BTreeSet<String>>,
+}
+
+impl IdlSubset {
+    /// Whether the subset is the whole IDL.
+    pub fn is_everything(&self) -> bool {
+        self.include_instructions.is_none()
+            && self.exclude_instructions.is_empty()
+            && self.include_accounts.is_none()
+            && self.include_types.is_none()
+    }
+
+    fn includes_instruction(&self, name: &str) -> bool {
+        let name = name.to_snake_case();
+        let included = match &self.include_instructions {
+            Some(included) => included.contains(&name),
+            None => true,
+        };
+        included && !self.exclude_instructions.contains(&name)
+    }
+
+    /// Removes every item of the IDL outside of the subset.
+    ///
+    /// Types referenced by any remaining item are always kept, so that the IDL never
+    /// references a type which is not defined.
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let idl: anchor_idl::Idl =
+    ///     serde_json::from_str(include_str!("../../../examples/govern-cpi/idl.json")).unwrap();
+    /// let subset = anchor_idl::IdlSubset {
+    ///     include_instructions: Some(BTreeSet::from(["create_proposal".to_string()])),
+    ///     include_accounts: Some(BTreeSet::new()),
+    ///     ..Default::default()
+    /// };
+    /// let idl = subset.apply(&idl);
+    /// assert_eq!(idl.instructions.len(), 1);
+    /// assert!(idl.accounts.is_empty());
+    /// // `createProposal` takes a `Vec<ProposalInstruction>`, which contains `ProposalAccountMeta`s,
+    /// // and events contain `GovernanceParameters`
+    /// let types = idl.types.iter().map(|def| def.name.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(types, ["GovernanceParameters", "ProposalInstruction", "ProposalAccountMeta"]);
+    /// ```
+    pub fn apply(&self, idl: &Idl) -> Idl {
+        if self.is_everything() {
+            return idl.clone();
+        }
+        let instructions = idl
+            .instructions
+            .iter()
+            .filter(|ix| self.includes_instruction(&ix.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut included_accounts = idl
+            .accounts
+            .iter()
+            .map(|def| &def.name)
+            .filter(|name| match &self.include_accounts {
+                Some(included) => included.contains(*name),
+                None => true,
+            })
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        // every type referenced by the remaining items
+        let mut referenced = BTreeSet::new();
+        let mut pending = instructions
+            .iter()
+            .flat_map(|ix| ix.args.iter().map(|arg| &arg.ty).chain(ix.returns.as_ref()))
+            .chain(
+                idl.events
+                    .iter()
+                    .flatten()
+                    .flat_map(|event| event.fields.iter().map(|field| &field.ty)),
+            )
+            .flat_map(defined_names)
+            .chain(included_accounts.iter().cloned())
+            .chain(self.include_types.iter().flatten().cloned())
+            .collect::<Vec<_>>();
+        while let Some(name) = pending.pop() {
+            if !referenced.insert(name.clone()) {
+                continue;
+            }
+            let def = idl
+                .types
+                .iter()
+                .chain(idl.accounts.iter())
+                .find(|def| def.name == name);
+            if let Some(def) = def {
+                pending.extend(def_references(&def.ty));
+            }
+        }
+        // accounts referenced as types must be generated too
+        included_accounts.extend(
+            idl.accounts
+                .iter()
+                .filter(|def| referenced.contains(&def.name))
+                .map(|def| def.name.clone()),
+        );
+
+        Idl {
+            instructions,
+            accounts: idl
+                .accounts
+                .iter()
+                .filter(|def| included_accounts.contains(&def.name))
+                .cloned()
+                .collect(),
+            types: idl
+                .types
+                .iter()
+                .filter(|def| referenced.contains(&def.name))
+                .cloned()
+                .collect(),
+            ..idl.clone()
+        }
+    }
+}
+
+/// Names of the types referenced by a type definition.
+fn def_references(ty: &IdlTypeDefinitionTy) -> Vec<String> {
+    match ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields
+            .iter()
+            .flat_map(|field| defined_names(&field.ty))
+            .collect(),
+        IdlTypeDefinitionTy::Enum { variants } => variants
+            .iter()
+            .flat_map(|variant| match &variant.fields {
+                Some(EnumFields::Named(fields)) => fields
+                    .iter()
+                    .flat_map(|field| defined_names(&field.ty))
+                    .collect(),
+                Some(EnumFields::Tuple(types)) => types.iter().flat_map(defined_names).collect(),
+                None => vec![],
+            })
+            .collect(),
+    }
+}
+
+/// Names of the types referenced by an [IdlType].
+fn defined_names(ty: &IdlType) -> Vec<String> {
+    match ty {
+        IdlType::Defined(name) => vec![name.clone()],
+        IdlType::Option(inner) | IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+            defined_names(inner)
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "orders",
+        "instructions": [
+            {
+                "name": "placeOrder",
+                "accounts": [],
+                "args": [{ "name": "order", "type": { "defined": "Order" } }]
+            },
+            {
+                "name": "cancelOrder",
+                "accounts": [],
+                "args": [{ "name": "id", "type": "u64" }]
+            }
+        ],
+        "accounts": [
+            {
+                "name": "Book",
+                "type": {
+                    "kind": "struct",
+                    "fields": [{ "name": "levels", "type": { "array": [{ "defined": "Level" }, 4] } }]
+                }
+            },
+            {
+                "name": "Market",
+                "type": { "kind": "struct", "fields": [{ "name": "fee", "type": { "defined": "Fee" } }] }
+            }
+        ],
+        "types": [
+            {
+                "name": "Order",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Market" },
+                        { "name": "Limit", "fields": [{ "name": "price", "type": { "defined": "Price" } }] },
+                        { "name": "Pegged", "fields": [{ "option": { "defined": "Offset" } }] }
+                    ]
+                }
+            },
+            { "name": "Price", "type": { "kind": "struct", "fields": [{ "name": "ticks", "type": { "array": [{ "defined": "Tick" }, 2] } }] } },
+            { "name": "Tick", "type": { "kind": "struct", "fields": [{ "name": "index", "type": "i32" }] } },
+            { "name": "Offset", "type": { "kind": "struct", "fields": [{ "name": "book", "type": { "defined": "Book" } }] } },
+            { "name": "Level", "type": { "kind": "struct", "fields": [{ "name": "size", "type": "u64" }] } },
+            { "name": "Fee", "type": { "kind": "struct", "fields": [{ "name": "bps", "type": "u16" }] } },
+            { "name": "Unused", "type": { "kind": "struct", "fields": [] } }
+        ]
+    }"#;
+
+    fn idl() -> Idl {
+        serde_json::from_str(IDL).unwrap()
+    }
+
+    fn names(defs: &[anchor_syn::idl::IdlTypeDefinition]) -> Vec<&str> {
+        defs.iter().map(|def| def.name.as_str()).collect()
+    }
+
+    fn set(names: &[&str]) -> Option<BTreeSet<String>> {
+        Some(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[test]
+    fn test_everything() {
+        let subset = IdlSubset::default();
+        assert!(subset.is_everything());
+        assert_eq!(subset.apply(&idl()), idl());
+    }
+
+    #[test]
+    fn test_closure_through_enum_payloads_and_arrays() {
+        let subset = IdlSubset {
+            include_instructions: set(&["place_order"]),
+            include_accounts: set(&[]),
+            ..Default::default()
+        };
+        let idl = subset.apply(&idl());
+        assert_eq!(idl.instructions.len(), 1);
+        // `Order` reaches `Price` through a struct variant, `Tick` through an array, and
+        // `Offset` through a tuple variant of an option, whose `Book` account reaches `Level`
+        assert_eq!(
+            names(&idl.types),
+            ["Order", "Price", "Tick", "Offset", "Level"]
+        );
+        assert_eq!(names(&idl.accounts), ["Book"]);
+    }
+
+    #[test]
+    fn test_included_accounts_and_types() {
+        let subset = IdlSubset {
+            include_instructions: set(&[]),
+            include_accounts: set(&["Market"]),
+            include_types: set(&["Unused"]),
+            ..Default::default()
+        };
+        let idl = subset.apply(&idl());
+        assert!(idl.instructions.is_empty());
+        assert_eq!(names(&idl.accounts), ["Market"]);
+        assert_eq!(names(&idl.types), ["Fee", "Unused"]);
+    }
+
+    #[test]
+    fn test_excluded_instructions() {
+        let subset = IdlSubset {
+            exclude_instructions: BTreeSet::from(["place_order".to_string()]),
+            ..Default::default()
+        };
+        let idl = subset.apply(&idl());
+        assert_eq!(idl.instructions.len(), 1);
+        assert_eq!(idl.instructions[0].name, "cancelOrder");
+        // every account is still included
+        assert_eq!(names(&idl.accounts), ["Book", "Market"]);
+        assert_eq!(names(&idl.types), ["Level", "Fee"]);
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::{HashMap, HashSet},
+};
+
+use anchor_syn::idl::{EnumFields, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition};
+
+use crate::{primitive_size, FieldListProperties};
+
+/// Properties of a type, or of the fields of a struct or variants of an enum.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TypeInfo {
+    /// Whether the type can derive `Copy` and `Default`, and its maximum size.
+    pub properties: FieldListProperties,
+    /// Size in bytes of every serialized value, or `None` if it varies.
+    pub fixed_size: Option<usize>,
+    /// Whether the type is plain old data, i.e. made of numbers and public keys only, possibly in
+    /// arrays and structs, so that it can be zero copy.
+    pub is_pod: bool,
+}
+
+/// Index of the types of an IDL by name, caching the properties of every type.
+///
+/// Types which are not defined have no known properties: they are neither `Copy` nor `Default`,
+/// and have no maximum size.
+///
+/// Properties of a type depend on those of every type it contains. Building the table once and
+/// sharing it between all `generate_*` functions computes them once per type, instead of once
+/// per reference.
+pub struct TypeTable<'a> {
+    defs: &'a [IdlTypeDefinition],
+    index: HashMap<&'a str, &'a IdlTypeDefinition>,
+    infos: RefCell<HashMap<&'a str, TypeInfo>>,
+    recursive: OnceCell<HashSet<&'a str>>,
+    /// Types whose properties are being computed.
+    visiting: RefCell<Vec<&'a str>>,
+}
+
+impl<'a> TypeTable<'a> {
+    /// Indexes a list of type definitions.
+    pub fn new(defs: &'a [IdlTypeDefinition]) -> Self {
+        TypeTable {
+            defs,
+            index: defs.iter().map(|def| (def.name.as_str(), def)).collect(),
+            infos: RefCell::default(),
+            recursive: OnceCell::new(),
+            visiting: RefCell::default(),
+        }
+    }
+
+    /// Type definitions, in the order of the IDL.
+    pub fn defs(&self) -> &'a [IdlTypeDefinition] {
+        self.defs
+    }
+
+    /// Definition of a type.
+    pub fn get(&self, name: &str) -> Option<&'a IdlTypeDefinition> {
+        self.index.get(name).copied()
+    }
+
+    /// Properties of a defined type, or `None` if it is not defined.
+    pub fn info(&self, name: &str) -> Option<TypeInfo> {
+        self.get(name).map(|_| self.defined_info(name))
+    }
+
+    /// Properties of the fields of a struct.
+    pub fn field_list_properties(&self, fields: &[IdlField]) -> FieldListProperties {
+        self.field_list_info(fields).properties
+    }
+
+    /// Properties of the fields of a tuple.
+    pub fn type_list_properties(&self, types: &[IdlType]) -> FieldListProperties {
+        self.type_list_info(types.iter()).properties
+    }
+
+    /// Properties of the variants of an enum.
+    pub fn variant_list_properties(&self, variants: &[IdlEnumVariant]) -> FieldListProperties {
+        self.variant_list_info(variants).properties
+    }
+
+    /// Properties of an [IdlType].
+    pub fn type_properties(&self, ty: &IdlType) -> FieldListProperties {
+        self.ty_info(ty).properties
+    }
+
+    /// Size in bytes of every serialized value of an [IdlType], or `None` if it varies.
+    pub fn fixed_size(&self, ty: &IdlType) -> Option<usize> {
+        self.ty_info(ty).fixed_size
+    }
+
+    /// Properties of the fields of a struct, as a whole.
+    pub fn field_list_info(&self, fields: &[IdlField]) -> TypeInfo {
+        self.type_list_info(fields.iter().map(|field| &field.ty))
+    }
+
+    fn type_list_info<'t>(&self, types: impl Iterator<Item = &'t IdlType>) -> TypeInfo {
+        types.fold(
+            TypeInfo {
+                properties: FieldListProperties {
+                    can_copy: true,
+                    can_derive_default: true,
+                    max_size: Some(0),
+                },
+                fixed_size: Some(0),
+                is_pod: true,
+            },
+            |acc, ty| {
+                let inner = self.ty_info(ty);
+                TypeInfo {
+                    properties: FieldListProperties {
+                        can_copy: acc.properties.can_copy && inner.properties.can_copy,
+                        can_derive_default: acc.properties.can_derive_default
+                            && inner.properties.can_derive_default,
+                        max_size: acc
+                            .properties
+                            .max_size
+                            .zip(inner.properties.max_size)
+                            .map(|(acc, inner)| acc + inner),
+                    },
+                    fixed_size: acc
+                        .fixed_size
+                        .zip(inner.fixed_size)
+                        .map(|(acc, inner)| acc + inner),
+                    is_pod: acc.is_pod && inner.is_pod,
+                }
+            },
+        )
+    }
+
+    fn variant_list_info(&self, variants: &[IdlEnumVariant]) -> TypeInfo {
+        let infos = variants
+            .iter()
+            .map(|variant| match &variant.fields {
+                Some(EnumFields::Named(fields)) => self.field_list_info(fields),
+                Some(EnumFields::Tuple(types)) => self.type_list_info(types.iter()),
+                None => self.type_list_info([].iter()),
+            })
+            .collect::<Vec<_>>();
+        let properties = infos.iter().fold(
+            FieldListProperties {
+                can_copy: true,
+                can_derive_default: true,
+                max_size: Some(0),
+            },
+            |acc, info| FieldListProperties {
+                can_copy: acc.can_copy && info.properties.can_copy,
+                can_derive_default: acc.can_derive_default && info.properties.can_derive_default,
+                max_size: acc
+                    .max_size
+                    .zip(info.properties.max_size)
+                    .map(|(acc, variant)| acc.max(variant)),
+            },
+        );
+        // the payloads of every variant must have the same size
+        let first = infos.first().map_or(Some(0), |info| info.fixed_size);
+        let fixed_size = first.filter(|_| infos.iter().all(|info| info.fixed_size == first));
+        // the variant index is serialized as a `u8`
+        TypeInfo {
+            properties: FieldListProperties {
+                max_size: properties.max_size.map(|size| 1 + size),
+                ..properties
+            },
+            fixed_size: fixed_size.map(|size| 1 + size),
+            is_pod: false,
+        }
+    }
+
+    fn ty_info(&self, ty: &IdlType) -> TypeInfo {
+        match ty {
+            IdlType::Bool
+            | IdlType::U8
+            | IdlType::I8
+            | IdlType::U16
+            | IdlType::I16
+            | IdlType::U32
+            | IdlType::I32
+            | IdlType::F32
+            | IdlType::U64
+            | IdlType::I64
+            | IdlType::F64
+            | IdlType::U128
+            | IdlType::I128
+            | IdlType::U256
+            | IdlType::I256
+            | IdlType::PublicKey => TypeInfo {
+                properties: FieldListProperties {
+                    can_copy: true,
+                    can_derive_default: true,
+                    max_size: Some(primitive_size(ty)),
+                },
+                fixed_size: Some(primitive_size(ty)),
+                // `bool`s may only be 0 or 1, and `u256`s are not native
+                is_pod: !matches!(ty, IdlType::Bool | IdlType::U256 | IdlType::I256),
+            },
+            IdlType::Bytes => TypeInfo {
+                properties: FieldListProperties {
+                    can_copy: false,
+                    can_derive_default: false,
+                    max_size: None,
+                },
+                fixed_size: None,
+                is_pod: false,
+            },
+            IdlType::String | IdlType::Vec(_) => TypeInfo {
+                properties: FieldListProperties {
+                    can_copy: false,
+                    can_derive_default: true,
+                    max_size: None,
+                },
+                fixed_size: None,
+                is_pod: false,
+            },
+            IdlType::Defined(name) => self.defined_info(name),
+            IdlType::Option(inner) => {
+                let inner = self.ty_info(inner);
+                TypeInfo {
+                    properties: FieldListProperties {
+                        max_size: inner.properties.max_size.map(|size| 1 + size),
+                        ..inner.properties
+                    },
+                    fixed_size: None,
+                    is_pod: false,
+                }
+            }
+            IdlType::Array(inner, len) => {
+                let inner = self.ty_info(inner);
+                let can_derive_array_len = *len <= 32;
+                TypeInfo {
+                    properties: FieldListProperties {
+                        can_copy: inner.properties.can_copy,
+                        can_derive_default: can_derive_array_len
+                            && inner.properties.can_derive_default,
+                        max_size: inner.properties.max_size.map(|size| size * len),
+                    },
+                    fixed_size: inner.fixed_size.map(|size| size * len),
+                    is_pod: inner.is_pod,
+                }
+            }
+        }
+    }
+
+    fn defined_info(&self, name: &str) -> TypeInfo {
+        if let Some(info) = self.infos.borrow().get(name) {
+            return *info;
+        }
+        if self.visiting.borrow().contains(&name) {
+            // a recursive reference, which is either boxed or behind a `Vec`
+            return TypeInfo {
+                properties: FieldListProperties {
+                    can_copy: false,
+                    can_derive_default: true,
+                    max_size: None,
+                },
+                fixed_size: None,
+                is_pod: false,
+            };
+        }
+        let def = match self.get(name) {
+            Some(def) => def,
+            None => return TypeInfo::default(),
+        };
+        self.visiting.borrow_mut().push(&def.name);
+        let info = match &def.ty {
+            anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => self.field_list_info(fields),
+            anchor_syn::idl::IdlTypeDefinitionTy::Enum { variants } => {
+                self.variant_list_info(variants)
+            }
+        };
+        self.visiting.borrow_mut().pop();
+        // types in a cycle get the same properties wherever the cycle is entered, since every
+        // cycle goes through an `Option` or a `Vec`
+        self.infos.borrow_mut().insert(&def.name, info);
+        info
+    }
+
+    /// Whether the type `name` refers to itself, directly or through other types.
+    ///
+    /// Such types cannot derive Borsh traits, since the derive macros bound the type of every
+    /// field.
+    pub fn is_recursive(&self, name: &str) -> bool {
+        self.recursive
+            .get_or_init(|| self.find_recursive_types())
+            .contains(name)
+    }
+
+    /// Names of the types referenced by the fields or variants of a definition.
+    fn references(&self, def: &'a IdlTypeDefinition) -> Vec<&'a str> {
+        let mut references = vec![];
+        match &def.ty {
+            anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => fields
+                .iter()
+                .for_each(|field| collect_defined(&field.ty, &mut references)),
+            anchor_syn::idl::IdlTypeDefinitionTy::Enum { variants } => {
+                for variant in variants {
+                    match &variant.fields {
+                        Some(EnumFields::Named(fields)) => fields
+                            .iter()
+                            .for_each(|field| collect_defined(&field.ty, &mut references)),
+                        Some(EnumFields::Tuple(types)) => types
+                            .iter()
+                            .for_each(|ty| collect_defined(ty, &mut references)),
+                        None => {}
+                    }
+                }
+            }
+        }
+        references
+    }
+
+    /// Finds the types which are part of a cycle of references, i.e. the strongly connected
+    /// components of the reference graph with more than one type, and the types referring to
+    /// themselves, using Tarjan's algorithm.
+    fn find_recursive_types(&self) -> HashSet<&'a str> {
+        let mut search = ComponentSearch {
+            table: self,
+            indices: HashMap::new(),
+            low_links: HashMap::new(),
+            stack: vec![],
+            on_stack: HashSet::new(),
+            recursive: HashSet::new(),
+        };
+        for def in self.defs {
+            if !search.indices.contains_key(def.name.as_str()) {
+                search.visit(def);
+            }
+        }
+        search.recursive
+    }
+}
+
+/// State of Tarjan's strongly connected components algorithm.
+struct ComponentSearch<'a, 't> {
+    table: &'t TypeTable<'a>,
+    /// Order in which each type was visited.
+    indices: HashMap<&'a str, usize>,
+    /// Smallest index reachable from each type still on the stack.
+    low_links: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: HashSet<&'a str>,
+    recursive: HashSet<&'a str>,
+}
+
+impl<'a, 't> ComponentSearch<'a, 't> {
+    fn visit(&mut self, def: &'a IdlTypeDefinition) {
+        let name = def.name.as_str();
+        let index = self.indices.len();
+        self.indices.insert(name, index);
+        self.low_links.insert(name, index);
+        self.stack.push(name);
+        self.on_stack.insert(name);
+
+        let references = self.table.references(def);
+        for &reference in &references {
+            let next = match self.table.get(reference) {
+                Some(next) => next,
+                None => continue,
+            };
+            if !self.indices.contains_key(reference) {
+                self.visit(next);
+                let low_link = self.low_links[name].min(self.low_links[reference]);
+                self.low_links.insert(name, low_link);
+            } else if self.on_stack.contains(reference) {
+                let low_link = self.low_links[name].min(self.indices[reference]);
+                self.low_links.insert(name, low_link);
+            }
+        }
+
+        if self.low_links[name] == index {
+            let start = self.stack.iter().rposition(|item| *item == name).unwrap();
+            let component = self.stack.split_off(start);
+            for item in &component {
+                self.on_stack.remove(item);
+            }
+            if component.len() > 1 || references.contains(&name) {
+                self.recursive.extend(component);
+            }
+        }
+    }
+}
+
+/// Names of the types referenced by an [IdlType].
+fn collect_defined<'t>(ty: &'t IdlType, names: &mut Vec<&'t str>) {
+    match ty {
+        IdlType::Defined(name) => names.push(name),
+        IdlType::Option(inner) | IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+            collect_defined(inner, names)
+        }
+        _ => {}
+    }
+}
@@ -1,131 +1,231 @@
 use std::collections::BTreeMap;
 
 use anchor_syn::idl::{EnumFields, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition};
-use heck::ToSnakeCase;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote};
 
-use crate::StructOpts;
+use crate::{NamingConvention, Representation, StructOpts, TypeTable};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct FieldListProperties {
     pub can_copy: bool,
     pub can_derive_default: bool,
+    /// Maximum Borsh-serialized size in bytes, if it is bounded.
+    pub max_size: Option<usize>,
 }
 
+/// Properties of the fields of a struct. See [TypeTable::field_list_properties].
 pub fn get_field_list_properties(
     defs: &[IdlTypeDefinition],
     fields: &[IdlField],
 ) -> FieldListProperties {
-    get_type_list_properties(
-        defs,
-        &fields.iter().map(|f| f.ty.clone()).collect::<Vec<_>>(),
-    )
+    TypeTable::new(defs).field_list_properties(fields)
 }
 
+/// Properties of the fields of a tuple. See [TypeTable::type_list_properties].
 pub fn get_type_list_properties(
     defs: &[IdlTypeDefinition],
     fields: &[IdlType],
 ) -> FieldListProperties {
-    fields.iter().fold(
-        FieldListProperties {
-            can_copy: true,
-            can_derive_default: true,
-        },
-        |acc, el| {
-            let inner_props = get_type_properties(defs, el);
-            let can_copy = acc.can_copy && inner_props.can_copy;
-            let can_derive_default = acc.can_derive_default && inner_props.can_derive_default;
-            FieldListProperties {
-                can_copy,
-                can_derive_default,
-            }
-        },
-    )
+    TypeTable::new(defs).type_list_properties(fields)
 }
 
+/// Properties of the variants of an enum. See [TypeTable::variant_list_properties].
 pub fn get_variant_list_properties(
     defs: &[IdlTypeDefinition],
     variants: &[IdlEnumVariant],
 ) -> FieldListProperties {
-    variants.iter().fold(
-        FieldListProperties {
-            can_copy: true,
-            can_derive_default: true,
-        },
-        |acc, el| {
-            let props = match &el.fields {
-                Some(EnumFields::Named(fields)) => get_field_list_properties(defs, fields),
-                Some(EnumFields::Tuple(fields)) => get_type_list_properties(defs, fields),
-                None => acc,
-            };
-            FieldListProperties {
-                can_copy: acc.can_copy && props.can_copy,
-                can_derive_default: acc.can_derive_default && props.can_derive_default,
-            }
-        },
-    )
+    TypeTable::new(defs).variant_list_properties(variants)
 }
 
+/// Properties of an [IdlType]. See [TypeTable::type_properties].
 pub fn get_type_properties(defs: &[IdlTypeDefinition], ty: &IdlType) -> FieldListProperties {
+    TypeTable::new(defs).type_properties(ty)
+}
+
+/// Size in bytes of a primitive [IdlType].
+pub(crate) fn primitive_size(ty: &IdlType) -> usize {
     match ty {
-        IdlType::Bool
-        | IdlType::U8
-        | IdlType::I8
-        | IdlType::U16
-        | IdlType::I16
-        | IdlType::U32
-        | IdlType::I32
-        | IdlType::F32
-        | IdlType::U64
-        | IdlType::I64
-        | IdlType::F64
-        | IdlType::U128
-        | IdlType::I128
-        | IdlType::U256
-        | IdlType::I256
-        | IdlType::PublicKey => FieldListProperties {
-            can_copy: true,
-            can_derive_default: true,
-        },
-        IdlType::Bytes => FieldListProperties {
-            can_copy: false,
-            can_derive_default: false,
-        },
-        IdlType::String | IdlType::Vec(_) => FieldListProperties {
-            can_copy: false,
-            can_derive_default: true,
-        },
-        IdlType::Defined(inner) => {
-            let def = defs.iter().find(|def| def.name == *inner).unwrap();
-            match &def.ty {
-                anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => {
-                    get_field_list_properties(defs, fields)
-                }
-                anchor_syn::idl::IdlTypeDefinitionTy::Enum { variants } => {
-                    get_variant_list_properties(defs, variants)
-                }
+        IdlType::Bool | IdlType::U8 | IdlType::I8 => 1,
+        IdlType::U16 | IdlType::I16 => 2,
+        IdlType::U32 | IdlType::I32 | IdlType::F32 => 4,
+        IdlType::U64 | IdlType::I64 | IdlType::F64 => 8,
+        IdlType::U128 | IdlType::I128 => 16,
+        IdlType::U256 | IdlType::I256 | IdlType::PublicKey => 32,
+        _ => unreachable!("not a primitive type"),
+    }
+}
+
+/// Generates the `LEN` constant of a type.
+///
+/// Zero copy structs are measured with `size_of` and additionally get a
+/// compile-time assertion that their in-memory size matches the IDL.
+pub fn generate_len(
+    name: &Ident,
+    props: FieldListProperties,
+    opts: StructOpts,
+    discriminator_len: usize,
+) -> TokenStream {
+    let (len, doc) = if opts.zero_copy.is_some() {
+        (
+            quote! { #discriminator_len + ::std::mem::size_of::<Self>() },
+            format!(" Size of [{}] in bytes.", name),
+        )
+    } else if let Some(max_size) = props.max_size {
+        let len = discriminator_len + max_size;
+        (
+            quote! { #len },
+            format!(" Maximum serialized size of [{}] in bytes.", name),
+        )
+    } else {
+        return quote! {};
+    };
+    let doc = if discriminator_len > 0 {
+        format!("{} Includes the account discriminator.", doc)
+    } else {
+        doc
+    };
+
+    let assertion = match (opts.zero_copy, props.max_size) {
+        (Some(_), Some(expected)) => {
+            let msg = format!(
+                "size of `{}` does not match the IDL layout of {} bytes; check its representation",
+                name, expected
+            );
+            quote! {
+                const _: () = assert!(::std::mem::size_of::<#name>() == #expected, #msg);
             }
         }
-        IdlType::Option(inner) => get_type_properties(defs, inner),
-        IdlType::Array(inner, len) => {
-            let inner = get_type_properties(defs, inner);
-            let can_derive_array_len = *len <= 32;
-            FieldListProperties {
-                can_copy: inner.can_copy,
-                can_derive_default: can_derive_array_len && inner.can_derive_default,
+        _ => quote! {},
+    };
+
+    quote! {
+        impl #name {
+            #[doc = #doc]
+            pub const LEN: usize = #len;
+        }
+        #assertion
+    }
+}
+
+/// Generates the `repr` attribute of a struct or enum, if it has one.
+pub fn generate_repr(opts: StructOpts) -> TokenStream {
+    let mut reprs = vec![];
+    match opts.representation {
+        // packed structs keep the field order of the IDL, which `repr(packed)` alone does not
+        // guarantee
+        Some(Representation::Packed) => reprs.push(quote! { C, packed }),
+        Some(repr) => {
+            let ident = format_ident!("{}", repr.name());
+            reprs.push(quote! { #ident });
+        }
+        None => {}
+    }
+    if let Some(align) = opts.align {
+        let align = Literal::u32_unsuffixed(align);
+        reprs.push(quote! { align(#align) });
+    }
+    if reprs.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #[repr(#(#reprs),*)]
+        }
+    }
+}
+
+/// Generates by-value getters and setters for the fields of a `repr(packed)` struct,
+/// since references to its fields may be unaligned.
+///
+/// Fields which are not `Copy` get neither, since they cannot be read without an unaligned
+/// reference or moving them out of the struct.
+pub fn generate_packed_accessors(
+    types: &TypeTable,
+    name: &Ident,
+    fields: &[IdlField],
+    opts: StructOpts,
+    naming: NamingConvention,
+) -> TokenStream {
+    if !matches!(opts.representation, Some(crate::Representation::Packed)) {
+        return quote! {};
+    }
+    let accessors = fields.iter().map(|field| {
+        if !types.type_properties(&field.ty).can_copy {
+            return quote! {};
+        }
+        let field_name = naming.field_ident(&field.name);
+        let setter_name = format_ident!("set_{}", field_name);
+        let ty: TokenStream = crate::field_ty_to_rust_type(types, &name.to_string(), &field.ty)
+            .parse()
+            .unwrap();
+
+        let getter_doc = format!(" Returns a copy of `{}`.", field_name);
+        let setter_doc = format!(" Sets `{}`.", field_name);
+        let element_accessors = match &field.ty {
+            IdlType::Array(inner, _) => {
+                let element_getter_name = format_ident!("{}_at", field_name);
+                let element_setter_name = format_ident!("set_{}_at", field_name);
+                let element_ty: TokenStream =
+                    crate::field_ty_to_rust_type(types, &name.to_string(), inner)
+                        .parse()
+                        .unwrap();
+                let element_getter_doc = format!(
+                    " Returns a copy of the element of `{}` at `index`.",
+                    field_name
+                );
+                let element_setter_doc =
+                    format!(" Sets the element of `{}` at `index`.", field_name);
+                quote! {
+                    #[doc = #element_getter_doc]
+                    pub fn #element_getter_name(&self, index: usize) -> #element_ty {
+                        self.#field_name[index]
+                    }
+
+                    #[doc = #element_setter_doc]
+                    pub fn #element_setter_name(&mut self, index: usize, value: #element_ty) {
+                        self.#field_name[index] = value;
+                    }
+                }
+            }
+            _ => quote! {},
+        };
+        quote! {
+            #[doc = #getter_doc]
+            pub fn #field_name(&self) -> #ty {
+                self.#field_name
             }
+
+            #[doc = #setter_doc]
+            pub fn #setter_name(&mut self, value: #ty) {
+                self.#field_name = value;
+            }
+
+            #element_accessors
+        }
+    });
+    let allow_attrs = naming.allow_attrs();
+    quote! {
+        #allow_attrs
+        impl #name {
+            #(#accessors)*
         }
     }
 }
 
-/// Generates struct fields from a list of [IdlField]s.
-pub fn generate_fields(fields: &[IdlField]) -> TokenStream {
+/// Generates the fields of the struct `parent` from a list of [IdlField]s.
+pub fn generate_fields(
+    types: &TypeTable,
+    parent: &str,
+    fields: &[IdlField],
+    naming: NamingConvention,
+) -> TokenStream {
+    let allow_attrs = naming.allow_attrs();
     let fields_rendered = fields.iter().map(|arg| {
-        let name = format_ident!("{}", arg.name.to_snake_case());
-        let type_name = crate::ty_to_rust_type(&arg.ty);
+        let name = naming.field_ident(&arg.name);
+        let type_name = crate::field_ty_to_rust_type(types, parent, &arg.ty);
         let stream: proc_macro2::TokenStream = type_name.parse().unwrap();
         quote! {
+            #allow_attrs
             pub #name: #stream
         }
     });
@@ -136,13 +236,14 @@ pub fn generate_fields(fields: &[IdlField]) -> TokenStream {
 
 /// Generates a struct.
 pub fn generate_struct(
-    defs: &[IdlTypeDefinition],
+    types: &TypeTable,
     struct_name: &Ident,
     fields: &[IdlField],
     opts: StructOpts,
+    naming: NamingConvention,
 ) -> TokenStream {
-    let fields_rendered = generate_fields(fields);
-    let props = get_field_list_properties(defs, fields);
+    let fields_rendered = generate_fields(types, &struct_name.to_string(), fields, naming);
+    let props = types.field_list_properties(fields);
 
     let derive_default = if props.can_derive_default {
         quote! {
@@ -151,34 +252,15 @@ pub fn generate_struct(
     } else {
         quote! {}
     };
+    let repr = generate_repr(opts);
     let derive_serializers = if let Some(zero_copy) = opts.zero_copy {
-        let zero_copy_quote = match zero_copy {
+        match zero_copy {
             crate::ZeroCopy::Unsafe => quote! {
                 #[zero_copy(unsafe)]
             },
             crate::ZeroCopy::Safe => quote! {
                 #[zero_copy]
             },
-        };
-        if let Some(repr) = opts.representation {
-            let repr_quote = match repr {
-                crate::Representation::C => quote! {
-                    #[repr(C)]
-                },
-                crate::Representation::Transparent => quote! {
-
-                    #[repr(transparent)]
-                },
-                crate::Representation::Packed => quote! {
-                    #[repr(packed)]
-                },
-            };
-            quote! {
-                #zero_copy_quote
-                #repr_quote
-            }
-        } else {
-            zero_copy_quote
         }
     } else {
         let derive_copy = if props.can_copy {
@@ -188,30 +270,106 @@ pub fn generate_struct(
         } else {
             quote! {}
         };
+        let derive_borsh = if types.is_recursive(&struct_name.to_string()) {
+            quote! {}
+        } else {
+            quote! {
+                #[derive(AnchorSerialize, AnchorDeserialize)]
+            }
+        };
         quote! {
-            #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            #derive_borsh
+            #[derive(Clone)]
             #derive_copy
         }
     };
+    let recursive_serializers =
+        if opts.zero_copy.is_none() && types.is_recursive(&struct_name.to_string()) {
+            generate_recursive_serializers(struct_name, fields, naming)
+        } else {
+            quote! {}
+        };
+
+    let len = generate_len(struct_name, props, opts, 0);
+    let accessors = generate_packed_accessors(types, struct_name, fields, opts, naming);
 
     quote! {
         #derive_serializers
+        #repr
         #[derive(Debug)]
         #derive_default
         pub struct #struct_name {
             #fields_rendered
         }
+
+        #recursive_serializers
+        #len
+        #accessors
+    }
+}
+
+/// Implements the Borsh traits of a recursive struct, which cannot derive them.
+fn generate_recursive_serializers(
+    struct_name: &Ident,
+    fields: &[IdlField],
+    naming: NamingConvention,
+) -> TokenStream {
+    let field_names = fields
+        .iter()
+        .map(|field| naming.field_ident(&field.name))
+        .collect::<Vec<_>>();
+    quote! {
+        impl AnchorSerialize for #struct_name {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                #(AnchorSerialize::serialize(&self.#field_names, writer)?;)*
+                Ok(())
+            }
+        }
+
+        impl AnchorDeserialize for #struct_name {
+            fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+                Ok(Self {
+                    #(#field_names: AnchorDeserialize::deserialize_reader(reader)?,)*
+                })
+            }
+        }
     }
 }
 
-/// Generates an enum.
+/// Generates an enum, with the payloads of its variants.
 pub fn generate_enum(
-    defs: &[IdlTypeDefinition],
+    types: &TypeTable,
     enum_name: &Ident,
     variants: &[IdlEnumVariant],
+    opts: StructOpts,
+    naming: NamingConvention,
 ) -> TokenStream {
-    let variant_idents = variants.iter().map(|v| format_ident!("{}", v.name));
-    let props = get_variant_list_properties(defs, variants);
+    let parent = enum_name.to_string();
+    let field_ty = |ty: &IdlType| -> TokenStream {
+        crate::field_ty_to_rust_type(types, &parent, ty)
+            .parse()
+            .unwrap()
+    };
+    let variants_rendered = variants.iter().map(|variant| {
+        let name = format_ident!("{}", variant.name);
+        match &variant.fields {
+            Some(EnumFields::Named(fields)) => {
+                let field_names = fields.iter().map(|field| naming.field_ident(&field.name));
+                let field_types = fields.iter().map(|field| field_ty(&field.ty));
+                quote! {
+                    #name { #(#field_names: #field_types),* }
+                }
+            }
+            Some(EnumFields::Tuple(tuple)) => {
+                let field_types = tuple.iter().map(field_ty);
+                quote! {
+                    #name(#(#field_types),*)
+                }
+            }
+            None => quote! { #name },
+        }
+    });
+    let props = types.variant_list_properties(variants);
 
     let derive_copy = if props.can_copy {
         quote! {
@@ -220,38 +378,178 @@ pub fn generate_enum(
     } else {
         quote! {}
     };
+    let (derive_borsh, recursive_serializers) = if types.is_recursive(&parent) {
+        (
+            quote! {},
+            generate_recursive_enum_serializers(enum_name, variants, naming),
+        )
+    } else {
+        (
+            quote! {
+                #[derive(AnchorSerialize, AnchorDeserialize)]
+            },
+            quote! {},
+        )
+    };
 
-    let default_variant = format_ident!("{}", variants.first().unwrap().name);
+    // the default is the first variant, if every variant has a default, which is what types
+    // containing the enum expect when deriving `Default`
+    let default_impl = if props.can_derive_default {
+        let first = variants.first().unwrap();
+        let default_variant = format_ident!("{}", first.name);
+        let default_value = match &first.fields {
+            Some(EnumFields::Named(fields)) => {
+                let field_names = fields.iter().map(|field| naming.field_ident(&field.name));
+                quote! {
+                    Self::#default_variant { #(#field_names: Default::default()),* }
+                }
+            }
+            Some(EnumFields::Tuple(tuple)) => {
+                let defaults = tuple.iter().map(|_| quote! { Default::default() });
+                quote! {
+                    Self::#default_variant(#(#defaults),*)
+                }
+            }
+            None => quote! { Self::#default_variant },
+        };
+        quote! {
+            impl Default for #enum_name {
+                fn default() -> Self {
+                    #default_value
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let repr = generate_repr(opts);
+    let len = generate_len(enum_name, props, opts, 0);
+    let allow_attrs = naming.allow_attrs();
 
     quote! {
-        #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+        #derive_borsh
+        #[derive(Clone, Debug)]
         #derive_copy
+        #repr
+        #allow_attrs
         pub enum #enum_name {
-            #(#variant_idents),*
+            #(#variants_rendered),*
         }
 
-        impl Default for #enum_name {
-            fn default() -> Self {
-                Self::#default_variant
+        #default_impl
+        #recursive_serializers
+        #len
+    }
+}
+
+/// Implements the Borsh traits of a recursive enum, which cannot derive them.
+///
+/// Variants are serialized as their index as a `u8`, followed by their payload.
+fn generate_recursive_enum_serializers(
+    enum_name: &Ident,
+    variants: &[IdlEnumVariant],
+    naming: NamingConvention,
+) -> TokenStream {
+    let (patterns, constructors): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|variant| {
+            let name = format_ident!("{}", variant.name);
+            match &variant.fields {
+                Some(EnumFields::Named(fields)) => {
+                    let field_names = fields
+                        .iter()
+                        .map(|field| naming.field_ident(&field.name))
+                        .collect::<Vec<_>>();
+                    (
+                        (
+                            quote! { Self::#name { #(#field_names),* } },
+                            field_names.clone(),
+                        ),
+                        quote! {
+                            Self::#name {
+                                #(#field_names: AnchorDeserialize::deserialize_reader(reader)?),*
+                            }
+                        },
+                    )
+                }
+                Some(EnumFields::Tuple(tuple)) => {
+                    let field_names = (0..tuple.len())
+                        .map(|index| format_ident!("field_{}", index))
+                        .collect::<Vec<_>>();
+                    let deserialized = tuple
+                        .iter()
+                        .map(|_| quote! { AnchorDeserialize::deserialize_reader(reader)? });
+                    (
+                        (quote! { Self::#name(#(#field_names),*) }, field_names),
+                        quote! { Self::#name(#(#deserialized),*) },
+                    )
+                }
+                None => ((quote! { Self::#name }, vec![]), quote! { Self::#name }),
+            }
+        })
+        .unzip();
+    let indices = (0..variants.len() as u8).collect::<Vec<_>>();
+    let serialized = patterns
+        .iter()
+        .zip(&indices)
+        .map(|((pattern, fields), index)| {
+            quote! {
+                #pattern => {
+                    AnchorSerialize::serialize(&#index, writer)?;
+                    #(AnchorSerialize::serialize(#fields, writer)?;)*
+                }
+            }
+        });
+    let allow_attrs = naming.allow_attrs();
+    quote! {
+        #allow_attrs
+        impl AnchorSerialize for #enum_name {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                match self {
+                    #(#serialized)*
+                }
+                Ok(())
+            }
+        }
+
+        impl AnchorDeserialize for #enum_name {
+            fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+                let index: u8 = AnchorDeserialize::deserialize_reader(reader)?;
+                Ok(match index {
+                    #(#indices => #constructors,)*
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("Unexpected variant index: {:?}", index),
+                        ))
+                    }
+                })
             }
         }
     }
 }
 
-/// Generates structs and enums.
+/// Generates structs and enums, re-exporting those defined in other crates.
 pub fn generate_typedefs(
-    typedefs: &[IdlTypeDefinition],
+    types: &TypeTable,
     struct_opts: &BTreeMap<String, StructOpts>,
+    extern_types: &BTreeMap<String, syn::Path>,
+    naming: NamingConvention,
 ) -> TokenStream {
-    let defined = typedefs.iter().map(|def| {
+    let defined = types.defs().iter().map(|def| {
+        if let Some(path) = extern_types.get(&def.name) {
+            return quote! {
+                pub use #path;
+            };
+        }
         let struct_name = format_ident!("{}", def.name);
+        let opts = struct_opts.get(&def.name).copied().unwrap_or_default();
         match &def.ty {
             anchor_syn::idl::IdlTypeDefinitionTy::Struct { fields } => {
-                let opts = struct_opts.get(&def.name).copied().unwrap_or_default();
-                generate_struct(typedefs, &struct_name, fields, opts)
+                generate_struct(types, &struct_name, fields, opts, naming)
             }
             anchor_syn::idl::IdlTypeDefinitionTy::Enum { variants } => {
-                generate_enum(typedefs, &struct_name, variants)
+                generate_enum(types, &struct_name, variants, opts, naming)
             }
         }
     });
@@ -259,3 +557,133 @@ pub fn generate_typedefs(
         #(#defined)*
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anchor_syn::idl::IdlTypeDefinitionTy;
+
+    use super::*;
+
+    fn generate_enums(defs: &str) -> Vec<String> {
+        let defs: Vec<IdlTypeDefinition> = serde_json::from_str(defs).unwrap();
+        let types = TypeTable::new(&defs);
+        defs.iter()
+            .map(|def| match &def.ty {
+                IdlTypeDefinitionTy::Enum { variants } => generate_enum(
+                    &types,
+                    &format_ident!("{}", def.name),
+                    variants,
+                    StructOpts::default(),
+                    NamingConvention::Rust,
+                )
+                .to_string(),
+                IdlTypeDefinitionTy::Struct { .. } => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_enum_default_is_first_variant() {
+        let enums = generate_enums(
+            r#"[
+                {
+                    "name": "Order",
+                    "type": {
+                        "kind": "enum",
+                        "variants": [
+                            { "name": "Limit", "fields": [{ "name": "price", "type": "u64" }] },
+                            { "name": "Market" }
+                        ]
+                    }
+                },
+                {
+                    "name": "Memo",
+                    "type": {
+                        "kind": "enum",
+                        "variants": [{ "name": "Raw", "fields": ["bytes"] }, { "name": "Empty" }]
+                    }
+                }
+            ]"#,
+        );
+        let default_impl = quote! {
+            impl Default for Order {
+                fn default() -> Self {
+                    Self::Limit { price: Default::default() }
+                }
+            }
+        };
+        assert!(enums[0].contains(&default_impl.to_string()));
+        // `Vec<u8>` payloads are not defaulted, as in structs
+        assert!(!enums[1].contains("impl Default"));
+    }
+
+    #[test]
+    fn test_packed_accessors_skip_fields_which_are_not_copy() {
+        let fields: Vec<IdlField> = serde_json::from_str(
+            r#"[
+                { "name": "amount", "type": "u64" },
+                { "name": "label", "type": "string" }
+            ]"#,
+        )
+        .unwrap();
+        let accessors = generate_packed_accessors(
+            &TypeTable::new(&[]),
+            &format_ident!("Vault"),
+            &fields,
+            StructOpts {
+                representation: Some(Representation::Packed),
+                ..Default::default()
+            },
+            NamingConvention::Rust,
+        )
+        .to_string();
+        assert!(accessors.contains("pub fn amount (& self) -> u64"));
+        assert!(accessors.contains("pub fn set_amount"));
+        assert!(!accessors.contains("label"));
+    }
+
+    #[test]
+    fn test_recursive_enum_implements_borsh() {
+        let enums = generate_enums(
+            r#"[{
+                "name": "Condition",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Always" },
+                        { "name": "All", "fields": [{ "vec": { "defined": "Condition" } }] }
+                    ]
+                }
+            }]"#,
+        );
+        assert!(!enums[0].contains("derive (AnchorSerialize"));
+        assert!(enums[0].contains("impl AnchorSerialize for Condition"));
+        assert!(enums[0].contains("impl AnchorDeserialize for Condition"));
+    }
+
+    fn repr(representation: Representation) -> String {
+        generate_repr(StructOpts {
+            representation: Some(representation),
+            ..Default::default()
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_packed_repr_keeps_field_order() {
+        assert_eq!(
+            repr(Representation::Packed),
+            quote! { #[repr(C, packed)] }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_repr() {
+        assert_eq!(generate_repr(StructOpts::default()).to_string(), "");
+        assert_eq!(repr(Representation::C), quote! { #[repr(C)] }.to_string());
+        assert_eq!(
+            repr(Representation::Transparent),
+            quote! { #[repr(transparent)] }.to_string()
+        );
+    }
+}
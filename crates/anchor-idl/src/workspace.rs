@@ -0,0 +1,397 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use heck::ToKebabCase;
+
+use crate::{find_shared_types, parse_idl_json, IdlJsonError, TypeMismatch, GEN_VERSION};
+
+/// An error reading an Anchor workspace.
+#[derive(Debug)]
+pub enum WorkspaceError {
+    /// A file could not be read or written.
+    Io { path: PathBuf, error: io::Error },
+    /// An IDL is not valid JSON, or not a valid Anchor IDL.
+    Parse { path: PathBuf, error: IdlJsonError },
+    /// `Anchor.toml` is not valid TOML.
+    Toml(toml::de::Error),
+    /// `Anchor.toml` has no programs for the cluster.
+    UnknownCluster(String),
+    /// The IDL of a program has not been built.
+    MissingIdl { program: String, path: PathBuf },
+}
+
+impl fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            WorkspaceError::Parse { path, error } => {
+                write!(f, "could not parse {}: {}", path.display(), error)
+            }
+            WorkspaceError::Toml(error) => write!(f, "invalid Anchor.toml: {}", error),
+            WorkspaceError::UnknownCluster(cluster) => {
+                write!(f, "Anchor.toml has no `[programs.{}]`", cluster)
+            }
+            WorkspaceError::MissingIdl { program, path } => write!(
+                f,
+                "IDL of `{}` not found at {}, run `anchor build` first",
+                program,
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkspaceError {}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> WorkspaceError + '_ {
+    move |error| WorkspaceError::Io {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+/// The crates generated for the programs of an Anchor workspace.
+#[derive(Clone, Debug)]
+pub struct GeneratedCrates {
+    /// Directories of the crates.
+    pub crates: Vec<PathBuf>,
+    /// Types defined with different layouts by different programs, which are generated
+    /// separately by each of them.
+    pub mismatches: Vec<TypeMismatch>,
+}
+
+/// An Anchor workspace, as configured by its `Anchor.toml`.
+#[derive(Clone, Debug)]
+pub struct AnchorWorkspace {
+    /// Directory containing `Anchor.toml`.
+    pub root: PathBuf,
+    /// Program ids of every program, by cluster and then by program name.
+    pub programs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// A program of an Anchor workspace deployed to a cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkspaceProgram {
+    /// Name of the program, e.g. `my_program`.
+    pub name: String,
+    /// Address of the program on the cluster.
+    pub program_id: String,
+    /// Path to the IDL built by `anchor build`, i.e. `target/idl/<name>.json`.
+    pub idl_path: PathBuf,
+}
+
+impl AnchorWorkspace {
+    /// Reads the `Anchor.toml` at `path`.
+    pub fn load(path: &Path) -> Result<Self, WorkspaceError> {
+        let contents = fs::read_to_string(path).map_err(io_error(path))?;
+        let root = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        Self::parse(root, &contents)
+    }
+
+    /// Parses the contents of an `Anchor.toml` located in `root`.
+    pub fn parse(root: PathBuf, contents: &str) -> Result<Self, WorkspaceError> {
+        let config: toml::Table = contents.parse().map_err(WorkspaceError::Toml)?;
+        let programs = config
+            .get("programs")
+            .and_then(|programs| programs.as_table())
+            .into_iter()
+            .flatten()
+            .map(|(cluster, programs)| {
+                let programs = programs
+                    .as_table()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(name, address)| {
+                        // programs are either `name = "address"` or `name = { address = "..." }`
+                        let address = address
+                            .as_str()
+                            .or_else(|| address.get("address")?.as_str())?;
+                        Some((name.clone(), address.to_string()))
+                    })
+                    .collect();
+                (cluster.clone(), programs)
+            })
+            .collect();
+        Ok(AnchorWorkspace { root, programs })
+    }
+
+    /// The programs deployed to `cluster`, e.g. `localnet`.
+    pub fn programs(&self, cluster: &str) -> Result<Vec<WorkspaceProgram>, WorkspaceError> {
+        let programs = self
+            .programs
+            .get(cluster)
+            .ok_or_else(|| WorkspaceError::UnknownCluster(cluster.to_string()))?;
+        Ok(programs
+            .iter()
+            .map(|(name, program_id)| WorkspaceProgram {
+                name: name.clone(),
+                program_id: program_id.clone(),
+                idl_path: self
+                    .root
+                    .join("target")
+                    .join("idl")
+                    .join(format!("{}.json", name)),
+            })
+            .collect())
+    }
+
+    /// Writes a CPI crate named `<name>-cpi` into `out_dir` for every program deployed to
+    /// `cluster`.
+    ///
+    /// IDLs may be in any of the formats accepted by [parse_idl_json]. Types which are defined
+    /// identically by several programs are only generated by the first of them, and used by the
+    /// others through `extern_types`.
+    ///
+    /// ```
+    /// use anchor_idl::AnchorWorkspace;
+    ///
+    /// let dir = std::env::temp_dir().join("anchor-idl-workspace-doctest");
+    /// std::fs::create_dir_all(dir.join("target/idl")).unwrap();
+    /// std::fs::copy("../../examples/govern-cpi/idl.json", dir.join("target/idl/govern.json")).unwrap();
+    /// std::fs::write(
+    ///     dir.join("Anchor.toml"),
+    ///     "[programs.localnet]\ngovern = \"Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw\"\n",
+    /// )
+    /// .unwrap();
+    ///
+    /// let workspace = AnchorWorkspace::load(&dir.join("Anchor.toml")).unwrap();
+    /// let generated = workspace.generate_crates("localnet", &dir.join("crates")).unwrap();
+    /// assert_eq!(generated.crates, vec![dir.join("crates/govern-cpi")]);
+    /// assert!(generated.mismatches.is_empty());
+    ///
+    /// let lib_rs = std::fs::read_to_string(dir.join("crates/govern-cpi/src/lib.rs")).unwrap();
+    /// assert!(lib_rs.contains(r#"idl_path = "../../target/idl/govern.json","#));
+    /// assert!(lib_rs.contains(r#"declare_id!("Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw");"#));
+    /// ```
+    pub fn generate_crates(
+        &self,
+        cluster: &str,
+        out_dir: &Path,
+    ) -> Result<GeneratedCrates, WorkspaceError> {
+        let programs = self.programs(cluster)?;
+        let mut idls = BTreeMap::new();
+        for program in &programs {
+            if !program.idl_path.is_file() {
+                return Err(WorkspaceError::MissingIdl {
+                    program: program.name.clone(),
+                    path: program.idl_path.clone(),
+                });
+            }
+            let contents = fs::read(&program.idl_path).map_err(io_error(&program.idl_path))?;
+            let idl = parse_idl_json(&contents).map_err(|error| WorkspaceError::Parse {
+                path: program.idl_path.clone(),
+                error,
+            })?;
+            idls.insert(program.name.clone(), idl);
+        }
+        let shared = find_shared_types(&idls);
+        let programs_by_name = programs
+            .iter()
+            .map(|program| (program.name.as_str(), program))
+            .collect::<BTreeMap<_, _>>();
+
+        let crates = programs
+            .iter()
+            .map(|program| {
+                let crate_dir = out_dir.join(program.crate_name());
+                let src_dir = crate_dir.join("src");
+                fs::create_dir_all(&src_dir).map_err(io_error(&src_dir))?;
+                let idl_path = relative_path(&crate_dir, &program.idl_path)
+                    .map_err(io_error(&program.idl_path))?;
+                let extern_types = shared
+                    .extern_types
+                    .get(&program.name)
+                    .cloned()
+                    .unwrap_or_default()
+                    .into_iter()
+                    // owners are keys of `idls`, so they are always among the programs
+                    .filter_map(|(name, owner)| {
+                        let owner = *programs_by_name.get(owner.as_str())?;
+                        Some((name, owner.clone()))
+                    })
+                    .collect::<BTreeMap<_, _>>();
+
+                let cargo_toml = crate_dir.join("Cargo.toml");
+                fs::write(&cargo_toml, program.generate_cargo_toml(&extern_types))
+                    .map_err(io_error(&cargo_toml))?;
+                let lib_rs = src_dir.join("lib.rs");
+                fs::write(&lib_rs, program.generate_lib_rs(&idl_path, &extern_types))
+                    .map_err(io_error(&lib_rs))?;
+                Ok(crate_dir)
+            })
+            .collect::<Result<_, WorkspaceError>>()?;
+        Ok(GeneratedCrates {
+            crates,
+            mismatches: shared.mismatches,
+        })
+    }
+}
+
+impl WorkspaceProgram {
+    /// Name of the generated CPI crate, e.g. `my-program-cpi`.
+    pub fn crate_name(&self) -> String {
+        format!("{}-cpi", self.name.to_kebab_case())
+    }
+
+    /// Generates the `Cargo.toml` of the CPI crate, depending on the crates of the programs
+    /// which define its `extern_types`.
+    pub fn generate_cargo_toml(&self, extern_types: &BTreeMap<String, WorkspaceProgram>) -> String {
+        let dependencies = extern_types
+            .values()
+            .map(|owner| owner.crate_name())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|name| format!("{} = {{ path = \"../{}\" }}\n", name, name))
+            .collect::<String>();
+        format!(
+            r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+description = "Autogenerated CPI client for the {program} program."
+
+[features]
+default = ["cpi"]
+no-entrypoint = []
+no-idl = []
+no-log-ix-name = []
+cpi = ["no-entrypoint"]
+
+[dependencies]
+anchor-gen = "{version}"
+anchor-lang = ">=0.28"
+{dependencies}"#,
+            name = self.crate_name(),
+            program = self.name,
+            version = GEN_VERSION.unwrap_or("*"),
+        )
+    }
+
+    /// Generates the `lib.rs` of the CPI crate, given the path of the IDL relative to the crate
+    /// and the programs which define its `extern_types`.
+    pub fn generate_lib_rs(
+        &self,
+        idl_path: &Path,
+        extern_types: &BTreeMap<String, WorkspaceProgram>,
+    ) -> String {
+        let extern_types = if extern_types.is_empty() {
+            String::new()
+        } else {
+            let types = extern_types
+                .iter()
+                .map(|(name, owner)| {
+                    format!(
+                        "        {} = \"{}::typedefs::{}\",\n",
+                        name,
+                        owner.crate_name().replace('-', "_"),
+                        name
+                    )
+                })
+                .collect::<String>();
+            format!("    extern_types(\n{}    ),\n", types)
+        };
+        format!(
+            "anchor_gen::generate_cpi_interface!(\n    idl_path = {:?},\n{});\n\ndeclare_id!({:?});\n",
+            idl_path.to_string_lossy().replace('\\', "/"),
+            extern_types,
+            self.program_id,
+        )
+    }
+}
+
+/// Computes the path of `to` relative to the directory `from`.
+fn relative_path(from: &Path, to: &Path) -> io::Result<PathBuf> {
+    let from = from.canonicalize()?;
+    let to = to.canonicalize()?;
+    let from = from.components().collect::<Vec<_>>();
+    let to = to.components().collect::<Vec<_>>();
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(from, to)| from == to)
+        .count();
+    Ok(from[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .chain(to[common..].iter().copied())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_syn::idl::Idl;
+
+    use super::*;
+    use crate::legacy_idl_to_new;
+
+    const GOVERN_IDL: &str = include_str!("../../../examples/govern-cpi/idl.json");
+
+    /// Creates a workspace in a temporary directory with the given IDLs deployed to `localnet`.
+    fn workspace(name: &str, idls: &[(&str, &str)]) -> AnchorWorkspace {
+        let root = std::env::temp_dir().join(format!("anchor-idl-workspace-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("target/idl")).unwrap();
+        let mut anchor_toml = "[programs.localnet]\n".to_string();
+        for (program, idl) in idls {
+            fs::write(root.join(format!("target/idl/{}.json", program)), idl).unwrap();
+            anchor_toml.push_str(&format!(
+                "{} = \"Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw\"\n",
+                program
+            ));
+        }
+        AnchorWorkspace::parse(root, &anchor_toml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let workspace = workspace("parse-error", &[("broken", "{ \"name\": ")]);
+        let error = workspace
+            .generate_crates("localnet", &workspace.root.join("crates"))
+            .unwrap_err();
+        match &error {
+            WorkspaceError::Parse { path, .. } => {
+                assert_eq!(path, &workspace.root.join("target/idl/broken.json"))
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+        assert!(error.to_string().starts_with("could not parse "));
+    }
+
+    #[test]
+    fn test_new_idl() {
+        let idl: Idl = serde_json::from_str(GOVERN_IDL).unwrap();
+        let new_idl = legacy_idl_to_new(&idl, Some("Govz1VyoyLD5BL6CSCxUJLVLsQHRwjfFj1prNsdNg5Jw"))
+            .unwrap()
+            .to_string();
+        let workspace = workspace("new-idl", &[("govern", &new_idl)]);
+        let out_dir = workspace.root.join("crates");
+        let generated = workspace.generate_crates("localnet", &out_dir).unwrap();
+        assert_eq!(generated.crates, vec![out_dir.join("govern-cpi")]);
+    }
+
+    #[test]
+    fn test_shared_types_owner() {
+        let workspace = workspace(
+            "shared-types",
+            &[("governor", GOVERN_IDL), ("another_governor", GOVERN_IDL)],
+        );
+        let out_dir = workspace.root.join("crates");
+        let generated = workspace.generate_crates("localnet", &out_dir).unwrap();
+        assert!(generated.mismatches.is_empty());
+
+        // `another_governor` comes first by name, so it generates the shared types
+        let owner = fs::read_to_string(out_dir.join("another-governor-cpi/src/lib.rs")).unwrap();
+        assert!(!owner.contains("extern_types"));
+        let cargo_toml = fs::read_to_string(out_dir.join("governor-cpi/Cargo.toml")).unwrap();
+        assert!(
+            cargo_toml.contains("another-governor-cpi = { path = \"../another-governor-cpi\" }")
+        );
+        let lib_rs = fs::read_to_string(out_dir.join("governor-cpi/src/lib.rs")).unwrap();
+        assert!(lib_rs.contains(
+            "GovernanceParameters = \"another_governor_cpi::typedefs::GovernanceParameters\","
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use govern_cpi::errors::{self, Error, ErrorCode, LoggedErrorOrigin};
+
+fn fixture(contents: &str) -> Vec<String> {
+    contents.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn test_parse_logs() {
+    let logs = fixture(include_str!("fixtures/set_governance_params_cpi.log"));
+    let error = errors::parse_logs(&govern_cpi::ID, &logs).unwrap();
+    assert_eq!(error.code, 6004);
+    assert!(matches!(
+        error.error,
+        Some(Error::Program(ErrorCode::ProposalNotActive))
+    ));
+    assert_eq!(error.name.as_deref(), Some("ProposalNotActive"));
+    assert_eq!(
+        error.message.as_deref(),
+        Some("The proposal must be active.")
+    );
+    assert_eq!(error.origin, None);
+}
+
+#[test]
+fn test_parse_logs_without_anchor_error() {
+    let logs = fixture(include_str!("fixtures/set_governance_params_cpi.log"))
+        .into_iter()
+        .filter(|log| !log.contains("AnchorError"))
+        .collect::<Vec<_>>();
+    let error = errors::parse_logs(&govern_cpi::ID, &logs).unwrap();
+    assert_eq!(error.code, 6004);
+    assert!(matches!(
+        error.error,
+        Some(Error::Program(ErrorCode::ProposalNotActive))
+    ));
+    assert_eq!(error.name, None);
+    assert_eq!(error.message, None);
+}
+
+#[test]
+fn test_parse_logs_succeeded() {
+    let logs = fixture(include_str!("fixtures/create_proposal.log"));
+    assert!(errors::parse_logs(&govern_cpi::ID, &logs).is_none());
+}
+
+#[test]
+fn test_parse_anchor_log_origin() {
+    let error = errors::LoggedError::from_anchor_log(
+        "Program log: AnchorError thrown in programs/govern/src/lib.rs:42. Error Code: ProposalNotDraft. Error Number: 6003. Error Message: The proposal must be a draft.",
+    )
+    .unwrap();
+    assert_eq!(
+        error.origin,
+        Some(LoggedErrorOrigin::Source {
+            file: "programs/govern/src/lib.rs".to_string(),
+            line: 42,
+        })
+    );
+
+    let error = errors::LoggedError::from_anchor_log(
+        "Program log: AnchorError caused by account: proposal. Error Code: ConstraintMut. Error Number: 2000. Error Message: A mut constraint was violated.",
+    )
+    .unwrap();
+    assert_eq!(
+        error.origin,
+        Some(LoggedErrorOrigin::Account("proposal".to_string()))
+    );
+    assert!(matches!(
+        error.error,
+        Some(Error::Anchor(anchor_lang::error::ErrorCode::ConstraintMut))
+    ));
+}
+
+#[test]
+fn test_from_program_error() {
+    let error = Error::from_program_error(&ProgramError::Custom(6004)).unwrap();
+    assert_eq!(error.code(), 6004);
+    assert_eq!(error.name(), "ProposalNotActive");
+    assert_eq!(error.to_string(), "The proposal must be active.");
+
+    let error = Error::from_program_error(&ProgramError::Custom(2000)).unwrap();
+    assert_eq!(error.name(), "ConstraintMut");
+
+    assert!(Error::from_program_error(&ProgramError::Custom(1)).is_none());
+    assert!(Error::from_program_error(&ProgramError::InvalidArgument).is_none());
+}
+
+#[test]
+fn test_from_anchor_error() {
+    let anchor_error: anchor_lang::error::Error = ErrorCode::GovernorNotFound.into();
+    let error = Error::from_anchor_error(&anchor_error).unwrap();
+    assert!(matches!(error, Error::Program(ErrorCode::GovernorNotFound)));
+
+    let anchor_error: anchor_lang::error::Error = ProgramError::Custom(6000).into();
+    let error = Error::from_anchor_error(&anchor_error).unwrap();
+    assert!(matches!(error, Error::Program(ErrorCode::InvalidVoteSide)));
+}
@@ -0,0 +1,79 @@
+use anchor_lang::{prelude::*, Discriminator};
+use govern_cpi::events::{self, Event, ProposalCreateEvent};
+
+fn fixture(contents: &str) -> Vec<String> {
+    contents.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn test_parse_logs() {
+    let logs = fixture(include_str!("fixtures/create_proposal.log"));
+    let events = events::parse_logs(&govern_cpi::ID, &logs);
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        Event::ProposalCreateEvent(event) => {
+            assert_eq!(event.governor, Pubkey::new_from_array([1; 32]));
+            assert_eq!(event.proposal, Pubkey::new_from_array([2; 32]));
+            assert_eq!(event.index, 7);
+            assert!(event.instructions.is_empty());
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn test_parse_logs_ignores_other_programs() {
+    let logs = fixture(include_str!("fixtures/set_governance_params_cpi.log"));
+    let events = events::parse_logs(&govern_cpi::ID, &logs);
+    assert_eq!(events.len(), 2);
+    match &events[0] {
+        Event::GovernorSetParamsEvent(event) => {
+            assert_eq!(event.params.voting_delay, 1);
+            assert_eq!(event.params.timelock_delay_seconds, 4);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    match &events[1] {
+        Event::VoteSetEvent(event) => {
+            assert_eq!(event.side, 1);
+            assert_eq!(event.weight, 500);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    // only the data logged directly by the smart wallet is attributed to it
+    let smart_wallet = "GokivDYuQXPZCWRkwMhdH2h91KpDQXBEmpgBgs55bnpH"
+        .parse::<Pubkey>()
+        .unwrap();
+    let events = events::parse_logs(&smart_wallet, &logs);
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| matches!(event, Event::VoteSetEvent(_))));
+}
+
+#[test]
+fn test_parse_cpi_events() {
+    let event = ProposalCreateEvent {
+        governor: Pubkey::new_unique(),
+        proposal: Pubkey::new_unique(),
+        index: 3,
+        instructions: vec![],
+    };
+    let mut data = anchor_lang::event::EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(&ProposalCreateEvent::DISCRIMINATOR);
+    data.extend(event.try_to_vec().unwrap());
+
+    let other_program = Pubkey::new_unique();
+    let instructions = [
+        (&govern_cpi::ID, data.as_slice()),
+        (&other_program, data.as_slice()),
+        (&govern_cpi::ID, &data[8..]),
+    ];
+    let events = events::parse_cpi_events(&govern_cpi::ID, instructions);
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        Event::ProposalCreateEvent(decoded) => assert_eq!(decoded.proposal, event.proposal),
+        event => panic!("unexpected event {:?}", event),
+    }
+}
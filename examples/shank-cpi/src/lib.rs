@@ -0,0 +1,3 @@
+anchor_gen::generate_cpi_crate!("idl.json");
+
+declare_id!("FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ");
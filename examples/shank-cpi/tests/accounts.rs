@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use shank_cpi::{Counter, Key};
+
+fn counter() -> Counter {
+    Counter {
+        key: Key::Counter,
+        authority: Pubkey::new_unique(),
+        count: 42,
+        label: "counter".to_string(),
+    }
+}
+
+#[test]
+fn test_account_has_no_discriminator() {
+    let counter = counter();
+    let mut data = vec![];
+    counter.try_serialize(&mut data).unwrap();
+    assert_eq!(data, counter.try_to_vec().unwrap());
+    assert_eq!(data[0], Key::Counter as u8);
+}
+
+#[test]
+fn test_decode_padded_account() {
+    let counter = counter();
+    let mut data = counter.try_to_vec().unwrap();
+    data.resize(data.len() + 64, 0);
+    let decoded = Counter::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(decoded.authority, counter.authority);
+    assert_eq!(decoded.count, 42);
+    assert_eq!(decoded.label, "counter");
+}
+
+#[test]
+fn test_decode_invalid_account() {
+    let error = Counter::try_deserialize(&mut [1u8, 2, 3].as_slice())
+        .err()
+        .unwrap();
+    assert_eq!(
+        error,
+        error!(anchor_lang::error::ErrorCode::AccountDidNotDeserialize)
+    );
+}
+
+#[test]
+fn test_account_owner() {
+    assert_eq!(Counter::owner(), shank_cpi::ID);
+}
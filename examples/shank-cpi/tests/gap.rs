@@ -0,0 +1,13 @@
+//! Instructions whose discriminants are not their positions, e.g. after a deprecated instruction
+//! was removed from the IDL.
+anchor_gen::generate_cpi_interface!(idl_path = "tests/fixtures/gap.json");
+
+declare_id!("FejG4HoM6SfT5PmRxnMAui23wpXTq8TX6WZjQAwvXGVJ");
+
+#[test]
+fn test_instruction_data() {
+    assert_eq!(instruction::Initialize::DISCRIMINATOR, [0]);
+    assert_eq!(instruction::Increment::DISCRIMINATOR, [1]);
+    assert_eq!(instruction::Close::DISCRIMINATOR, [5]);
+    assert_eq!(instruction::Close {}.data(), [5]);
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use shank_cpi::{accounts, instruction, InitializeArgs};
+
+#[test]
+fn test_instruction_data() {
+    assert_eq!(instruction::Initialize::DISCRIMINATOR, [0]);
+    assert_eq!(instruction::Increment::DISCRIMINATOR, [1]);
+    assert_eq!(instruction::Close::DISCRIMINATOR, [2]);
+
+    assert_eq!(
+        instruction::Increment { amount: 5 }.data(),
+        [1, 5, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(instruction::Close {}.data(), [2]);
+    assert_eq!(
+        instruction::Initialize {
+            initialize_args: InitializeArgs {
+                label: "ab".to_string(),
+            },
+        }
+        .data(),
+        [0, 2, 0, 0, 0, b'a', b'b']
+    );
+}
+
+#[test]
+fn test_instruction_accounts() {
+    let counter = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let metas = accounts::Increment { counter, authority }.to_account_metas(None);
+    assert_eq!(
+        metas,
+        [
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(authority, true),
+        ]
+    );
+}
@@ -0,0 +1,39 @@
+//! Packed zero copy types follow the layout of the IDL.
+
+use std::ptr::addr_of;
+
+use whirlpools::{Tick, TickArray};
+
+/// Offset of a field of `value`, from its address.
+fn offset<T, F>(value: &T, field: *const F) -> usize {
+    field as usize - value as *const T as usize
+}
+
+#[test]
+fn test_packed_size() {
+    assert_eq!(std::mem::size_of::<Tick>(), 1 + 16 * 7);
+    assert_eq!(std::mem::size_of::<TickArray>(), 4 + 88 * 113 + 32);
+    assert_eq!(TickArray::LEN, 8 + 4 + 88 * 113 + 32);
+}
+
+#[test]
+fn test_packed_field_order() {
+    let tick = Tick::default();
+    assert_eq!(offset(&tick, addr_of!(tick.initialized)), 0);
+    assert_eq!(offset(&tick, addr_of!(tick.liquidity_net)), 1);
+    assert_eq!(offset(&tick, addr_of!(tick.liquidity_gross)), 17);
+    assert_eq!(offset(&tick, addr_of!(tick.fee_growth_outside_a)), 33);
+    assert_eq!(offset(&tick, addr_of!(tick.fee_growth_outside_b)), 49);
+    assert_eq!(offset(&tick, addr_of!(tick.reward_growths_outside)), 65);
+
+    let tick_array = TickArray::default();
+    assert_eq!(
+        offset(&tick_array, addr_of!(tick_array.start_tick_index)),
+        0
+    );
+    assert_eq!(offset(&tick_array, addr_of!(tick_array.ticks)), 4);
+    assert_eq!(
+        offset(&tick_array, addr_of!(tick_array.whirlpool)),
+        4 + 88 * 113
+    );
+}